@@ -0,0 +1,516 @@
+use crate::awc::MetarDto;
+use crate::networks::AtisNetworkKind;
+use crate::settings::Settings;
+use crate::state::AppState;
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+pub const ALTIMETER_CHANGED_EVENT: &str = "altimeter-changed";
+pub const ATIS_CHANGED_EVENT: &str = "atis-changed";
+
+/// Hours of history used for the pressure tendency included with each change
+const PRESSURE_TREND_HOURS: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AltimeterObservation {
+    obs_time: DateTime<Utc>,
+    in_hg: f64,
+    hpa: f64,
+}
+
+impl AltimeterObservation {
+    fn from_metar(metar: &MetarDto) -> Option<Self> {
+        // Providers without an altimeter value report zero
+        (metar.altim > 0.0).then(|| Self {
+            obs_time: metar.obs_time,
+            in_hg: metar.altimeter_in_hg(),
+            hpa: metar.altimeter_hpa(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AltimeterChangedPayload {
+    pub station: String,
+    pub previous_obs_time: DateTime<Utc>,
+    pub obs_time: DateTime<Utc>,
+    pub previous_in_hg: f64,
+    pub previous_hpa: f64,
+    pub in_hg: f64,
+    pub hpa: f64,
+    pub delta_in_hg: f64,
+    pub delta_hpa: f64,
+    /// Change in hPa over the last `PRESSURE_TREND_HOURS` of recorded history, if any
+    pub trend_hpa: Option<f64>,
+}
+
+/// Remembers the last altimeter seen for each station so changes between consecutive
+/// observations can be reported
+pub struct AltimeterTracker {
+    latest: Mutex<BTreeMap<String, AltimeterObservation>>,
+    /// Smallest change in hPa that raises a notification, for stations configured with one
+    min_notify_change_hpa: Mutex<BTreeMap<String, f64>>,
+}
+
+impl AltimeterTracker {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            latest: Mutex::new(BTreeMap::new()),
+            min_notify_change_hpa: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn set_min_notify_change(&self, station: &str, min_change_hpa: Option<f64>) {
+        let mut thresholds = self.min_notify_change_hpa.lock().unwrap();
+        match min_change_hpa {
+            Some(hpa) => thresholds.insert(station.to_uppercase(), hpa),
+            None => thresholds.remove(&station.to_uppercase()),
+        };
+    }
+
+    fn should_notify(&self, change: &AltimeterChangedPayload) -> bool {
+        self.min_notify_change_hpa
+            .lock()
+            .unwrap()
+            .get(&change.station)
+            .is_none_or(|min| change.delta_hpa.abs() >= *min)
+    }
+
+    /// Returns the change from the previous observation of the same station, if the altimeter
+    /// differs. The first observation of a station is only recorded.
+    fn observe(&self, metar: &MetarDto) -> Option<AltimeterChangedPayload> {
+        let current = AltimeterObservation::from_metar(metar)?;
+        let station = metar.icao_id.to_uppercase();
+
+        let mut latest = self.latest.lock().unwrap();
+        let previous = match latest.get(&station) {
+            Some(p) if p.obs_time >= current.obs_time => return None,
+            p => p.copied(),
+        };
+        latest.insert(station.clone(), current);
+        drop(latest);
+
+        let previous = previous?;
+        let delta_in_hg = round_to(current.in_hg - previous.in_hg, 2);
+        let delta_hpa = round_to(current.hpa - previous.hpa, 1);
+        if delta_in_hg == 0.0 && delta_hpa == 0.0 {
+            return None;
+        }
+
+        Some(AltimeterChangedPayload {
+            station,
+            previous_obs_time: previous.obs_time,
+            obs_time: current.obs_time,
+            previous_in_hg: previous.in_hg,
+            previous_hpa: previous.hpa,
+            in_hg: current.in_hg,
+            hpa: current.hpa,
+            delta_in_hg,
+            delta_hpa,
+            trend_hpa: None,
+        })
+    }
+}
+
+impl Default for AltimeterTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Emits an `ALTIMETER_CHANGED_EVENT` for every station whose altimeter changed since its
+/// previous observation, and raises a desktop notification if enabled in settings
+pub fn check_altimeter_changes(app: &AppHandle, state: &AppState, metars: &[MetarDto]) {
+    let notify = state
+        .settings
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(Settings::notify_altimeter_changes);
+
+    for metar in metars {
+        let Some(mut change) = state.altimeter_tracker.observe(metar) else {
+            continue;
+        };
+        change.trend_hpa = pressure_trend(state, &change.station, change.hpa);
+        debug!("Altimeter changed: {change:?}");
+
+        if notify && state.altimeter_tracker.should_notify(&change) {
+            let body = format!(
+                "{:.2} inHg ({:+.2}) / {:.0} hPa ({:+.1})",
+                change.in_hg, change.delta_in_hg, change.hpa, change.delta_hpa
+            );
+            if let Err(e) = app
+                .notification()
+                .builder()
+                .title(format!("{} altimeter changed", change.station))
+                .body(body)
+                .show()
+            {
+                warn!("Could not show altimeter notification: {e:?}");
+            }
+        }
+
+        if let Err(e) = app.emit(ALTIMETER_CHANGED_EVENT, change) {
+            warn!("Could not emit altimeter change: {e:?}");
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AtisChangedPayload {
+    pub station: String,
+    pub previous_letter: String,
+    pub letter: String,
+    /// Items of the new ATIS text that were not in the previous one
+    pub added: Vec<String>,
+    /// Items of the previous ATIS text that are no longer present
+    pub removed: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AtisObservation {
+    letter: String,
+    texts: Vec<String>,
+}
+
+#[derive(Debug)]
+struct AtisTrackerState {
+    network: Option<AtisNetworkKind>,
+    latest: BTreeMap<String, AtisObservation>,
+}
+
+/// Remembers the last online ATIS letter and texts returned for each station
+pub struct AtisTracker {
+    state: Mutex<AtisTrackerState>,
+}
+
+impl AtisTracker {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(AtisTrackerState {
+                network: None,
+                latest: BTreeMap::new(),
+            }),
+        }
+    }
+
+    /// Returns the change from the previous lookup of the same station, if an online letter was
+    /// replaced by a different online letter. An ATIS going offline (`-`) keeps the last online
+    /// letter, so connecting or disconnecting is not a change. Arrival and departure letters
+    /// (`A/B`) are compared separately. Switching networks forgets all stations.
+    fn observe(
+        &self,
+        network: AtisNetworkKind,
+        station: &str,
+        letter: &str,
+        texts: &[String],
+    ) -> Option<AtisChangedPayload> {
+        let station = station.to_uppercase();
+        let mut state = self.state.lock().unwrap();
+        if state.network != Some(network) {
+            state.network = Some(network);
+            state.latest.clear();
+        }
+
+        let letters = letter.split('/').collect::<Vec<_>>();
+        let previous = state.latest.get(&station).cloned();
+        let previous_letters = previous
+            .as_ref()
+            .map(|p| p.letter.split('/').collect::<Vec<_>>())
+            .filter(|p| p.len() == letters.len());
+        let online = letters.iter().any(|l| *l != OFFLINE_LETTER);
+
+        let (merged, changed) = previous_letters.map_or_else(
+            || (letters.clone(), false),
+            |previous_letters| {
+                let merged = letters
+                    .iter()
+                    .zip(&previous_letters)
+                    .map(|(l, p)| if *l == OFFLINE_LETTER { *p } else { *l })
+                    .collect();
+                let changed = letters
+                    .iter()
+                    .zip(&previous_letters)
+                    .any(|(l, p)| *l != OFFLINE_LETTER && *p != OFFLINE_LETTER && l != p);
+                (merged, changed)
+            },
+        );
+        if online {
+            state.latest.insert(
+                station.clone(),
+                AtisObservation {
+                    letter: merged.join("/"),
+                    texts: texts.to_vec(),
+                },
+            );
+        }
+        drop(state);
+
+        let previous = previous.filter(|_| changed)?;
+        let previous_items = atis_items(&previous.texts);
+        let current_items = atis_items(texts);
+        Some(AtisChangedPayload {
+            station,
+            previous_letter: previous.letter,
+            letter: letter.to_string(),
+            added: current_items
+                .iter()
+                .filter(|i| !previous_items.contains(i))
+                .map(ToString::to_string)
+                .collect(),
+            removed: previous_items
+                .iter()
+                .filter(|i| !current_items.contains(i))
+                .map(ToString::to_string)
+                .collect(),
+        })
+    }
+}
+
+impl Default for AtisTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Letter returned for a station without an ATIS online
+const OFFLINE_LETTER: &str = "-";
+
+/// ATIS broadcasts are a series of period-separated items, e.g. runways in use or a NOTAM
+fn atis_items(texts: &[String]) -> Vec<&str> {
+    texts
+        .iter()
+        .flat_map(|t| t.split('.'))
+        .map(str::trim)
+        .filter(|i| !i.is_empty())
+        .collect()
+}
+
+/// Emits an `ATIS_CHANGED_EVENT` if the letter for the station changed since its previous lookup.
+/// Whether that plays a sound or raises a notification is up to the profile loaded in the UI.
+pub fn check_atis_change(
+    app: &AppHandle,
+    state: &AppState,
+    network: AtisNetworkKind,
+    station: &str,
+    letter: &str,
+    texts: &[String],
+) {
+    let Some(change) = state.atis_tracker.observe(network, station, letter, texts) else {
+        return;
+    };
+
+    debug!(
+        "ATIS for {} changed from {} to {}",
+        change.station, change.previous_letter, change.letter
+    );
+    if let Err(e) = app.emit(ATIS_CHANGED_EVENT, change) {
+        warn!("Could not emit ATIS change: {e:?}");
+    }
+}
+
+fn pressure_trend(state: &AppState, station: &str, current_hpa: f64) -> Option<f64> {
+    state
+        .metar_history
+        .query(station, PRESSURE_TREND_HOURS)
+        .first()
+        .filter(|m| m.altim > 0.0)
+        .map(|m| round_to(current_hpa - m.altimeter_hpa(), 1))
+}
+
+fn round_to(value: f64, decimals: i32) -> f64 {
+    let factor = 10f64.powi(decimals);
+    (value * factor).round() / factor
+}
+
+#[cfg(test)]
+// Deltas are rounded, so exact comparisons are meaningful
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn texts(text: &str) -> Vec<String> {
+        vec![text.to_string()]
+    }
+
+    fn letters(tracker: &AtisTracker, network: AtisNetworkKind, letters: &[&str]) -> Vec<String> {
+        letters
+            .iter()
+            .filter_map(|l| tracker.observe(network, "ksfo", l, &texts(l)))
+            .map(|c| format!("{}>{}", c.previous_letter, c.letter))
+            .collect()
+    }
+
+    fn metar(raw: &str) -> MetarDto {
+        crate::providers::metar_dto_from_raw(
+            raw,
+            Utc.with_ymd_and_hms(2024, 10, 16, 13, 0, 0).unwrap(),
+        )
+        .unwrap()
+    }
+
+    fn deltas(change: &AltimeterChangedPayload) -> (f64, f64) {
+        (change.delta_in_hg, change.delta_hpa)
+    }
+
+    #[test]
+    fn records_first_and_unchanged_altimeters_silently() {
+        let tracker = AltimeterTracker::new();
+        assert!(tracker
+            .observe(&metar("KSFO 161156Z 28012KT 10SM FEW010 16/12 A2992"))
+            .is_none());
+        assert!(tracker
+            .observe(&metar("KSFO 161256Z 29015KT 10SM FEW010 17/12 A2992"))
+            .is_none());
+        // Other stations are tracked separately
+        assert!(tracker
+            .observe(&metar("KOAK 161256Z 29015KT 10SM FEW010 17/12 A2995"))
+            .is_none());
+    }
+
+    #[test]
+    fn reports_altimeter_changes_in_both_units() {
+        let tracker = AltimeterTracker::new();
+        tracker.observe(&metar("KSFO 161156Z 28012KT 10SM FEW010 16/12 A2992"));
+        let change = tracker
+            .observe(&metar("ksfo 161256Z 28012KT 10SM FEW010 16/12 A2995"))
+            .unwrap();
+        assert_eq!(change.station, "KSFO");
+        assert_eq!(
+            change.previous_obs_time,
+            Utc.with_ymd_and_hms(2024, 10, 16, 11, 56, 0).unwrap()
+        );
+        assert_eq!(
+            change.obs_time,
+            Utc.with_ymd_and_hms(2024, 10, 16, 12, 56, 0).unwrap()
+        );
+        assert_eq!((change.previous_in_hg, change.in_hg), (29.92, 29.95));
+        // 1013.2 to 1014.2 hPa
+        assert_eq!(deltas(&change), (0.03, 1.0));
+
+        let tracker = AltimeterTracker::new();
+        tracker.observe(&metar("EGLL 161150Z 25014KT 9999 BKN015 14/08 Q1015"));
+        let change = tracker
+            .observe(&metar("EGLL 161220Z 25014KT 9999 BKN015 14/08 Q1013"))
+            .unwrap();
+        // 29.97 to 29.91 inHg
+        assert_eq!(deltas(&change), (-0.06, -2.0));
+    }
+
+    #[test]
+    fn ignores_observations_older_than_the_latest() {
+        let tracker = AltimeterTracker::new();
+        tracker.observe(&metar("KSFO 161256Z 28012KT 10SM FEW010 16/12 A2992"));
+        assert!(tracker
+            .observe(&metar("KSFO 161156Z 28012KT 10SM FEW010 16/12 A2989"))
+            .is_none());
+        assert!(tracker
+            .observe(&metar("KSFO 161256Z 28012KT 10SM FEW010 16/12 A2989"))
+            .is_none());
+
+        // The out-of-order observation did not replace the latest one
+        let change = tracker
+            .observe(&metar("KSFO 161356Z 28012KT 10SM FEW010 16/12 A2993"))
+            .unwrap();
+        assert_eq!(change.previous_in_hg, 29.92);
+    }
+
+    #[test]
+    fn suppresses_notifications_below_minimum_change() {
+        let tracker = AltimeterTracker::new();
+        tracker.observe(&metar("KSFO 161156Z 28012KT 10SM FEW010 16/12 A2992"));
+        // 0.3 hPa
+        let change = tracker
+            .observe(&metar("KSFO 161256Z 28012KT 10SM FEW010 16/12 A2993"))
+            .unwrap();
+        assert_eq!(deltas(&change), (0.01, 0.3));
+        assert!(tracker.should_notify(&change));
+
+        tracker.set_min_notify_change("ksfo", Some(0.5));
+        assert!(!tracker.should_notify(&change));
+        tracker.set_min_notify_change("KSFO", Some(0.3));
+        assert!(tracker.should_notify(&change));
+        tracker.set_min_notify_change("KSFO", None);
+        assert!(tracker.should_notify(&change));
+    }
+
+    #[test]
+    fn alerts_when_online_letter_changes() {
+        let tracker = AtisTracker::new();
+        assert!(tracker
+            .observe(
+                AtisNetworkKind::Vatsim,
+                "ksfo",
+                "A",
+                &texts("INFO A. RWY 28L. NOTAMS")
+            )
+            .is_none());
+
+        let change = tracker
+            .observe(
+                AtisNetworkKind::Vatsim,
+                "KSFO",
+                "B",
+                &texts("INFO B. RWY 28R. NOTAMS"),
+            )
+            .unwrap();
+        assert_eq!(
+            change,
+            AtisChangedPayload {
+                station: "KSFO".to_string(),
+                previous_letter: "A".to_string(),
+                letter: "B".to_string(),
+                added: vec!["INFO B".to_string(), "RWY 28R".to_string()],
+                removed: vec!["INFO A".to_string(), "RWY 28L".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn ignores_atis_connecting_and_disconnecting() {
+        let tracker = AtisTracker::new();
+        assert!(letters(
+            &tracker,
+            AtisNetworkKind::Vatsim,
+            &["-", "A", "A", "-", "-", "A"]
+        )
+        .is_empty());
+        assert_eq!(
+            letters(&tracker, AtisNetworkKind::Vatsim, &["-", "B"]),
+            ["A>B"]
+        );
+    }
+
+    #[test]
+    fn compares_arrival_and_departure_letters_separately() {
+        let tracker = AtisTracker::new();
+        assert_eq!(
+            letters(
+                &tracker,
+                AtisNetworkKind::Vatsim,
+                &["A/-", "A/K", "-/K", "B/K", "B/-", "B/L", "B"]
+            ),
+            ["A/K>B/K", "B/K>B/L"]
+        );
+    }
+
+    #[test]
+    fn forgets_letters_when_network_changes() {
+        let tracker = AtisTracker::new();
+        assert!(letters(&tracker, AtisNetworkKind::Vatsim, &["A"]).is_empty());
+        assert!(letters(&tracker, AtisNetworkKind::Ivao, &["D"]).is_empty());
+        assert!(letters(&tracker, AtisNetworkKind::Vatsim, &["B"]).is_empty());
+        assert_eq!(letters(&tracker, AtisNetworkKind::Vatsim, &["C"]), ["B>C"]);
+    }
+}
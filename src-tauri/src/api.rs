@@ -0,0 +1,408 @@
+use crate::state::AppState;
+use crate::{FetchAtisResponse, FetchMetarResponse};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::Response;
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use log::{debug, error, info, trace};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+use tokio::net::TcpListener;
+use tokio::sync::Notify;
+
+/// Latest data shown for a station in the window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StationSnapshot {
+    pub station: String,
+    pub metar: Option<FetchMetarResponse>,
+    pub atis: Option<FetchAtisResponse>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(skip)]
+    version: u64,
+}
+
+/// Sent over the WebSocket when a station is no longer shown in the window
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StationRemoved {
+    pub station: String,
+    pub removed: bool,
+}
+
+struct StationDataInner {
+    stations: BTreeMap<String, StationSnapshot>,
+    /// Version at which each station was removed
+    removed: BTreeMap<String, u64>,
+    version: u64,
+}
+
+/// Keeps the METAR and ATIS last fetched for each station shown in the window, for the local API
+pub struct StationData {
+    inner: Mutex<StationDataInner>,
+    changed: Notify,
+}
+
+impl StationData {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            inner: Mutex::new(StationDataInner {
+                stations: BTreeMap::new(),
+                removed: BTreeMap::new(),
+                version: 0,
+            }),
+            changed: Notify::const_new(),
+        }
+    }
+
+    pub fn update_metars(&self, metars: &[FetchMetarResponse]) {
+        for metar in metars {
+            self.update(&metar.metar.icao_id, |s| {
+                s.metar = Some(metar.clone());
+            });
+        }
+    }
+
+    pub fn update_atis(&self, station: &str, atis: &FetchAtisResponse) {
+        self.update(station, |s| {
+            // The age changes on every lookup and would otherwise push an update each time
+            let unchanged = s.atis.as_ref().is_some_and(|a| {
+                FetchAtisResponse {
+                    data_age_secs: atis.data_age_secs,
+                    ..a.clone()
+                } == *atis
+            });
+            if !unchanged {
+                s.atis = Some(atis.clone());
+            }
+        });
+    }
+
+    pub fn remove(&self, station: &str) {
+        let station = station.to_uppercase();
+        let mut inner = self.inner.lock().unwrap();
+        if inner.stations.remove(&station).is_none() {
+            return;
+        }
+        inner.version += 1;
+        let version = inner.version;
+        inner.removed.insert(station, version);
+        drop(inner);
+
+        self.changed.notify_waiters();
+    }
+
+    fn update(&self, station: &str, f: impl FnOnce(&mut StationSnapshot)) {
+        let station = station.to_uppercase();
+        let mut inner = self.inner.lock().unwrap();
+        let next_version = inner.version + 1;
+        let snapshot = inner
+            .stations
+            .entry(station.clone())
+            .or_insert_with(|| StationSnapshot {
+                station,
+                metar: None,
+                atis: None,
+                updated_at: Utc::now(),
+                version: 0,
+            });
+
+        let previous = snapshot.clone();
+        f(snapshot);
+        if *snapshot == previous {
+            return;
+        }
+        snapshot.updated_at = Utc::now();
+        snapshot.version = next_version;
+        let station = snapshot.station.clone();
+        inner.removed.remove(&station);
+        inner.version = next_version;
+        drop(inner);
+
+        self.changed.notify_waiters();
+    }
+
+    fn snapshots(&self) -> Vec<StationSnapshot> {
+        self.inner
+            .lock()
+            .unwrap()
+            .stations
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    fn snapshot(&self, station: &str) -> Option<StationSnapshot> {
+        self.inner
+            .lock()
+            .unwrap()
+            .stations
+            .get(&station.to_uppercase())
+            .cloned()
+    }
+
+    /// Snapshots changed and stations removed after `version`, and the version to pass next time.
+    /// Removals are not reported from version 0 as the caller has not seen those stations yet.
+    fn changed_since(&self, version: u64) -> (u64, Vec<StationSnapshot>, Vec<StationRemoved>) {
+        let inner = self.inner.lock().unwrap();
+        let changed = inner
+            .stations
+            .values()
+            .filter(|s| s.version > version)
+            .cloned()
+            .collect();
+        let removed = inner
+            .removed
+            .iter()
+            .filter(|(_, &removed_at)| version > 0 && removed_at > version)
+            .map(|(station, _)| StationRemoved {
+                station: station.clone(),
+                removed: true,
+            })
+            .collect();
+        (inner.version, changed, removed)
+    }
+}
+
+impl Default for StationData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Serves `StationData` over HTTP and WebSocket if enabled in settings. The bind address is read
+/// once at startup.
+pub async fn run_api_server(app: AppHandle) {
+    let Some(state) = app.try_state::<Arc<AppState>>().map(|s| Arc::clone(&s)) else {
+        error!("Could not get app state, local API not started");
+        return;
+    };
+    let address = state
+        .settings
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|s| s.api_address());
+    let Some((host, port)) = address else {
+        debug!("Local API disabled");
+        return;
+    };
+
+    let listener = match TcpListener::bind((host.as_str(), port)).await {
+        Ok(l) => l,
+        Err(e) => {
+            error!("Could not bind local API to {host}:{port}: {e:?}");
+            return;
+        }
+    };
+    info!("Local API listening on {host}:{port}");
+
+    if let Err(e) = axum::serve(listener, router(state)).await {
+        error!("Local API stopped: {e:?}");
+    }
+}
+
+fn router(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/stations", get(list_stations))
+        .route("/stations/:id", get(get_station))
+        .route("/ws", get(subscribe))
+        .with_state(state)
+}
+
+async fn list_stations(State(state): State<Arc<AppState>>) -> Json<Vec<StationSnapshot>> {
+    Json(state.station_data.snapshots())
+}
+
+async fn get_station(
+    Path(id): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<StationSnapshot>, StatusCode> {
+    state
+        .station_data
+        .snapshot(&id)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn subscribe(ws: WebSocketUpgrade, State(state): State<Arc<AppState>>) -> Response {
+    ws.on_upgrade(move |socket| push_updates(socket, state))
+}
+
+/// Sends every station on connect, then each station as it changes or is removed, one JSON object
+/// per message
+async fn push_updates(mut socket: WebSocket, state: Arc<AppState>) {
+    let mut sent_version = 0;
+    loop {
+        // Registered before reading so a change in between is not missed
+        let changed = state.station_data.changed.notified();
+        let (version, snapshots, removed) = state.station_data.changed_since(sent_version);
+        let messages = snapshots
+            .iter()
+            .map(serde_json::to_string)
+            .chain(removed.iter().map(serde_json::to_string));
+        for message in messages {
+            let Ok(text) = message else {
+                continue;
+            };
+            if socket.send(Message::Text(text)).await.is_err() {
+                trace!("Local API WebSocket client disconnected");
+                return;
+            }
+        }
+        sent_version = version;
+
+        tokio::select! {
+            () = changed => {},
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_)) | Err(_)) | None => {
+                    trace!("Local API WebSocket client disconnected");
+                    return;
+                }
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight_category::FlightCategoryThresholds;
+    use chrono::TimeZone;
+    use serde_json::Value;
+
+    const KSFO: &str = "KSFO 161256Z 28012KT 10SM FEW010 16/12 A2992";
+
+    fn metar(raw: &str) -> FetchMetarResponse {
+        let metar = crate::providers::metar_dto_from_raw(
+            raw,
+            Utc.with_ymd_and_hms(2024, 10, 16, 13, 0, 0).unwrap(),
+        )
+        .unwrap();
+        FetchMetarResponse::new(metar, &FlightCategoryThresholds::default())
+    }
+
+    fn atis(letter: &str, data_age_secs: i64) -> FetchAtisResponse {
+        FetchAtisResponse {
+            letter: letter.to_string(),
+            texts: vec![format!("OAKLAND INFO {letter}")],
+            decoded: vec![],
+            runway_summary: None,
+            data_age_secs: Some(data_age_secs),
+        }
+    }
+
+    fn stations(snapshots: &[StationSnapshot]) -> Vec<&str> {
+        snapshots.iter().map(|s| s.station.as_str()).collect()
+    }
+
+    fn removed(station: &str) -> StationRemoved {
+        StationRemoved {
+            station: station.to_string(),
+            removed: true,
+        }
+    }
+
+    #[test]
+    fn versions_only_changed_stations() {
+        let data = StationData::new();
+        assert_eq!(data.changed_since(0), (0, vec![], vec![]));
+
+        data.update_metars(&[metar(KSFO)]);
+        data.update_atis("koak", &atis("A", 10));
+        let (version, changed, _) = data.changed_since(0);
+        assert_eq!((version, stations(&changed)), (2, vec!["KOAK", "KSFO"]));
+        assert_eq!(data.changed_since(2), (2, vec![], vec![]));
+
+        // Same METAR, and the same ATIS read from a newer feed
+        data.update_metars(&[metar(KSFO)]);
+        data.update_atis("KOAK", &atis("A", 20));
+        assert_eq!(data.changed_since(2), (2, vec![], vec![]));
+
+        data.update_atis("KOAK", &atis("B", 20));
+        let (version, changed, _) = data.changed_since(2);
+        assert_eq!((version, stations(&changed)), (3, vec!["KOAK"]));
+        assert_eq!(changed[0].atis, Some(atis("B", 20)));
+
+        // Clients that saw nothing yet get every station
+        let (_, changed, _) = data.changed_since(0);
+        assert_eq!(stations(&changed), vec!["KOAK", "KSFO"]);
+    }
+
+    #[test]
+    fn reports_removed_stations() {
+        let data = StationData::new();
+        data.update_metars(&[metar(KSFO)]);
+        data.update_atis("KOAK", &atis("A", 10));
+
+        data.remove("ksfo");
+        assert_eq!(data.changed_since(2), (3, vec![], vec![removed("KSFO")]));
+        assert_eq!(data.changed_since(3), (3, vec![], vec![]));
+        let (version, changed, removals) = data.changed_since(0);
+        assert_eq!((version, stations(&changed)), (3, vec!["KOAK"]));
+        assert_eq!(removals, vec![]);
+        assert_eq!(data.snapshot("KSFO"), None);
+
+        // Removing a station not shown changes nothing
+        data.remove("EGLL");
+        assert_eq!(data.changed_since(3), (3, vec![], vec![]));
+
+        // Shown again, so no longer removed
+        data.update_metars(&[metar(KSFO)]);
+        let (version, changed, removals) = data.changed_since(2);
+        assert_eq!((version, stations(&changed)), (4, vec!["KSFO"]));
+        assert_eq!(removals, vec![]);
+    }
+
+    #[test]
+    fn serializes_removals() {
+        assert_eq!(
+            serde_json::to_value(removed("KSFO")).unwrap(),
+            serde_json::json!({ "station": "KSFO", "removed": true })
+        );
+    }
+
+    #[tokio::test]
+    async fn serves_station_snapshots() {
+        let state = Arc::new(AppState::new());
+        state.station_data.update_metars(&[metar(KSFO)]);
+        state.station_data.update_atis("KOAK", &atis("A", 10));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let router = router(Arc::clone(&state));
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        let get = |path: &str| reqwest::get(format!("http://{address}{path}"));
+
+        let stations: Value = get("/stations").await.unwrap().json().await.unwrap();
+        let stations = stations.as_array().unwrap();
+        assert_eq!(stations.len(), 2);
+        let koak = &stations[0];
+        assert_eq!(koak["station"], "KOAK");
+        assert_eq!(koak["metar"], Value::Null);
+        assert_eq!(koak["atis"]["letter"], "A");
+        assert_eq!(koak["atis"]["dataAgeSecs"], 10);
+        assert!(koak["updatedAt"].is_string());
+        assert!(koak.get("version").is_none());
+        let ksfo = &stations[1];
+        assert_eq!(ksfo["station"], "KSFO");
+        assert_eq!(ksfo["metar"]["metar"]["rawOb"], KSFO);
+        assert_eq!(ksfo["metar"]["altimeter"]["inHg"], 29.92);
+        assert_eq!(ksfo["metar"]["windString"], "28012KT");
+        assert_eq!(ksfo["atis"], Value::Null);
+
+        let response = get("/stations/ksfo").await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(response.json::<Value>().await.unwrap(), *ksfo);
+
+        let response = get("/stations/EGLL").await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,690 @@
+use crate::metar::{parse_wind, resolve_day_time, AltimeterSetting, SpeedUnit, Wind};
+use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+static ITEM_SEPARATOR_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\.(?:\s+|$)|\s+\.\s*").unwrap());
+static RUNWAY_IDENT_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d{1,2})([LCR])?$").unwrap());
+static ALTIMETER_KEYWORD_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(ALTIMETER|ALSTG|QNH)\s+(\d{2}\.\d{2}|\d{3,4})\b").unwrap());
+static ALTIMETER_GROUP_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b([AQ])(\d{4})\b").unwrap());
+static TRANSITION_LEVEL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b(?:TRANSITION LEVEL|TRANS LEVEL|TRL)\s+(?:FL\s*)?(\d{2,3})\b").unwrap()
+});
+static SPOKEN_WIND_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"\bWINDS?\s+(\d{3}|VRB|VARIABLE)(?:\s+(?:DEGREES|DEG))?(?:\s*/\s*|\s+AT\s+|\s+)(\d{1,3})(?:\s*(?:KNOTS|KTS|KT))?(?:\s+(?:GUSTS|GUSTING|GUST)(?:\s+TO)?\s+(\d{1,3}))?",
+    )
+    .unwrap()
+});
+static CALM_WIND_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\bWINDS?\s+CALM\b").unwrap());
+static OBSERVATION_TIME_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(\d{2})?(\d{2})(\d{2})Z\b").unwrap());
+
+/// Keywords that mark an item as notice-type information rather than routine ATIS content
+const NOTAM_KEYWORDS: &[&str] = &[
+    "NOTAM",
+    "NOTICE TO",
+    "CLOSED",
+    "CLSD",
+    "OUT OF SERVICE",
+    "OTS",
+    "U/S",
+    "UNSERVICEABLE",
+    "INOP",
+    "WIP",
+    "WORK IN PROGRESS",
+    "CONSTRUCTION",
+    "CRANE",
+    "BIRD",
+    "CAUTION",
+    "WARNING",
+];
+
+/// Information decoded from the text of a single ATIS broadcast
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AtisInfo {
+    pub letter: Option<char>,
+    pub arrival_runways: Vec<String>,
+    pub departure_runways: Vec<String>,
+    /// Approach types in use, e.g. `ILS` or `VISUAL`
+    pub approaches: Vec<String>,
+    pub altimeter: Option<AltimeterSetting>,
+    pub wind: Option<Wind>,
+    pub observation_time: Option<AtisObservationTime>,
+    pub transition_level: Option<u32>,
+    pub notams: Vec<String>,
+}
+
+impl AtisInfo {
+    const fn runways_for(&mut self, role: Role) -> &mut Vec<String> {
+        match role {
+            Role::Arrival => &mut self.arrival_runways,
+            Role::Departure => &mut self.departure_runways,
+        }
+    }
+
+    /// Runways in use in the form `ARR 27L/28R DEP 1L`, `None` if no runways were found
+    pub fn runway_summary(&self) -> Option<String> {
+        let parts = [
+            ("ARR", &self.arrival_runways),
+            ("DEP", &self.departure_runways),
+        ]
+        .into_iter()
+        .filter(|(_, runways)| !runways.is_empty())
+        .map(|(label, runways)| format!("{label} {}", runways.join("/")))
+        .collect::<Vec<_>>();
+
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join(" "))
+        }
+    }
+}
+
+/// Time of the observation an ATIS is based on, e.g. `1856Z` or `161856Z`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AtisObservationTime {
+    pub day: Option<u32>,
+    pub hour: u32,
+    pub minute: u32,
+}
+
+impl AtisObservationTime {
+    /// Full observation time, taking the missing parts from the nearest match to `reference`
+    pub fn resolve(&self, reference: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if let Some(day) = self.day {
+            return resolve_day_time(reference, day, self.hour, self.minute);
+        }
+
+        let same_day = reference
+            .date_naive()
+            .and_hms_opt(self.hour, self.minute, 0)?
+            .and_utc();
+        [
+            same_day - Duration::days(1),
+            same_day,
+            same_day + Duration::days(1),
+        ]
+        .into_iter()
+        .min_by_key(|t| (*t - reference).num_seconds().abs())
+    }
+}
+
+/// Runway summary across several ATIS, e.g. separate arrival and departure broadcasts
+pub fn combined_runway_summary(infos: &[AtisInfo]) -> Option<String> {
+    let mut combined = AtisInfo::default();
+    for info in infos {
+        for runway in &info.arrival_runways {
+            push_unique(&mut combined.arrival_runways, runway.clone());
+        }
+        for runway in &info.departure_runways {
+            push_unique(&mut combined.departure_runways, runway.clone());
+        }
+    }
+    combined.runway_summary()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Arrival,
+    Departure,
+}
+
+/// Decodes the lines of `Atis::text_atis`
+pub fn parse_atis_text(text_lines: &[String]) -> AtisInfo {
+    let joined = text_lines.join(" ").to_uppercase();
+    let mut info = AtisInfo {
+        letter: parse_code_from_text(text_lines),
+        altimeter: parse_altimeter(&joined),
+        wind: parse_atis_wind(&joined),
+        observation_time: parse_observation_time(&joined),
+        transition_level: TRANSITION_LEVEL_REGEX
+            .captures(&joined)
+            .and_then(|c| c[1].parse().ok()),
+        ..AtisInfo::default()
+    };
+
+    for item in ITEM_SEPARATOR_REGEX
+        .split(&joined)
+        .map(str::trim)
+        .filter(|i| !i.is_empty())
+    {
+        if is_notam_item(item) {
+            info.notams.push(item.to_string());
+            continue;
+        }
+
+        parse_runways_in_item(item, &mut info);
+        if is_approach_item(item) {
+            for approach in item.split_whitespace().filter_map(approach_type) {
+                push_unique(&mut info.approaches, approach.to_string());
+            }
+        }
+    }
+
+    info
+}
+
+/// Assigns runway idents to arrivals or departures based on the role words preceding them, or
+/// following them as in `RWY 27L AND 26R FOR LANDING`. Runways without a role word are used for
+/// both if the item says they are in use.
+fn parse_runways_in_item(item: &str, info: &mut AtisInfo) {
+    let tokens = item
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '/' || c == '&')
+        .filter(|t| !t.is_empty())
+        .collect::<Vec<_>>();
+
+    let mut roles: Vec<Role> = vec![];
+    let mut assigned_since_role = false;
+    let mut unassigned = vec![];
+    // Runways just read without a role word, which a following role word applies to
+    let mut trailing: Vec<String> = vec![];
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let token = tokens[i];
+        if let Some(role) = role_for_word(token) {
+            if !trailing.is_empty() && !precedes_runways(&tokens[i + 1..]) {
+                unassigned.retain(|r| !trailing.contains(r));
+                for runway in &trailing {
+                    push_unique(info.runways_for(role), runway.clone());
+                }
+            } else {
+                trailing.clear();
+                if assigned_since_role {
+                    roles.clear();
+                    assigned_since_role = false;
+                }
+                if !roles.contains(&role) {
+                    roles.push(role);
+                }
+            }
+        } else if is_runway_keyword(token) {
+            let (runways, consumed) = collect_runway_idents(&tokens[i + 1..]);
+            trailing.clear();
+            for runway in runways {
+                if roles.is_empty() {
+                    push_unique(&mut unassigned, runway.clone());
+                    trailing.push(runway);
+                } else {
+                    for role in &roles {
+                        push_unique(info.runways_for(*role), runway.clone());
+                    }
+                }
+            }
+            assigned_since_role = !roles.is_empty();
+            i += consumed;
+        } else if !matches!(token, "FOR" | "AND" | "IN" | "USE") {
+            trailing.clear();
+        }
+        i += 1;
+    }
+
+    if !unassigned.is_empty() && (item.contains("IN USE") || item.contains("ACTIVE")) {
+        for runway in unassigned {
+            push_unique(&mut info.arrival_runways, runway.clone());
+            push_unique(&mut info.departure_runways, runway);
+        }
+    }
+}
+
+fn is_runway_keyword(word: &str) -> bool {
+    matches!(word, "RWY" | "RWYS" | "RUNWAY" | "RUNWAYS")
+}
+
+/// Whether a role word is followed by the runways it applies to, skipping further role words
+fn precedes_runways(tokens: &[&str]) -> bool {
+    tokens
+        .iter()
+        .find(|t| role_for_word(t).is_none() && **t != "AND")
+        .is_some_and(|t| is_runway_keyword(t))
+}
+
+/// Reads consecutive runway idents, returning them and the number of tokens consumed
+fn collect_runway_idents(tokens: &[&str]) -> (Vec<String>, usize) {
+    let mut runways: Vec<String> = vec![];
+    let mut consumed = 0;
+    // Whether the last ident is being spelled out digit by digit, e.g. `TWO SEVEN LEFT`
+    let mut spelling = false;
+
+    for &token in tokens {
+        if let Some(c) = RUNWAY_IDENT_REGEX.captures(token) {
+            if !c[1].parse::<u32>().is_ok_and(|n| (1..=36).contains(&n)) {
+                break;
+            }
+            runways.push(token.to_string());
+            spelling = false;
+        } else if let Some(digit) = spelled_digit(token) {
+            match runways.last_mut() {
+                Some(last) if spelling && last.len() == 1 => last.push(digit),
+                _ => runways.push(digit.to_string()),
+            }
+            spelling = true;
+        } else if let (Some(last), Some(side)) = (runways.last_mut(), spelled_side(token)) {
+            if last.ends_with(|c: char| c.is_ascii_digit()) {
+                last.push(side);
+                spelling = false;
+            } else {
+                break;
+            }
+        } else if runways.is_empty() && matches!(token, "IN" | "USE" | "ACTIVE") {
+            // e.g. `RUNWAY IN USE 25C`
+        } else if token != "AND" || runways.is_empty() {
+            break;
+        }
+        consumed += 1;
+    }
+
+    (runways, consumed)
+}
+
+fn role_for_word(word: &str) -> Option<Role> {
+    match word {
+        "LDG" | "LNDG" | "LANDING" | "ARR" | "ARRIVAL" | "ARRIVALS" | "ARRIVING" | "INBOUND"
+        | "APCH" | "APCHS" | "APP" | "APPROACH" | "APPROACHES" | "APPS" => Some(Role::Arrival),
+        "DEP" | "DEPG" | "DEPTG" | "DEPARTING" | "DEPARTURE" | "DEPARTURES" | "TKOF"
+        | "TAKEOFF" | "TAKE-OFF" => Some(Role::Departure),
+        _ => approach_type(word).map(|_| Role::Arrival),
+    }
+}
+
+fn approach_type(word: &str) -> Option<&'static str> {
+    match word {
+        "ILS" => Some("ILS"),
+        "LOC" | "LOCALIZER" | "LOCALISER" => Some("LOC"),
+        "RNAV" => Some("RNAV"),
+        "RNP" => Some("RNP"),
+        "GPS" => Some("GPS"),
+        "VOR" => Some("VOR"),
+        "NDB" => Some("NDB"),
+        "GLS" => Some("GLS"),
+        "LDA" => Some("LDA"),
+        "SDF" => Some("SDF"),
+        "VISUAL" | "VISUALS" => Some("VISUAL"),
+        _ => None,
+    }
+}
+
+fn spelled_digit(word: &str) -> Option<char> {
+    match word {
+        "ZERO" => Some('0'),
+        "ONE" | "WUN" => Some('1'),
+        "TWO" | "TOO" => Some('2'),
+        "THREE" | "TREE" => Some('3'),
+        "FOUR" | "FOWER" => Some('4'),
+        "FIVE" | "FIFE" => Some('5'),
+        "SIX" => Some('6'),
+        "SEVEN" => Some('7'),
+        "EIGHT" | "AIT" => Some('8'),
+        "NINE" | "NINER" => Some('9'),
+        _ => None,
+    }
+}
+
+fn spelled_side(word: &str) -> Option<char> {
+    match word {
+        "LEFT" => Some('L'),
+        "CENTER" | "CENTRE" => Some('C'),
+        "RIGHT" => Some('R'),
+        _ => None,
+    }
+}
+
+fn is_approach_item(item: &str) -> bool {
+    item.split_whitespace().any(|w| {
+        matches!(
+            w,
+            "APCH" | "APCHS" | "APP" | "APPS" | "APPROACH" | "APPROACHES" | "EXPECT" | "EXP"
+        )
+    })
+}
+
+fn is_notam_item(item: &str) -> bool {
+    NOTAM_KEYWORDS.iter().any(|k| {
+        item.match_indices(k).any(|(start, _)| {
+            let before = item[..start].chars().next_back();
+            let after = item[start + k.len()..].chars().next();
+            !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+        })
+    })
+}
+
+/// Prefers an explicitly labelled altimeter or QNH, falling back to a METAR-style group
+fn parse_altimeter(text: &str) -> Option<AltimeterSetting> {
+    if let Some(c) = ALTIMETER_KEYWORD_REGEX.captures(text) {
+        let value = &c[2];
+        if &c[1] == "QNH" {
+            return value.parse().ok().map(AltimeterSetting::Hpa);
+        }
+        return if value.contains('.') {
+            value.parse().ok().map(AltimeterSetting::InHg)
+        } else if value.len() == 4 {
+            value
+                .parse::<f64>()
+                .ok()
+                .map(|v| AltimeterSetting::InHg(v / 100.0))
+        } else {
+            None
+        };
+    }
+
+    let c = ALTIMETER_GROUP_REGEX.captures(text)?;
+    let value = c[2].parse::<f64>().ok()?;
+    Some(if &c[1] == "A" {
+        AltimeterSetting::InHg(value / 100.0)
+    } else {
+        AltimeterSetting::Hpa(value)
+    })
+}
+
+/// Prefers a METAR-style wind group, falling back to a spoken form like `WIND 280 AT 12`
+fn parse_atis_wind(text: &str) -> Option<Wind> {
+    if let Some(wind) = text
+        .split_whitespace()
+        .map(|t| t.trim_end_matches([',', '.']))
+        .find_map(parse_wind)
+    {
+        return Some(wind);
+    }
+
+    let wind = if let Some(c) = SPOKEN_WIND_REGEX.captures(text) {
+        Wind {
+            direction: c[1].parse().ok(),
+            speed: c[2].parse().ok()?,
+            gust: c.get(3).and_then(|g| g.as_str().parse().ok()),
+            unit: SpeedUnit::Knots,
+            variable_from: None,
+            variable_to: None,
+        }
+    } else if CALM_WIND_REGEX.is_match(text) {
+        Wind {
+            direction: Some(0),
+            speed: 0,
+            gust: None,
+            unit: SpeedUnit::Knots,
+            variable_from: None,
+            variable_to: None,
+        }
+    } else {
+        return None;
+    };
+    Some(wind)
+}
+
+fn parse_observation_time(text: &str) -> Option<AtisObservationTime> {
+    OBSERVATION_TIME_REGEX.captures_iter(text).find_map(|c| {
+        let time = AtisObservationTime {
+            day: c.get(1).and_then(|d| d.as_str().parse().ok()),
+            hour: c[2].parse().ok()?,
+            minute: c[3].parse().ok()?,
+        };
+        let valid_day = time.day.is_none_or(|d| (1..=31).contains(&d));
+        (valid_day && time.hour < 24 && time.minute < 60).then_some(time)
+    })
+}
+
+fn push_unique(list: &mut Vec<String>, value: String) {
+    if !list.contains(&value) {
+        list.push(value);
+    }
+}
+
+pub fn parse_code_from_text(text_lines: &[String]) -> Option<char> {
+    static INFO_CHAR_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(?:INFO|INFORMATION) ([A-Z])(?:\W|$)").unwrap());
+    static INFO_WORD_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(?:INFO|INFORMATION) ([A-Z]+)(?:\W|$)").unwrap());
+
+    let joined = text_lines.join(" ");
+    INFO_CHAR_REGEX.captures(&joined).map_or_else(
+        || {
+            INFO_WORD_REGEX
+                .captures(&joined)
+                .and_then(|c| nato_to_char(&c[1]))
+        },
+        |c| c[1].chars().next(),
+    )
+}
+
+fn nato_to_char(str: &str) -> Option<char> {
+    match str.to_uppercase().as_str() {
+        "ALPHA" => Some('A'),
+        "BRAVO" => Some('B'),
+        "CHARLIE" => Some('C'),
+        "DELTA" => Some('D'),
+        "ECHO" => Some('E'),
+        "FOXTROT" => Some('F'),
+        "GOLF" => Some('G'),
+        "HOTEL" => Some('H'),
+        "INDIA" => Some('I'),
+        "JULIET" => Some('J'),
+        "KILO" => Some('K'),
+        "LIMA" => Some('L'),
+        "MIKE" => Some('M'),
+        "NOVEMBER" => Some('N'),
+        "OSCAR" => Some('O'),
+        "PAPA" => Some('P'),
+        "QUEBEC" => Some('Q'),
+        "ROMEO" => Some('R'),
+        "SIERRA" => Some('S'),
+        "TANGO" => Some('T'),
+        "UNIFORM" => Some('U'),
+        "VICTOR" => Some('V'),
+        "WHISKEY" => Some('W'),
+        "XRAY" | "X-RAY" => Some('X'),
+        "YANKEE" => Some('Y'),
+        "ZULU" => Some('Z'),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const KSFO: &[&str] = &[
+        "SFO ATIS INFO C 1856Z. 28012G20KT 10SM FEW010 16/12 A2992 (TWO NINER NINER TWO).",
+        "SIMUL CHARTED VISUAL APCHS IN USE. LDG RWY 28L 28R DEP RWY 1L 1R.",
+        "NOTAMS... TWY C CLSD BTN TWY D AND TWY E. ADVS YOU HAVE INFO C.",
+    ];
+    const EDDF: &[&str] = &[
+        "FRANKFURT INFORMATION DELTA, MET REPORT TIME 1250Z. EXPECT ILS APPROACH.",
+        "RUNWAY IN USE 25C. TRANSITION LEVEL 70. WIND 240 DEGREES 8 KNOTS.",
+        "VISIBILITY 10 KM. FEW 3500 FT. TEMPERATURE 14 DEW POINT 8. QNH 1013. NOSIG.",
+    ];
+    const EGLL: &[&str] = &[
+        "THIS IS HEATHROW INFORMATION KILO, TIME 161250Z.",
+        "LANDING RUNWAY TWO SEVEN LEFT, DEPARTURES RUNWAY TWO SEVEN RIGHT.",
+        "SURFACE WIND 250 DEGREES 14 KNOTS. QNH 1009. TRANS LEVEL FL 75.",
+        "BIRD ACTIVITY IN THE VICINITY OF THE AERODROME.",
+    ];
+    const KJFK: &[&str] = &[
+        "JFK ATIS INFO Y 2251Z. 31015KT 10SM BKN250 M01/M12 A3015.",
+        "ILS RWY 4R APCH IN USE. DEPTG RWY 4L. ALTIMETER 3015.",
+    ];
+    const LFPG: &[&str] = &[
+        "CHARLES DE GAULLE INFORMATION ALPHA 0630Z.",
+        "ILS RWY 27L APCH IN USE. RWY 27L AND 26R FOR LANDING. TAKEOFF RWY 26R.",
+    ];
+
+    fn parse(text: &[&str]) -> AtisInfo {
+        parse_atis_text(&text.iter().map(ToString::to_string).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn decodes_letter_and_observation_time() {
+        for (text, letter, time) in [
+            (KSFO, 'C', (None, 18, 56)),
+            (EDDF, 'D', (None, 12, 50)),
+            (EGLL, 'K', (Some(16), 12, 50)),
+            (KJFK, 'Y', (None, 22, 51)),
+            (LFPG, 'A', (None, 6, 30)),
+        ] {
+            let info = parse(text);
+            assert_eq!(info.letter, Some(letter), "{text:?}");
+            assert_eq!(
+                info.observation_time,
+                Some(AtisObservationTime {
+                    day: time.0,
+                    hour: time.1,
+                    minute: time.2,
+                }),
+                "{text:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn assigns_runways_to_arrivals_and_departures() {
+        for (text, arrivals, departures) in [
+            (KSFO, vec!["28L", "28R"], vec!["1L", "1R"]),
+            (EDDF, vec!["25C"], vec!["25C"]),
+            (EGLL, vec!["27L"], vec!["27R"]),
+            (KJFK, vec!["4R"], vec!["4L"]),
+            (LFPG, vec!["27L", "26R"], vec!["26R"]),
+        ] {
+            let info = parse(text);
+            assert_eq!(info.arrival_runways, arrivals, "{text:?}");
+            assert_eq!(info.departure_runways, departures, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn reads_spelled_runway_idents() {
+        for (text, runways) in [
+            ("RUNWAY TWO SEVEN LEFT IN USE", vec!["27L"]),
+            (
+                "RUNWAYS TWO EIGHT LEFT AND TWO EIGHT RIGHT IN USE",
+                vec!["28L", "28R"],
+            ),
+            ("RUNWAY ZERO NINER IN USE", vec!["09"]),
+            ("RUNWAY 27 LEFT IN USE", vec!["27L"]),
+            ("RUNWAY 25 CENTRE IN USE", vec!["25C"]),
+        ] {
+            assert_eq!(parse(&[text]).arrival_runways, runways, "{text}");
+        }
+    }
+
+    #[test]
+    fn decodes_approaches() {
+        for (text, approaches) in [
+            (KSFO, vec!["VISUAL"]),
+            (EDDF, vec!["ILS"]),
+            (EGLL, vec![]),
+            (KJFK, vec!["ILS"]),
+            (LFPG, vec!["ILS"]),
+        ] {
+            assert_eq!(parse(text).approaches, approaches, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn decodes_altimeter_wind_and_transition_level() {
+        for (text, altimeter, wind, transition_level) in [
+            (
+                KSFO,
+                AltimeterSetting::InHg(29.92),
+                (Some(280), 12, Some(20)),
+                None,
+            ),
+            (
+                EDDF,
+                AltimeterSetting::Hpa(1013.0),
+                (Some(240), 8, None),
+                Some(70),
+            ),
+            (
+                EGLL,
+                AltimeterSetting::Hpa(1009.0),
+                (Some(250), 14, None),
+                Some(75),
+            ),
+            (
+                KJFK,
+                AltimeterSetting::InHg(30.15),
+                (Some(310), 15, None),
+                None,
+            ),
+        ] {
+            let info = parse(text);
+            assert_eq!(info.altimeter, Some(altimeter), "{text:?}");
+            assert_eq!(
+                info.wind.map(|w| (w.direction, w.speed, w.gust)),
+                Some(wind),
+                "{text:?}"
+            );
+            assert_eq!(info.transition_level, transition_level, "{text:?}");
+        }
+
+        assert_eq!(
+            parse(&["ALTIMETER 29.92"]).altimeter,
+            Some(AltimeterSetting::InHg(29.92))
+        );
+        assert_eq!(
+            parse(&["WIND CALM"]).wind.map(|w| (w.direction, w.speed)),
+            Some((Some(0), 0))
+        );
+        assert_eq!(parse(LFPG).altimeter, None);
+    }
+
+    #[test]
+    fn separates_notam_items() {
+        let info = parse(KSFO);
+        assert_eq!(info.notams, ["TWY C CLSD BTN TWY D AND TWY E"]);
+        assert_eq!(
+            parse(EGLL).notams,
+            ["BIRD ACTIVITY IN THE VICINITY OF THE AERODROME"]
+        );
+        assert!(parse(EDDF).notams.is_empty());
+
+        // Runways in a NOTAM item are not runways in use
+        let info = parse(&["RWY 28L IN USE. RWY 1R CLSD."]);
+        assert_eq!(info.arrival_runways, ["28L"]);
+        assert_eq!(info.notams, ["RWY 1R CLSD"]);
+        // Keywords only match whole words
+        assert!(parse(&["BIRDSEYE VIEW. RWY 28L IN USE"]).notams.is_empty());
+    }
+
+    #[test]
+    fn combines_runway_summaries() {
+        let info = |arrivals: &[&str], departures: &[&str]| AtisInfo {
+            arrival_runways: arrivals.iter().map(ToString::to_string).collect(),
+            departure_runways: departures.iter().map(ToString::to_string).collect(),
+            ..AtisInfo::default()
+        };
+
+        assert_eq!(
+            combined_runway_summary(&[info(&["27L", "28R"], &[]), info(&["27L"], &["1L"])]),
+            Some("ARR 27L/28R DEP 1L".to_string())
+        );
+        assert_eq!(
+            combined_runway_summary(&[parse(KSFO)]),
+            Some("ARR 28L/28R DEP 1L/1R".to_string())
+        );
+        assert_eq!(combined_runway_summary(&[info(&[], &[])]), None);
+    }
+
+    #[test]
+    fn resolves_observation_time_across_midnight() {
+        let reference = Utc.with_ymd_and_hms(2024, 10, 17, 0, 5, 0).unwrap();
+        let time = AtisObservationTime {
+            day: None,
+            hour: 23,
+            minute: 56,
+        };
+        assert_eq!(
+            time.resolve(reference),
+            Some(Utc.with_ymd_and_hms(2024, 10, 16, 23, 56, 0).unwrap())
+        );
+    }
+}
@@ -1,39 +1,117 @@
+use crate::metar::{parse_metar, MetarReport};
+use crate::providers::WeatherProvider;
+use crate::taf::{parse_taf, TafReport};
+use crate::utils;
 use anyhow::{anyhow, bail};
+use async_trait::async_trait;
 use chrono::serde::ts_seconds;
 use chrono::{DateTime, Utc};
 use flate2::read::GzDecoder;
-use log::debug;
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use log::{debug, warn};
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{Client, StatusCode};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Formatter;
 use std::io::Read;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
 
-const BASE_URL: &str = "https://aviationweather.gov/";
-const MBAR_TO_INHG_FACTOR: f64 = 0.02953;
+const DEFAULT_BASE_URL: &str = "https://aviationweather.gov";
+pub const MBAR_TO_INHG_FACTOR: f64 = 0.02953;
+
+/// Station table as persisted in `stations.json`, with the validators of the download it came
+/// from so refreshes can be conditional
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StationCache {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    stations: Vec<Station>,
+}
+
+#[derive(Default)]
+struct StationTable {
+    stations: HashMap<String, Station>,
+    faa_icao_lookup: HashMap<String, String>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl StationTable {
+    fn from_cache(cache: StationCache) -> Self {
+        let stations = cache
+            .stations
+            .into_iter()
+            .map(|s| (s.icao_id.to_uppercase(), s))
+            .collect::<HashMap<_, _>>();
+        let faa_icao_lookup = stations
+            .values()
+            .map(|s| (s.faa_id.to_uppercase(), s.icao_id.to_uppercase()))
+            .collect();
+
+        Self {
+            stations,
+            faa_icao_lookup,
+            etag: cache.etag,
+            last_modified: cache.last_modified,
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct AviationWeatherCenterApi {
     client: Client,
-    stations: Option<HashMap<String, Station>>,
-    faa_icao_lookup: Option<HashMap<String, String>>,
+    base_url: String,
+    stations: Arc<RwLock<Option<StationTable>>>,
+    stations_from_cache: bool,
 }
 
 impl AviationWeatherCenterApi {
-    pub async fn try_new() -> Result<Self, anyhow::Error> {
-        let mut new = Self {
-            client: Client::builder().build()?,
-            stations: None,
-            faa_icao_lookup: None,
-        };
+    /// Uses the station table cached on disk if there is one, so station lookups are available
+    /// without waiting on the download. Otherwise the table is downloaded before returning.
+    pub async fn try_new(base_url: Option<&str>) -> Result<Self, anyhow::Error> {
+        let mut new = Self::without_stations(base_url)?;
+
+        match read_station_cache() {
+            Ok(cache) => {
+                debug!("Loaded {} stations from cache", cache.stations.len());
+                *new.stations.write().unwrap() = Some(StationTable::from_cache(cache));
+                new.stations_from_cache = true;
+            }
+            Err(e) => {
+                debug!("No usable station cache, downloading stations: {e}");
+                new.refresh_stations().await?;
+            }
+        }
 
-        new.update_stations().await?;
         Ok(new)
     }
 
-    fn metars_json_url(airports_string: &str) -> String {
-        format!("{BASE_URL}/api/data/metar/?ids={airports_string}&format=json")
+    fn without_stations(base_url: Option<&str>) -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            client: Client::builder().build()?,
+            base_url: base_url
+                .unwrap_or(DEFAULT_BASE_URL)
+                .trim_end_matches('/')
+                .to_string(),
+            stations: Arc::new(RwLock::new(None)),
+            stations_from_cache: false,
+        })
+    }
+
+    /// Whether the station table was loaded from disk and may be out of date
+    pub const fn stations_from_cache(&self) -> bool {
+        self.stations_from_cache
+    }
+
+    fn metars_json_url(&self, airports_string: &str) -> String {
+        format!(
+            "{}/api/data/metar/?ids={airports_string}&format=json",
+            self.base_url
+        )
     }
 
     pub async fn fetch_metar(&self, station_id: &str) -> Result<MetarDto, anyhow::Error> {
@@ -45,7 +123,7 @@ impl AviationWeatherCenterApi {
 
         let metars = self
             .client
-            .get(Self::metars_json_url(&id_sanitized))
+            .get(self.metars_json_url(&id_sanitized))
             .send()
             .await?
             .json::<Vec<MetarDto>>()
@@ -58,106 +136,194 @@ impl AviationWeatherCenterApi {
         }
     }
 
-    #[allow(dead_code)]
-    pub async fn fetch_metars(
-        &self,
-        station_ids: &[&str],
-    ) -> Result<Vec<MetarDto>, reqwest::Error> {
+    fn tafs_json_url(&self, airports_string: &str) -> String {
+        format!(
+            "{}/api/data/taf/?ids={airports_string}&format=json",
+            self.base_url
+        )
+    }
+
+    pub async fn fetch_taf(&self, station_id: &str) -> Result<TafDto, anyhow::Error> {
+        if station_id.starts_with('@') || station_id.len() > 4 || station_id.contains(',') {
+            bail!("Invalid station ID, must be a single ICAO or FAA ID")
+        }
+
+        let id_sanitized = self.sanitize_id(station_id);
+
+        let tafs = self
+            .client
+            .get(self.tafs_json_url(&id_sanitized))
+            .send()
+            .await?
+            .json::<Vec<TafDto>>()
+            .await?;
+
+        tafs.into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("No TAFs found in result list"))
+    }
+
+    pub async fn fetch_metars(&self, station_ids: &[&str]) -> Result<Vec<MetarDto>, anyhow::Error> {
         let sanitized_ids = station_ids
             .iter()
             .map(|id| self.sanitize_id(id))
             .collect::<Vec<_>>();
 
         self.client
-            .get(Self::metars_json_url(&sanitized_ids.join(",")))
+            .get(self.metars_json_url(&sanitized_ids.join(",")))
             .send()
             .await?
             .json::<Vec<MetarDto>>()
             .await
+            .map_err(Into::into)
     }
 
-    pub async fn update_stations(&mut self) -> Result<HashMap<String, Station>, anyhow::Error> {
-        let stations = self.fetch_stations_hashmap().await?;
-        self.stations = Some(stations.clone());
-        self.faa_icao_lookup = Some(
-            stations
-                .values()
-                .map(|s| (s.faa_id.to_uppercase(), s.icao_id.to_uppercase()))
-                .collect(),
-        );
-        Ok(stations)
+    /// Downloads the station table if it has changed since the current copy, and writes it to
+    /// the on-disk cache. Returns `false` if the server reported no change. On error the current
+    /// table is kept.
+    pub async fn refresh_stations(&self) -> Result<bool, anyhow::Error> {
+        let Some(cache) = self.fetch_stations_if_changed().await? else {
+            debug!("Station table not modified");
+            return Ok(false);
+        };
+
+        debug!("Downloaded {} stations", cache.stations.len());
+        if let Err(e) = write_station_cache(&cache) {
+            warn!("Could not write station cache: {e:?}");
+        }
+        *self.stations.write().unwrap() = Some(StationTable::from_cache(cache));
+        Ok(true)
     }
 
-    pub async fn fetch_stations(&self) -> Result<Vec<Station>, anyhow::Error> {
-        let gzipped = self
-            .client
-            .get(format!("{BASE_URL}/data/cache/stations.cache.json.gz"))
-            .send()
-            .await?
-            .bytes()
-            .await?;
+    async fn fetch_stations_if_changed(&self) -> Result<Option<StationCache>, anyhow::Error> {
+        let mut request = self.client.get(format!(
+            "{}/data/cache/stations.cache.json.gz",
+            self.base_url
+        ));
+        if let Some(table) = self.stations.read().unwrap().as_ref() {
+            if let Some(etag) = &table.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &table.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = request.send().await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        let response = response.error_for_status()?;
+
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(ToString::to_string)
+        };
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+        let gzipped = response.bytes().await?;
 
         // AWC doesn't set a header that reqwest automatically catches, so need
         // to do manual GZIP decompression
-        let read = &gzipped.into_iter().collect::<Vec<_>>()[..];
-        let mut d = GzDecoder::new(read);
+        let mut d = GzDecoder::new(&gzipped[..]);
         let mut s = String::new();
         d.read_to_string(&mut s)?;
 
-        Ok(serde_json::from_str(&s)?)
-    }
-
-    pub async fn fetch_stations_hashmap(&self) -> Result<HashMap<String, Station>, anyhow::Error> {
-        let stations = self.fetch_stations().await?;
-        let map = stations
-            .into_iter()
-            .map(|s| (s.icao_id.to_uppercase(), s))
-            .collect::<HashMap<_, _>>();
-        Ok(map)
+        Ok(Some(StationCache {
+            etag,
+            last_modified,
+            stations: serde_json::from_str(&s)?,
+        }))
     }
 
     pub fn lookup_station(&self, lookup_id: &str) -> Result<Station, anyhow::Error> {
         let uppercase = lookup_id.to_uppercase();
-        if let (Some(stations), Some(faa_icao_map)) = (&self.stations, &self.faa_icao_lookup) {
-            if let Some(station) = stations.get(&uppercase) {
-                Ok(station.clone())
-            } else if let Some(id) = faa_icao_map.get(&uppercase) {
-                stations.get(&id.to_uppercase()).map_or_else(
-                    || Err(anyhow!("Error: inconsistency between FAA and ICAO data")),
-                    |s| Ok(s.clone()),
-                )
-            } else {
-                bail!("Error: could not find ID in ICAO or FAA lookups")
-            }
-        } else {
+        let stations = self.stations.read().unwrap();
+        let Some(table) = stations.as_ref() else {
             bail!("Error: station data not initialized")
-        }
+        };
+
+        let ret = match (
+            table.stations.get(&uppercase),
+            table.faa_icao_lookup.get(&uppercase),
+        ) {
+            (Some(station), _) => Ok(station.clone()),
+            (None, Some(id)) => table
+                .stations
+                .get(&id.to_uppercase())
+                .cloned()
+                .ok_or_else(|| anyhow!("Error: inconsistency between FAA and ICAO data")),
+            (None, None) => Err(anyhow!("Error: could not find ID in ICAO or FAA lookups")),
+        };
+        drop(stations);
+
+        ret
     }
 
     fn sanitize_id(&self, id: &str) -> String {
-        let ret = self
-            .stations
-            .as_ref()
-            .map_or(id.to_uppercase(), |stations| {
+        let ret = self.stations.read().unwrap().as_ref().map_or_else(
+            || id.to_uppercase(),
+            |table| {
                 let id_is_state = id.starts_with('@') && id.len() == 3;
-                let id_is_valid_icao = stations.contains_key(id);
+                let id_is_valid_icao = table.stations.contains_key(id);
 
                 if id_is_state || id_is_valid_icao {
                     id.to_uppercase()
-                } else if let Some(faa_icao_map) = &self.faa_icao_lookup {
-                    faa_icao_map
+                } else {
+                    table
+                        .faa_icao_lookup
                         .get(id)
                         .map_or_else(|| id.to_uppercase(), ToString::to_string)
-                } else {
-                    id.to_uppercase()
                 }
-            });
+            },
+        );
         debug!("Sanitized id for {id}: {ret}");
 
         ret
     }
 }
 
+fn station_cache_path() -> Option<PathBuf> {
+    dirs::config_local_dir().map(|p| p.join("Mini METARs").join("stations.json"))
+}
+
+fn read_station_cache() -> Result<StationCache, anyhow::Error> {
+    let path =
+        station_cache_path().ok_or_else(|| anyhow!("Could not construct path to stations.json"))?;
+    let cache: StationCache = utils::deserialize_from_file(&path)?;
+    if cache.stations.is_empty() {
+        bail!("Station cache at {} is empty", path.display());
+    }
+    Ok(cache)
+}
+
+fn write_station_cache(cache: &StationCache) -> Result<(), anyhow::Error> {
+    let path =
+        station_cache_path().ok_or_else(|| anyhow!("Could not construct path to stations.json"))?;
+    path.parent()
+        .and_then(utils::get_or_create_path)
+        .ok_or_else(|| anyhow!("Could not create directory for {}", path.display()))?;
+    utils::serialize_to_file(&path, cache)
+}
+
+#[async_trait]
+impl WeatherProvider for AviationWeatherCenterApi {
+    fn name(&self) -> &'static str {
+        "AWC"
+    }
+
+    async fn fetch_metar(&self, station_id: &str) -> Result<MetarDto, anyhow::Error> {
+        Self::fetch_metar(self, station_id).await
+    }
+
+    async fn fetch_metars(&self, station_ids: &[&str]) -> Result<Vec<MetarDto>, anyhow::Error> {
+        Self::fetch_metars(self, station_ids).await
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Station {
@@ -179,7 +345,7 @@ pub struct Station {
 pub struct MetarDto {
     pub icao_id: String,
     pub receipt_time: String,
-    #[serde(deserialize_with = "ts_seconds::deserialize")]
+    #[serde(deserialize_with = "deserialize_timestamp")]
     pub obs_time: DateTime<Utc>,
     pub report_time: String,
     pub temp: Option<f64>,
@@ -214,6 +380,24 @@ pub struct MetarDto {
     pub clouds: Vec<Cloud>,
 }
 
+/// AWC sends Unix timestamps, while copies serialized by the app itself (e.g. the METAR history)
+/// contain RFC 3339 strings
+fn deserialize_timestamp<'de, D: Deserializer<'de>>(d: D) -> Result<DateTime<Utc>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Timestamp {
+        Seconds(i64),
+        Rfc3339(DateTime<Utc>),
+    }
+
+    match Timestamp::deserialize(d)? {
+        Timestamp::Seconds(s) => {
+            DateTime::from_timestamp(s, 0).ok_or_else(|| D::Error::custom("timestamp out of range"))
+        }
+        Timestamp::Rfc3339(t) => Ok(t),
+    }
+}
+
 impl MetarDto {
     pub fn altimeter_in_hg(&self) -> f64 {
         (self.altim * MBAR_TO_INHG_FACTOR * 100.0).round() / 100.0
@@ -225,24 +409,69 @@ impl MetarDto {
 
     pub fn wind_string(&self) -> String {
         if let (Some(wind_dir), Some(wind_spd)) = (&self.wdir, self.wspd) {
-            let mut return_s = String::new();
-
             let dir_str = match wind_dir {
-                StringOrI32::String(s) => s.to_string(),
+                StringOrI32::String(s) => s.clone(),
                 StringOrI32::I32(i) => format!("{i:03}"),
             };
-            return_s.push_str(&dir_str);
-            return_s.push_str(&format!("{wind_spd:02}"));
-            if let Some(gusts) = self.wgst {
-                return_s.push_str(&format!("G{gusts}"));
-            }
-            return_s.push_str("KT");
+            let gust_str = self.wgst.map(|g| format!("G{g}")).unwrap_or_default();
 
-            return_s
+            format!("{dir_str}{wind_spd:02}{gust_str}KT")
         } else {
             String::new()
         }
     }
+
+    pub fn decode(&self) -> Result<MetarReport, anyhow::Error> {
+        parse_metar(&self.raw_ob)
+    }
+
+    /// Visibility in statute miles, where AWC reports e.g. `"10+"` as a string
+    pub fn visibility_sm(&self) -> Option<f64> {
+        match &self.visib {
+            StringOrF64::F64(v) => Some(*v),
+            StringOrF64::String(s) => s.trim_end_matches('+').parse().ok(),
+        }
+    }
+
+    /// Height of the lowest broken or overcast layer, or the vertical visibility if obscured
+    pub fn ceiling_ft(&self) -> Option<i32> {
+        self.clouds
+            .iter()
+            .filter(|c| matches!(c.cover.as_str(), "BKN" | "OVC" | "OVX"))
+            .filter_map(|c| c.base)
+            .chain(self.vert_vis)
+            .min()
+    }
+
+    pub fn is_cavok(&self) -> bool {
+        self.clouds.iter().any(|c| c.cover == "CAVOK")
+            || self.raw_ob.split_whitespace().any(|t| t == "CAVOK")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TafDto {
+    pub icao_id: String,
+    pub issue_time: Option<String>,
+    #[serde(deserialize_with = "ts_seconds::deserialize")]
+    pub valid_time_from: DateTime<Utc>,
+    #[serde(deserialize_with = "ts_seconds::deserialize")]
+    pub valid_time_to: DateTime<Utc>,
+    #[serde(rename = "rawTAF")]
+    pub raw_taf: String,
+    pub most_recent: Option<i32>,
+    pub remarks: Option<String>,
+    pub lat: f64,
+    pub lon: f64,
+    pub elev: i32,
+    pub name: String,
+}
+
+impl TafDto {
+    pub fn decode(&self) -> Result<TafReport, anyhow::Error> {
+        parse_taf(&self.raw_taf, self.valid_time_from)
+    }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -283,3 +512,116 @@ impl fmt::Display for StringOrF64 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::MockServer;
+    use serde_json::json;
+
+    fn metar_json(icao_id: &str, altim: f64) -> serde_json::Value {
+        json!({
+            "icaoId": icao_id,
+            "receiptTime": "2024-10-16 12:58:12",
+            "obsTime": 1_729_083_360,
+            "reportTime": "2024-10-16 13:00:00",
+            "temp": 16.1,
+            "dewp": 12.2,
+            "wdir": 280,
+            "wspd": 12,
+            "wgst": null,
+            "visib": "10+",
+            "altim": altim,
+            "slp": 1013.1,
+            "qcField": 4,
+            "wxString": null,
+            "presTend": null,
+            "maxT": null,
+            "minT": null,
+            "maxT24": null,
+            "minT24": null,
+            "precip": null,
+            "pcp3hr": null,
+            "pcp6hr": null,
+            "pcp24hr": null,
+            "snow": null,
+            "vertVis": null,
+            "metarType": "METAR",
+            "rawOb": format!("{icao_id} 161256Z 28012KT 10SM FEW010 16/12 A2992"),
+            "mostRecent": 1,
+            "lat": 37.619,
+            "lon": -122.365,
+            "elev": 3,
+            "prior": 0,
+            "name": "Test Intl, CA, US",
+            "clouds": [{"cover": "FEW", "base": 1000}]
+        })
+    }
+
+    fn client_with_stations(server: &MockServer) -> AviationWeatherCenterApi {
+        let client =
+            AviationWeatherCenterApi::without_stations(Some(&format!("{}/", server.url())))
+                .unwrap();
+        let stations = ["KSFO", "KOAK"]
+            .into_iter()
+            .map(|id| Station {
+                icao_id: id.to_string(),
+                faa_id: id[1..].to_string(),
+                ..Station::default()
+            })
+            .collect();
+        *client.stations.write().unwrap() = Some(StationTable::from_cache(StationCache {
+            stations,
+            ..StationCache::default()
+        }));
+        client
+    }
+
+    #[tokio::test]
+    async fn fetches_batched_metars_by_icao_id() {
+        let body = json!([metar_json("KSFO", 1013.2), metar_json("KOAK", 1013.5)]).to_string();
+        let server = MockServer::start(move |_| (200, body.clone())).await;
+        let client = client_with_stations(&server);
+
+        let metars = client.fetch_metars(&["SFO", "koak"]).await.unwrap();
+        assert_eq!(
+            server.requests(),
+            ["/api/data/metar/?ids=KSFO,KOAK&format=json"]
+        );
+        assert_eq!(metars.len(), 2);
+        assert_eq!(metars[0].icao_id, "KSFO");
+        assert_eq!(
+            metars[0].obs_time,
+            DateTime::from_timestamp(1_729_083_360, 0).unwrap()
+        );
+        assert!((metars[0].altimeter_in_hg() - 29.92).abs() < f64::EPSILON);
+        assert!((metars[1].altimeter_hpa() - 1013.5).abs() < f64::EPSILON);
+    }
+
+    #[tokio::test]
+    async fn fetches_single_metar() {
+        let server = MockServer::start(|path| {
+            if path.contains("KSFO") {
+                (200, json!([metar_json("KSFO", 1013.2)]).to_string())
+            } else {
+                (200, "[]".to_string())
+            }
+        })
+        .await;
+        let client = client_with_stations(&server);
+
+        assert_eq!(client.fetch_metar("KSFO").await.unwrap().icao_id, "KSFO");
+        assert!(client.fetch_metar("KOAK").await.is_err());
+        assert!(client.fetch_metar("KSFO,KOAK").await.is_err());
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn uses_default_base_url() {
+        let client = AviationWeatherCenterApi::without_stations(None).unwrap();
+        assert_eq!(
+            client.metars_json_url("KSFO"),
+            "https://aviationweather.gov/api/data/metar/?ids=KSFO&format=json"
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use crate::networks::NetworkAtis;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_ARRIVAL_PATTERNS: &[&str] = &["_A_"];
+const DEFAULT_DEPARTURE_PATTERNS: &[&str] = &["_D_"];
+
+/// How ATIS callsigns are matched to a station, configured per station in the profile
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AtisCallsignRules {
+    /// Callsign prefixes matched in addition to the station ID, e.g. the FAA ID `SFO`
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Regexes matched against the whole callsign, e.g. `^KSFO_ATIS$` for a combined ATIS
+    /// that also covers this station
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// Substrings marking an arrival ATIS, `_A_` if empty
+    #[serde(default)]
+    pub arrival_suffixes: Vec<String>,
+    /// Substrings marking a departure ATIS, `_D_` if empty
+    #[serde(default)]
+    pub departure_suffixes: Vec<String>,
+}
+
+/// Compiled form of `AtisCallsignRules` for one station
+#[derive(Debug)]
+pub struct CallsignMatcher {
+    station: String,
+    prefixes: Vec<String>,
+    patterns: Vec<Regex>,
+    arrival_suffixes: Vec<String>,
+    departure_suffixes: Vec<String>,
+}
+
+impl CallsignMatcher {
+    pub fn new(station: &str, rules: &AtisCallsignRules) -> Result<Self, regex::Error> {
+        let patterns = rules
+            .patterns
+            .iter()
+            .map(|p| RegexBuilder::new(p).case_insensitive(true).build())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            station: station.to_string(),
+            prefixes: std::iter::once(station)
+                .chain(rules.aliases.iter().map(String::as_str))
+                .map(str::to_uppercase)
+                .collect(),
+            patterns,
+            arrival_suffixes: uppercase_or_default(
+                &rules.arrival_suffixes,
+                DEFAULT_ARRIVAL_PATTERNS,
+            ),
+            departure_suffixes: uppercase_or_default(
+                &rules.departure_suffixes,
+                DEFAULT_DEPARTURE_PATTERNS,
+            ),
+        })
+    }
+
+    pub fn station(&self) -> &str {
+        &self.station
+    }
+
+    pub fn matches(&self, atis: &NetworkAtis) -> bool {
+        let callsign = atis.callsign.to_uppercase();
+        self.prefixes.iter().any(|p| callsign.starts_with(p))
+            || self.patterns.iter().any(|p| p.is_match(&callsign))
+    }
+
+    pub fn is_arrival(&self, atis: &NetworkAtis) -> bool {
+        contains_any(&atis.callsign, &self.arrival_suffixes)
+    }
+
+    pub fn is_departure(&self, atis: &NetworkAtis) -> bool {
+        contains_any(&atis.callsign, &self.departure_suffixes)
+    }
+}
+
+fn uppercase_or_default(configured: &[String], default: &[&str]) -> Vec<String> {
+    if configured.is_empty() {
+        default.iter().map(ToString::to_string).collect()
+    } else {
+        configured.iter().map(|s| s.to_uppercase()).collect()
+    }
+}
+
+fn contains_any(callsign: &str, patterns: &[String]) -> bool {
+    let callsign = callsign.to_uppercase();
+    patterns.iter().any(|p| callsign.contains(p.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ONLINE: &[&str] = &[
+        "KSFO_ATIS",
+        "SFO_ATIS",
+        "KSFO_A_ATIS",
+        "KSFO_D_ATIS",
+        "KSFO_ARR_ATIS",
+        "KSFO_DEP_ATIS",
+        "KSFOX_ATIS",
+        "KOAK_ATIS",
+        "NCT_ATIS",
+        "ksjc_atis",
+    ];
+
+    fn atis(callsign: &str) -> NetworkAtis {
+        NetworkAtis {
+            callsign: callsign.to_string(),
+            atis_code: None,
+            text_atis: None,
+        }
+    }
+
+    fn matched(station: &str, rules: &AtisCallsignRules) -> Vec<&'static str> {
+        let matcher = CallsignMatcher::new(station, rules).unwrap();
+        ONLINE
+            .iter()
+            .copied()
+            .filter(|c| matcher.matches(&atis(c)))
+            .collect()
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn default_rules_match_callsigns_starting_with_station() {
+        assert_eq!(
+            matched("KSFO", &AtisCallsignRules::default()),
+            [
+                "KSFO_ATIS",
+                "KSFO_A_ATIS",
+                "KSFO_D_ATIS",
+                "KSFO_ARR_ATIS",
+                "KSFO_DEP_ATIS",
+                "KSFOX_ATIS"
+            ]
+        );
+        assert_eq!(
+            matched("ksjc", &AtisCallsignRules::default()),
+            ["ksjc_atis"]
+        );
+        assert!(matched("KLAX", &AtisCallsignRules::default()).is_empty());
+
+        let matcher = CallsignMatcher::new("KSFO", &AtisCallsignRules::default()).unwrap();
+        for (callsign, arrival, departure) in [
+            ("KSFO_A_ATIS", true, false),
+            ("KSFO_D_ATIS", false, true),
+            ("ksfo_a_atis", true, false),
+            ("KSFO_ATIS", false, false),
+            ("KSFO_ARR_ATIS", false, false),
+        ] {
+            assert_eq!(matcher.is_arrival(&atis(callsign)), arrival, "{callsign}");
+            assert_eq!(
+                matcher.is_departure(&atis(callsign)),
+                departure,
+                "{callsign}"
+            );
+        }
+    }
+
+    #[test]
+    fn matches_aliases_such_as_faa_ids() {
+        let rules = AtisCallsignRules {
+            aliases: strings(&["sfo"]),
+            ..AtisCallsignRules::default()
+        };
+        assert!(matched("KSFO", &rules).contains(&"SFO_ATIS"));
+        assert!(!matched("KSFO", &AtisCallsignRules::default()).contains(&"SFO_ATIS"));
+    }
+
+    #[test]
+    fn matches_patterns_against_whole_callsign() {
+        let rules = AtisCallsignRules {
+            patterns: strings(&["^ksfo_atis$"]),
+            ..AtisCallsignRules::default()
+        };
+        assert_eq!(matched("KSQL", &rules), ["KSFO_ATIS"]);
+
+        let rules = AtisCallsignRules {
+            patterns: strings(&["("]),
+            ..AtisCallsignRules::default()
+        };
+        assert!(CallsignMatcher::new("KSFO", &rules).is_err());
+    }
+
+    #[test]
+    fn matches_combined_atis_for_two_fields() {
+        // NCT_ATIS covers both KOAK and KSJC
+        let rules = AtisCallsignRules {
+            patterns: strings(&["^NCT_ATIS$"]),
+            ..AtisCallsignRules::default()
+        };
+        assert_eq!(matched("KOAK", &rules), ["KOAK_ATIS", "NCT_ATIS"]);
+        assert_eq!(matched("KSJC", &rules), ["NCT_ATIS", "ksjc_atis"]);
+    }
+
+    #[test]
+    fn uses_configured_arrival_and_departure_suffixes() {
+        let rules = AtisCallsignRules {
+            arrival_suffixes: strings(&["_arr_"]),
+            departure_suffixes: strings(&["_DEP_"]),
+            ..AtisCallsignRules::default()
+        };
+        let matcher = CallsignMatcher::new("KSFO", &rules).unwrap();
+        for (callsign, arrival, departure) in [
+            ("KSFO_ARR_ATIS", true, false),
+            ("KSFO_DEP_ATIS", false, true),
+            // Configured suffixes replace the defaults
+            ("KSFO_A_ATIS", false, false),
+            ("KSFO_D_ATIS", false, false),
+        ] {
+            assert_eq!(matcher.is_arrival(&atis(callsign)), arrival, "{callsign}");
+            assert_eq!(
+                matcher.is_departure(&atis(callsign)),
+                departure,
+                "{callsign}"
+            );
+        }
+    }
+}
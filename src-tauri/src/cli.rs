@@ -0,0 +1,420 @@
+use crate::callsigns::CallsignMatcher;
+use crate::flight_category::FlightCategory;
+use crate::networks::AtisNetworkKind;
+use crate::profiles::{read_profile_from_file, AltimeterUnits, Profile, StationEntry};
+use crate::settings::read_settings_or_default;
+use crate::state::AppState;
+use crate::{fetch_network_atis, find_atis, FetchAtisResponse, FetchMetarResponse};
+use anyhow::{anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub const CLI_FLAG: &str = "--cli";
+const DEFAULT_WATCH_INTERVAL: Duration = Duration::from_secs(60);
+
+const USAGE: &str = "\
+Usage: mini-metars --cli [OPTIONS] [STATION...]
+
+Prints METAR and ATIS information for the given stations, or for the stations of a profile.
+
+Options:
+  --profile <PATH>     Read stations, units and ATIS network from a profile
+                       (default: the most recently used profile if no stations are given)
+  --network <NETWORK>  Network to read ATIS from: vatsim or ivao
+  --hpa                Show the altimeter in hPa
+  --watch [SECONDS]    Refresh every SECONDS (default 60) until interrupted
+  --json               Print JSON instead of a table, one line per refresh
+  -h, --help           Print this help";
+
+#[derive(Debug, Default)]
+struct CliOptions {
+    stations: Vec<String>,
+    profile: Option<PathBuf>,
+    network: Option<AtisNetworkKind>,
+    hpa: bool,
+    watch: Option<Duration>,
+    json: bool,
+    help: bool,
+}
+
+impl CliOptions {
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, anyhow::Error> {
+        let mut options = Self::default();
+        let mut args = args.into_iter().peekable();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                CLI_FLAG => {}
+                "--profile" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--profile requires a path"))?;
+                    options.profile = Some(PathBuf::from(path));
+                }
+                "--network" => {
+                    options.network = Some(match args.next().as_deref() {
+                        Some(n) if n.eq_ignore_ascii_case("vatsim") => AtisNetworkKind::Vatsim,
+                        Some(n) if n.eq_ignore_ascii_case("ivao") => AtisNetworkKind::Ivao,
+                        _ => bail!("--network requires vatsim or ivao"),
+                    });
+                }
+                "--hpa" => options.hpa = true,
+                "--watch" => {
+                    let secs = args.next_if(|a| a.parse::<u64>().is_ok());
+                    options.watch = Some(secs.map_or(DEFAULT_WATCH_INTERVAL, |s| {
+                        Duration::from_secs(s.parse::<u64>().unwrap_or_default().max(1))
+                    }));
+                }
+                "--json" => options.json = true,
+                "-h" | "--help" => options.help = true,
+                a if a.starts_with('-') => bail!("Unknown option {a}"),
+                station => options.stations.push(station.to_uppercase()),
+            }
+        }
+
+        Ok(options)
+    }
+
+    /// Fills in the stations and network not given on the command line from the profile
+    fn apply_profile(&mut self, profile: &Profile) {
+        if self.stations.is_empty() {
+            self.stations = profile
+                .stations
+                .iter()
+                .filter(|s| !s.hidden)
+                .map(|s| s.id.to_uppercase())
+                .collect();
+        }
+        self.network = self.network.or(Some(profile.network));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CliRow {
+    station: String,
+    metar: Option<FetchMetarResponse>,
+    atis: Option<FetchAtisResponse>,
+    errors: Vec<String>,
+    #[serde(skip)]
+    hpa: bool,
+}
+
+/// Runs the headless mode and returns the process exit code
+pub fn run(args: impl IntoIterator<Item = String>) -> i32 {
+    let options = match CliOptions::parse(args) {
+        Ok(o) if o.help => {
+            println!("{USAGE}");
+            return 0;
+        }
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            return 2;
+        }
+    };
+
+    match tauri::async_runtime::block_on(run_options(options)) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Error: {e:?}");
+            1
+        }
+    }
+}
+
+async fn run_options(mut options: CliOptions) -> Result<(), anyhow::Error> {
+    let settings = read_settings_or_default();
+    if options.profile.is_none() && options.stations.is_empty() {
+        options.profile = settings.most_recent_profile().map(PathBuf::from);
+    }
+
+    let profile = options
+        .profile
+        .as_deref()
+        .map(read_profile_from_file)
+        .transpose()?;
+    if let Some(profile) = &profile {
+        options.apply_profile(profile);
+    }
+    if options.stations.is_empty() {
+        bail!("No stations given and no profile to read them from");
+    }
+
+    let state = AppState::new();
+    *state.settings.lock().unwrap() = Some(settings);
+
+    loop {
+        let rows = fetch_rows(&state, &options, profile.as_ref()).await;
+        if options.json {
+            println!("{}", serde_json::to_string(&rows)?);
+        } else {
+            if options.watch.is_some() {
+                // Clear the terminal so each refresh replaces the previous table
+                print!("\x1b[2J\x1b[H");
+            }
+            print_table(&rows);
+        }
+        std::io::stdout().flush()?;
+
+        let Some(interval) = options.watch else {
+            return Ok(());
+        };
+        tokio::time::sleep(interval).await;
+    }
+}
+
+async fn fetch_rows(
+    state: &AppState,
+    options: &CliOptions,
+    profile: Option<&Profile>,
+) -> Vec<CliRow> {
+    let mut rows = empty_rows(options, profile);
+
+    // Resolve FAA and IATA IDs the same way the window does
+    if let Ok(client) = state.get_awc_client().await {
+        for row in &mut rows {
+            if let Ok(station) = client.lookup_station(&row.station) {
+                row.station = station.icao_id;
+            }
+        }
+    }
+
+    let thresholds = state.flight_category_thresholds();
+    match state.get_weather_provider().await {
+        Ok(provider) => {
+            let ids = rows.iter().map(|r| r.station.as_str()).collect::<Vec<_>>();
+            match provider.fetch_metars(&ids).await {
+                Ok(metars) => {
+                    for metar in metars {
+                        if let Some(row) = rows
+                            .iter_mut()
+                            .find(|r| r.station.eq_ignore_ascii_case(&metar.icao_id))
+                        {
+                            row.metar = Some(FetchMetarResponse::new(metar, &thresholds));
+                        }
+                    }
+                }
+                Err(e) => rows.iter_mut().for_each(|r| r.errors.push(e.to_string())),
+            }
+        }
+        Err(e) => rows.iter_mut().for_each(|r| r.errors.push(e.to_string())),
+    }
+
+    match fetch_network_atis(state, options.network.unwrap_or_default()).await {
+        Ok(data) => {
+            for (row, id) in rows.iter_mut().zip(&options.stations) {
+                let station_rules = profile
+                    .and_then(|p| station_entry(p, id).or_else(|| station_entry(p, &row.station)))
+                    .and_then(|s| s.atis_callsign_rules.clone())
+                    .unwrap_or_default();
+                match CallsignMatcher::new(&row.station, &station_rules) {
+                    Ok(matcher) => row.atis = Some(find_atis(&data.atis, &matcher)),
+                    Err(e) => row
+                        .errors
+                        .push(format!("Invalid ATIS callsign pattern: {e}")),
+                }
+            }
+        }
+        Err(e) => rows.iter_mut().for_each(|r| r.errors.push(e.to_string())),
+    }
+
+    rows
+}
+
+/// A row per station without data yet, in the units of `--hpa`, the station's profile entry or
+/// the profile, in that order
+fn empty_rows(options: &CliOptions, profile: Option<&Profile>) -> Vec<CliRow> {
+    options
+        .stations
+        .iter()
+        .map(|id| CliRow {
+            station: id.clone(),
+            metar: None,
+            atis: None,
+            errors: vec![],
+            hpa: options.hpa
+                || profile.is_some_and(|p| {
+                    station_entry(p, id)
+                        .and_then(|s| s.units)
+                        .unwrap_or(p.units)
+                        == AltimeterUnits::hPa
+                }),
+        })
+        .collect()
+}
+
+fn station_entry<'a>(profile: &'a Profile, id: &str) -> Option<&'a StationEntry> {
+    profile
+        .stations
+        .iter()
+        .find(|s| s.id.eq_ignore_ascii_case(id))
+}
+
+fn print_table(rows: &[CliRow]) {
+    println!(
+        "{:<8}{:<6}{:<8}{:<14}CAT",
+        "STATION", "ATIS", "ALTIM", "WIND"
+    );
+    for row in rows {
+        let altimeter = row.metar.as_ref().map_or_else(String::new, |m| {
+            if row.hpa {
+                format!("{:.0}", m.altimeter.hpa)
+            } else {
+                format!("{:.2}", m.altimeter.in_hg)
+            }
+        });
+        let wind = row
+            .metar
+            .as_ref()
+            .map(|m| m.wind_string.clone())
+            .unwrap_or_default();
+        let category = row
+            .metar
+            .as_ref()
+            .and_then(|m| m.flight_category)
+            .map_or("", FlightCategory::code);
+        let letter = row.atis.as_ref().map_or("-", |a| a.letter.as_str());
+
+        println!(
+            "{:<8}{:<6}{:<8}{:<14}{}",
+            row.station, letter, altimeter, wind, category
+        );
+        for error in &row.errors {
+            eprintln!("  {}: {error}", row.station);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse(args: &[&str]) -> Result<CliOptions, anyhow::Error> {
+        CliOptions::parse(args.iter().map(ToString::to_string))
+    }
+
+    fn profile() -> Profile {
+        serde_json::from_value(json!({
+            "version": 2,
+            "name": "Test",
+            "stations": [
+                {"id": "ksfo"},
+                {"id": "KOAK", "units": "inHg"},
+                {"id": "SJC", "hidden": true},
+                {"id": "EGLL", "units": "hPa"}
+            ],
+            "units": "hPa",
+            "network": "Ivao"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn parses_stations_and_flags() {
+        let options = parse(&["--cli", "ksfo", "--hpa", "KOAK", "--json"]).unwrap();
+        assert_eq!(options.stations, ["KSFO", "KOAK"]);
+        assert!(options.hpa);
+        assert!(options.json);
+        assert!(!options.help);
+        assert_eq!(options.watch, None);
+        assert_eq!(options.network, None);
+
+        assert!(parse(&["--cli", "-h"]).unwrap().help);
+        assert_eq!(
+            parse(&["--profile", "a.json"]).unwrap().profile,
+            Some(PathBuf::from("a.json"))
+        );
+    }
+
+    #[test]
+    fn parses_watch_with_and_without_seconds() {
+        let options = parse(&["--watch"]).unwrap();
+        assert_eq!(options.watch, Some(DEFAULT_WATCH_INTERVAL));
+
+        let options = parse(&["--watch", "KSFO"]).unwrap();
+        assert_eq!(options.watch, Some(DEFAULT_WATCH_INTERVAL));
+        assert_eq!(options.stations, ["KSFO"]);
+
+        let options = parse(&["--watch", "30", "KSFO"]).unwrap();
+        assert_eq!(options.watch, Some(Duration::from_secs(30)));
+        assert_eq!(options.stations, ["KSFO"]);
+
+        let options = parse(&["--watch", "0"]).unwrap();
+        assert_eq!(options.watch, Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn parses_network() {
+        assert_eq!(
+            parse(&["--network", "vatsim"]).unwrap().network,
+            Some(AtisNetworkKind::Vatsim)
+        );
+        assert_eq!(
+            parse(&["--network", "IVAO"]).unwrap().network,
+            Some(AtisNetworkKind::Ivao)
+        );
+        assert!(parse(&["--network", "pilotedge"]).is_err());
+        assert!(parse(&["--network"]).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_options() {
+        assert!(parse(&["KSFO", "--verbose"]).is_err());
+        assert!(parse(&["-v"]).is_err());
+        assert!(parse(&["--profile"]).is_err());
+    }
+
+    #[test]
+    fn reads_visible_stations_and_network_from_profile() {
+        let mut options = parse(&[]).unwrap();
+        options.apply_profile(&profile());
+        assert_eq!(options.stations, ["KSFO", "KOAK", "EGLL"]);
+        assert_eq!(options.network, Some(AtisNetworkKind::Ivao));
+
+        let mut options = parse(&["KLAX", "--network", "vatsim"]).unwrap();
+        options.apply_profile(&profile());
+        assert_eq!(options.stations, ["KLAX"]);
+        assert_eq!(options.network, Some(AtisNetworkKind::Vatsim));
+    }
+
+    #[test]
+    fn resolves_units_per_station() {
+        let profile = profile();
+        let units = |options: &CliOptions, profile: Option<&Profile>| {
+            empty_rows(options, profile)
+                .into_iter()
+                .map(|r| (r.station, r.hpa))
+                .collect::<Vec<_>>()
+        };
+
+        let options = parse(&["KSFO", "KOAK", "EGLL", "KLAX"]).unwrap();
+        assert_eq!(
+            units(&options, Some(&profile)),
+            [
+                ("KSFO".to_string(), true),
+                ("KOAK".to_string(), false),
+                ("EGLL".to_string(), true),
+                ("KLAX".to_string(), true),
+            ]
+        );
+        assert_eq!(
+            units(&options, None),
+            [
+                ("KSFO".to_string(), false),
+                ("KOAK".to_string(), false),
+                ("EGLL".to_string(), false),
+                ("KLAX".to_string(), false),
+            ]
+        );
+
+        let options = parse(&["KOAK", "--hpa"]).unwrap();
+        assert_eq!(
+            units(&options, Some(&profile)),
+            [("KOAK".to_string(), true)]
+        );
+    }
+}
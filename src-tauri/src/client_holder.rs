@@ -0,0 +1,291 @@
+use anyhow::anyhow;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+const MAX_BACKOFF: Duration = Duration::from_mins(5);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "status")]
+pub enum ClientStatus {
+    Uninitialized,
+    Connected,
+    #[serde(rename_all = "camelCase")]
+    Reconnecting {
+        attempts: u32,
+        retry_in_secs: u64,
+        last_error: String,
+    },
+}
+
+struct HolderState<T> {
+    client: Option<Arc<T>>,
+    attempts: u32,
+    next_attempt: Option<Instant>,
+    last_error: Option<String>,
+}
+
+/// Lazily initializes a client, retrying failed initializations with exponential backoff instead
+/// of caching the first error for the lifetime of the app
+pub struct ClientHolder<T> {
+    name: &'static str,
+    state: Mutex<HolderState<T>>,
+    /// Held for the duration of an initialization attempt, so `state` stays readable meanwhile
+    attempt_lock: tokio::sync::Mutex<()>,
+}
+
+impl<T: Send + Sync> ClientHolder<T> {
+    #[must_use]
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            state: Mutex::new(HolderState {
+                client: None,
+                attempts: 0,
+                next_attempt: None,
+                last_error: None,
+            }),
+            attempt_lock: tokio::sync::Mutex::const_new(()),
+        }
+    }
+
+    /// Returns the client, running `init` if there is none yet and the backoff since the last
+    /// failed attempt has elapsed. Concurrent callers wait on the same attempt.
+    pub async fn get_or_try_init<F, Fut, E>(&self, init: F) -> Result<Arc<T>, anyhow::Error>
+    where
+        F: FnOnce() -> Fut + Send,
+        Fut: Future<Output = Result<T, E>> + Send,
+        E: Into<anyhow::Error>,
+    {
+        if let Some(client) = &self.state.lock().unwrap().client {
+            return Ok(Arc::clone(client));
+        }
+
+        let _attempt = self.attempt_lock.lock().await;
+        let attempts = {
+            // Another caller may have finished an attempt while this one was waiting
+            let state = self.state.lock().unwrap();
+            if let Some(client) = &state.client {
+                return Ok(Arc::clone(client));
+            }
+
+            if let Some(next_attempt) = state.next_attempt {
+                let now = Instant::now();
+                if now < next_attempt {
+                    return Err(anyhow!(
+                        "{} not initialized, retrying in {}s: {}",
+                        self.name,
+                        (next_attempt - now).as_secs(),
+                        state.last_error.as_deref().unwrap_or_default()
+                    ));
+                }
+            }
+            state.attempts
+        };
+
+        debug!("Initializing {} (attempt {})", self.name, attempts + 1);
+        let result = init().await.map_err(Into::into);
+
+        let mut state = self.state.lock().unwrap();
+        match result {
+            Ok(client) => {
+                let client = Arc::new(client);
+                state.client = Some(Arc::clone(&client));
+                state.attempts = 0;
+                state.next_attempt = None;
+                state.last_error = None;
+                drop(state);
+
+                debug!("Initialized {}", self.name);
+                Ok(client)
+            }
+            Err(e) => {
+                state.attempts += 1;
+                let backoff = backoff_for_attempt(state.attempts);
+                state.next_attempt = Some(Instant::now() + backoff);
+                state.last_error = Some(e.to_string());
+                let attempts = state.attempts;
+                drop(state);
+
+                warn!(
+                    "Could not initialize {} (attempt {attempts}), retrying in {}s: {e:?}",
+                    self.name,
+                    backoff.as_secs()
+                );
+                Err(e)
+            }
+        }
+    }
+
+    /// Does not wait for an attempt in progress, which reports the outcome of the previous one
+    pub fn status(&self) -> ClientStatus {
+        let state = self.state.lock().unwrap();
+        if state.client.is_some() {
+            ClientStatus::Connected
+        } else if let (Some(next_attempt), Some(last_error)) =
+            (state.next_attempt, &state.last_error)
+        {
+            ClientStatus::Reconnecting {
+                attempts: state.attempts,
+                retry_in_secs: next_attempt
+                    .saturating_duration_since(Instant::now())
+                    .as_secs(),
+                last_error: last_error.clone(),
+            }
+        } else {
+            ClientStatus::Uninitialized
+        }
+    }
+
+    /// Time until the next initialization attempt is allowed, `None` if already initialized
+    pub fn retry_delay(&self) -> Option<Duration> {
+        let state = self.state.lock().unwrap();
+        if state.client.is_some() {
+            None
+        } else {
+            Some(state.next_attempt.map_or(Duration::ZERO, |n| {
+                n.saturating_duration_since(Instant::now())
+            }))
+        }
+    }
+}
+
+fn backoff_for_attempt(attempts: u32) -> Duration {
+    INITIAL_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempts.saturating_sub(1)))
+        .min(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::MockServer;
+    use std::sync::atomic::{AtomicU16, Ordering};
+    use tokio::sync::Notify;
+
+    async fn fetch(url: &str) -> Result<String, reqwest::Error> {
+        reqwest::get(url).await?.error_for_status()?.text().await
+    }
+
+    /// Server answering `/client` with whatever status is currently stored in the returned atomic
+    async fn server_with_status(status: u16) -> (MockServer, Arc<AtomicU16>) {
+        let status = Arc::new(AtomicU16::new(status));
+        let server_status = Arc::clone(&status);
+        let server = MockServer::start(move |_| {
+            (server_status.load(Ordering::SeqCst), "client".to_string())
+        })
+        .await;
+        (server, status)
+    }
+
+    fn allow_retry_now<T>(holder: &ClientHolder<T>) {
+        holder.state.lock().unwrap().next_attempt = Some(Instant::now());
+    }
+
+    #[tokio::test]
+    async fn failed_init_is_reported_and_backed_off() {
+        let (server, _) = server_with_status(503).await;
+        let url = format!("{}/client", server.url());
+        let holder = ClientHolder::<String>::new("Test client");
+        assert_eq!(holder.status(), ClientStatus::Uninitialized);
+        assert_eq!(holder.retry_delay(), Some(Duration::ZERO));
+
+        assert!(holder.get_or_try_init(|| fetch(&url)).await.is_err());
+        let ClientStatus::Reconnecting {
+            attempts,
+            retry_in_secs,
+            last_error,
+        } = holder.status()
+        else {
+            panic!("Expected reconnecting status");
+        };
+        assert_eq!(attempts, 1);
+        assert!(retry_in_secs <= INITIAL_BACKOFF.as_secs());
+        assert!(last_error.contains("503"), "{last_error}");
+
+        // Within the backoff the server is not contacted again
+        let err = holder.get_or_try_init(|| fetch(&url)).await.unwrap_err();
+        assert!(err.to_string().contains("retrying in"), "{err}");
+        assert_eq!(server.requests().len(), 1);
+        assert!(holder.retry_delay().unwrap() > Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn retries_with_growing_backoff_and_recovers() {
+        let (server, status) = server_with_status(500).await;
+        let url = format!("{}/client", server.url());
+        let holder = ClientHolder::<String>::new("Test client");
+
+        assert!(holder.get_or_try_init(|| fetch(&url)).await.is_err());
+        allow_retry_now(&holder);
+        assert!(holder.get_or_try_init(|| fetch(&url)).await.is_err());
+        assert!(matches!(
+            holder.status(),
+            ClientStatus::Reconnecting { attempts: 2, .. }
+        ));
+        assert!(holder.retry_delay().unwrap() > INITIAL_BACKOFF);
+
+        status.store(200, Ordering::SeqCst);
+        allow_retry_now(&holder);
+        let client = holder.get_or_try_init(|| fetch(&url)).await.unwrap();
+        assert_eq!(*client, "client");
+        assert_eq!(holder.status(), ClientStatus::Connected);
+        assert_eq!(holder.retry_delay(), None);
+
+        // The initialized client is kept, even if the server fails again
+        status.store(500, Ordering::SeqCst);
+        assert!(holder.get_or_try_init(|| fetch(&url)).await.is_ok());
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn status_is_available_during_an_attempt() {
+        let (server, _) = server_with_status(503).await;
+        let url = format!("{}/client", server.url());
+        let holder = Arc::new(ClientHolder::<String>::new("Test client"));
+        assert!(holder.get_or_try_init(|| fetch(&url)).await.is_err());
+        allow_retry_now(&holder);
+
+        let started = Arc::new(Notify::new());
+        let release = Arc::new(Notify::new());
+        let attempt = {
+            let (holder, started, release) = (
+                Arc::clone(&holder),
+                Arc::clone(&started),
+                Arc::clone(&release),
+            );
+            tokio::spawn(async move {
+                holder
+                    .get_or_try_init(|| async move {
+                        started.notify_one();
+                        release.notified().await;
+                        fetch(&url).await
+                    })
+                    .await
+            })
+        };
+
+        started.notified().await;
+        assert!(matches!(
+            holder.status(),
+            ClientStatus::Reconnecting { attempts: 1, .. }
+        ));
+        release.notify_one();
+        assert!(attempt.await.unwrap().is_err());
+        assert!(matches!(
+            holder.status(),
+            ClientStatus::Reconnecting { attempts: 2, .. }
+        ));
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        assert_eq!(backoff_for_attempt(1), INITIAL_BACKOFF);
+        assert_eq!(backoff_for_attempt(2), INITIAL_BACKOFF * 2);
+        assert_eq!(backoff_for_attempt(3), INITIAL_BACKOFF * 4);
+        assert_eq!(backoff_for_attempt(30), MAX_BACKOFF);
+    }
+}
@@ -0,0 +1,269 @@
+use crate::atis::AtisInfo;
+use crate::awc::{MetarDto, StringOrI32};
+use crate::metar::{AltimeterSetting, Wind};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Differences below these are treated as rounding or reporting noise
+const WIND_DIRECTION_TOLERANCE: i32 = 30;
+const WIND_SPEED_TOLERANCE_KNOTS: i32 = 5;
+/// Below this speed in both reports the direction is not compared, as light winds vary a lot
+const LIGHT_WIND_KNOTS: i32 = 6;
+
+/// A value quoted in an ATIS that does not match the latest METAR
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Discrepancy {
+    /// The METAR value is given in the same unit as the ATIS
+    Altimeter {
+        atis: AltimeterSetting,
+        metar: AltimeterSetting,
+    },
+    Wind {
+        atis: String,
+        metar: String,
+    },
+    /// The ATIS is based on an observation older than the latest METAR
+    ObservationTime {
+        atis: DateTime<Utc>,
+        metar: DateTime<Utc>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StationConsistency {
+    pub station: String,
+    pub atis_letter: String,
+    pub metar_obs_time: DateTime<Utc>,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+/// Compares each of a station's ATIS against its latest METAR
+pub fn check_consistency(atis: &[AtisInfo], metar: &MetarDto) -> Vec<Discrepancy> {
+    let mut discrepancies = vec![];
+    for info in atis {
+        let found = [
+            info.altimeter.and_then(|a| altimeter_discrepancy(a, metar)),
+            info.wind.and_then(|w| wind_discrepancy(&w, metar)),
+            info.observation_time
+                .and_then(|t| t.resolve(metar.obs_time))
+                .filter(|t| *t < metar.obs_time)
+                .map(|t| Discrepancy::ObservationTime {
+                    atis: t,
+                    metar: metar.obs_time,
+                }),
+        ];
+        for discrepancy in found.into_iter().flatten() {
+            if !discrepancies.contains(&discrepancy) {
+                discrepancies.push(discrepancy);
+            }
+        }
+    }
+    discrepancies
+}
+
+fn altimeter_discrepancy(atis: AltimeterSetting, metar: &MetarDto) -> Option<Discrepancy> {
+    // Providers without an altimeter value report zero
+    if metar.altim <= 0.0 {
+        return None;
+    }
+
+    let (matches, metar_setting) = match atis {
+        AltimeterSetting::InHg(v) => {
+            let metar_in_hg = metar.altimeter_in_hg();
+            (
+                (v - metar_in_hg).abs() < 0.005,
+                AltimeterSetting::InHg(metar_in_hg),
+            )
+        }
+        AltimeterSetting::Hpa(v) => {
+            let metar_hpa = metar.altimeter_hpa();
+            (
+                (v - metar_hpa).abs() < 0.5,
+                AltimeterSetting::Hpa(metar_hpa.round()),
+            )
+        }
+    };
+
+    (!matches).then_some(Discrepancy::Altimeter {
+        atis,
+        metar: metar_setting,
+    })
+}
+
+fn wind_discrepancy(atis: &Wind, metar: &MetarDto) -> Option<Discrepancy> {
+    let metar_speed = metar.wspd?;
+    let metar_direction = match metar.wdir.as_ref()? {
+        StringOrI32::I32(d) => Some(*d),
+        StringOrI32::String(_) => None,
+    };
+    let atis_speed = atis.speed_knots();
+
+    let speed_differs = (atis_speed - metar_speed).abs() >= WIND_SPEED_TOLERANCE_KNOTS;
+    let gust_differs =
+        (atis.gust_knots().unwrap_or(atis_speed) - metar.wgst.unwrap_or(metar_speed)).abs()
+            >= WIND_SPEED_TOLERANCE_KNOTS;
+    let direction_differs = match (atis.direction, metar_direction) {
+        (Some(a), Some(m)) if atis_speed >= LIGHT_WIND_KNOTS && metar_speed >= LIGHT_WIND_KNOTS => {
+            angle_between(a, m) >= WIND_DIRECTION_TOLERANCE
+        }
+        _ => false,
+    };
+
+    (speed_differs || gust_differs || direction_differs).then(|| Discrepancy::Wind {
+        atis: wind_string(atis),
+        metar: metar.wind_string(),
+    })
+}
+
+fn angle_between(a: i32, b: i32) -> i32 {
+    let diff = (a - b).rem_euclid(360);
+    diff.min(360 - diff)
+}
+
+/// Same format as `MetarDto::wind_string`, with speeds converted to knots
+fn wind_string(wind: &Wind) -> String {
+    let direction = wind
+        .direction
+        .map_or_else(|| "VRB".to_string(), |d| format!("{d:03}"));
+    let gust = wind
+        .gust_knots()
+        .map(|g| format!("G{g}"))
+        .unwrap_or_default();
+    format!("{direction}{:02}{gust}KT", wind.speed_knots())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::atis::parse_atis_text;
+    use crate::providers::metar_dto_from_raw;
+    use chrono::TimeZone;
+
+    fn metar(raw: &str) -> MetarDto {
+        metar_dto_from_raw(raw, Utc.with_ymd_and_hms(2024, 10, 17, 0, 30, 0).unwrap()).unwrap()
+    }
+
+    fn atis(text: &str) -> AtisInfo {
+        parse_atis_text(&[text.to_string()])
+    }
+
+    #[test]
+    fn flags_altimeter_mismatches() {
+        let metar = metar("KSFO 170056Z 28012KT 10SM FEW010 16/12 A2989");
+        assert_eq!(
+            check_consistency(
+                &[atis(
+                    "SFO ATIS INFO C 0056Z. 28012KT 10SM FEW010 16/12 A2992."
+                )],
+                &metar
+            ),
+            vec![Discrepancy::Altimeter {
+                atis: AltimeterSetting::InHg(29.92),
+                metar: AltimeterSetting::InHg(29.89),
+            }]
+        );
+        assert_eq!(
+            check_consistency(
+                &[atis(
+                    "SFO ATIS INFO C 0056Z. 28012KT 10SM FEW010 16/12 A2989."
+                )],
+                &metar
+            ),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn compares_hpa_within_rounding() {
+        // 29.92 inHg is 1013.2 hPa
+        let metar = metar("KSFO 170056Z 28012KT 10SM FEW010 16/12 A2992");
+        for (text, discrepancies) in [
+            ("INFORMATION DELTA TIME 0056Z. QNH 1013.", vec![]),
+            (
+                "INFORMATION DELTA TIME 0056Z. QNH 1012.",
+                vec![Discrepancy::Altimeter {
+                    atis: AltimeterSetting::Hpa(1012.0),
+                    metar: AltimeterSetting::Hpa(1013.0),
+                }],
+            ),
+        ] {
+            assert_eq!(
+                check_consistency(&[atis(text)], &metar),
+                discrepancies,
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn flags_wind_mismatches() {
+        let metar = metar("KSFO 170056Z 28012G20KT 10SM FEW010 16/12 A2992");
+        for (text, flagged) in [
+            ("SFO ATIS INFO C 0056Z. 28012G20KT A2992.", false),
+            // Within the direction and speed tolerances
+            ("SFO ATIS INFO C 0056Z. 30014G22KT A2992.", false),
+            ("SFO ATIS INFO C 0056Z. 32012G20KT A2992.", true),
+            ("SFO ATIS INFO C 0056Z. 28018G26KT A2992.", true),
+            ("SFO ATIS INFO C 0056Z. 28012KT A2992.", true),
+        ] {
+            let expected = if flagged {
+                vec![Discrepancy::Wind {
+                    atis: wind_string(&atis(text).wind.unwrap()),
+                    metar: "28012G20KT".to_string(),
+                }]
+            } else {
+                vec![]
+            };
+            assert_eq!(check_consistency(&[atis(text)], &metar), expected, "{text}");
+        }
+
+        // Light winds are only compared by speed
+        let metar = self::metar("KSFO 170056Z 09003KT 10SM FEW010 16/12 A2992");
+        assert_eq!(
+            check_consistency(&[atis("SFO ATIS INFO C 0056Z. 27004KT A2992.")], &metar),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn flags_atis_older_than_metar() {
+        let metar = metar("KSFO 170005Z 28012KT 10SM FEW010 16/12 A2992");
+        assert_eq!(
+            check_consistency(&[atis("SFO ATIS INFO B 2356Z. 28012KT A2992.")], &metar),
+            vec![Discrepancy::ObservationTime {
+                atis: Utc.with_ymd_and_hms(2024, 10, 16, 23, 56, 0).unwrap(),
+                metar: Utc.with_ymd_and_hms(2024, 10, 17, 0, 5, 0).unwrap(),
+            }]
+        );
+        for text in [
+            "SFO ATIS INFO C 0005Z. 28012KT A2992.",
+            "SFO ATIS INFO D 0015Z. 28012KT A2992.",
+        ] {
+            assert_eq!(check_consistency(&[atis(text)], &metar), vec![], "{text}");
+        }
+
+        // An ATIS issued just after midnight is newer than a METAR from just before
+        let metar = self::metar("KSFO 162356Z 28012KT 10SM FEW010 16/12 A2992");
+        assert_eq!(
+            check_consistency(&[atis("SFO ATIS INFO C 0005Z. 28012KT A2992.")], &metar),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn reports_each_discrepancy_once() {
+        let metar = metar("KSFO 170056Z 28012KT 10SM FEW010 16/12 A2989");
+        let arrival = atis("SFO ARR INFO C 0056Z. 28012KT A2992.");
+        let departure = atis("SFO DEP INFO C 0056Z. 28012KT A2992.");
+        assert_eq!(check_consistency(&[arrival, departure], &metar).len(), 1);
+    }
+
+    #[test]
+    fn has_no_discrepancies_without_atis() {
+        let metar = metar("KSFO 170056Z 28012KT 10SM FEW010 16/12 A2989");
+        assert_eq!(check_consistency(&[], &metar), vec![]);
+        assert_eq!(check_consistency(&[AtisInfo::default()], &metar), vec![]);
+    }
+}
@@ -0,0 +1,234 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use vatsim_utils::models::{Controller, V3ResponseData};
+
+/// Controller positions shown for a station, in order from the ground up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ControllerPosition {
+    Delivery,
+    Ground,
+    Tower,
+    Approach,
+    Center,
+}
+
+impl ControllerPosition {
+    fn from_callsign(callsign: &str) -> Option<Self> {
+        match callsign.rsplit('_').next()?.to_uppercase().as_str() {
+            "DEL" => Some(Self::Delivery),
+            "GND" => Some(Self::Ground),
+            "TWR" => Some(Self::Tower),
+            "APP" | "DEP" => Some(Self::Approach),
+            "CTR" => Some(Self::Center),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OnlineController {
+    pub callsign: String,
+    pub name: String,
+    pub position: ControllerPosition,
+    pub frequency: String,
+    pub logon_time: Option<DateTime<Utc>>,
+}
+
+impl OnlineController {
+    fn from_controller(controller: &Controller) -> Option<Self> {
+        Some(Self {
+            position: ControllerPosition::from_callsign(&controller.callsign)?,
+            callsign: controller.callsign.clone(),
+            name: controller.name.clone(),
+            frequency: controller.frequency.clone(),
+            logon_time: DateTime::parse_from_rfc3339(&controller.logon_time)
+                .map(|t| t.with_timezone(&Utc))
+                .ok(),
+        })
+    }
+}
+
+/// Callsign prefixes for a station: its ICAO ID, its FAA ID where it has one, as US controllers
+/// usually drop the leading K (e.g. `SFO_TWR` for KSFO), and any prefixes configured in settings
+/// for it, e.g. `NCT` for the approach controller covering several fields
+pub fn station_prefixes(icao_id: &str, faa_id: Option<&str>, configured: &[String]) -> Vec<String> {
+    std::iter::once(icao_id)
+        .chain(faa_id.filter(|f| *f != "-"))
+        .map(ToString::to_string)
+        .chain(configured.iter().cloned())
+        .collect()
+}
+
+/// Controllers whose callsign starts with one of `prefixes`, e.g. `SFO` matches `SFO_TWR` and
+/// `SFO_1_GND` but not `SFOA_TWR`. Sorted by position, then callsign.
+pub fn controllers_for_prefixes(
+    datafeed: &V3ResponseData,
+    prefixes: &[String],
+) -> Vec<OnlineController> {
+    let mut ret = datafeed
+        .controllers
+        .iter()
+        .filter(|c| {
+            c.callsign
+                .split('_')
+                .next()
+                .is_some_and(|p| prefixes.iter().any(|prefix| p.eq_ignore_ascii_case(prefix)))
+        })
+        .filter_map(OnlineController::from_controller)
+        .collect::<Vec<_>>();
+    ret.sort_by(|a, b| {
+        a.position
+            .cmp(&b.position)
+            .then_with(|| a.callsign.cmp(&b.callsign))
+    });
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vatsim_utils::models::GeneralData;
+
+    fn controller(callsign: &str, frequency: &str) -> Controller {
+        Controller {
+            cid: 1_300_001,
+            name: format!("{callsign} Controller"),
+            callsign: callsign.to_string(),
+            frequency: frequency.to_string(),
+            facility: 0,
+            rating: 3,
+            server: "USA-WEST".to_string(),
+            visual_range: 50,
+            text_atis: None,
+            last_updated: "2024-10-16T12:00:00.0000000Z".to_string(),
+            logon_time: "2024-10-16T10:30:00.0000000Z".to_string(),
+        }
+    }
+
+    fn datafeed(controllers: Vec<Controller>) -> V3ResponseData {
+        V3ResponseData {
+            general: GeneralData {
+                version: 3,
+                reload: 1,
+                update: "20241016120000".to_string(),
+                update_timestamp: "2024-10-16T12:00:00.0000000Z".to_string(),
+                connected_clients: 0,
+                unique_users: 0,
+            },
+            pilots: vec![],
+            controllers,
+            atis: vec![],
+            servers: vec![],
+            facilities: vec![],
+            ratings: vec![],
+            pilot_ratings: vec![],
+            military_ratings: vec![],
+        }
+    }
+
+    #[test]
+    fn filters_station_controllers_by_facility() {
+        let datafeed = datafeed(
+            [
+                ("SFO_CTR", "199.998"),
+                ("OAK_36_CTR", "132.900"),
+                ("NCT_APP", "135.650"),
+                ("SFO_DEP", "120.900"),
+                ("SFO_TWR", "120.500"),
+                ("sfo_1_gnd", "121.800"),
+                ("KSFO_DEL", "118.200"),
+                ("SFO_ATIS", "118.850"),
+                ("SFO_OBS", "199.998"),
+                ("SFO_M_OBS", "199.998"),
+                ("SFO_SUP", "199.998"),
+                ("SFOA_TWR", "124.000"),
+                ("OAK_TWR", "118.300"),
+                ("SFO", "199.998"),
+            ]
+            .into_iter()
+            .map(|(callsign, frequency)| controller(callsign, frequency))
+            .collect(),
+        );
+
+        let callsigns = |prefixes: &[String]| {
+            controllers_for_prefixes(&datafeed, prefixes)
+                .into_iter()
+                .map(|c| (c.callsign, c.position))
+                .collect::<Vec<_>>()
+        };
+        let owned = |values: &[(&str, ControllerPosition)]| {
+            values
+                .iter()
+                .map(|(c, p)| ((*c).to_string(), *p))
+                .collect::<Vec<_>>()
+        };
+
+        for (configured, expected) in [
+            (
+                vec![],
+                owned(&[
+                    ("KSFO_DEL", ControllerPosition::Delivery),
+                    ("sfo_1_gnd", ControllerPosition::Ground),
+                    ("SFO_TWR", ControllerPosition::Tower),
+                    ("SFO_DEP", ControllerPosition::Approach),
+                    ("SFO_CTR", ControllerPosition::Center),
+                ]),
+            ),
+            (
+                vec!["NCT".to_string()],
+                owned(&[
+                    ("KSFO_DEL", ControllerPosition::Delivery),
+                    ("sfo_1_gnd", ControllerPosition::Ground),
+                    ("SFO_TWR", ControllerPosition::Tower),
+                    ("NCT_APP", ControllerPosition::Approach),
+                    ("SFO_DEP", ControllerPosition::Approach),
+                    ("SFO_CTR", ControllerPosition::Center),
+                ]),
+            ),
+            (
+                vec!["oak".to_string()],
+                owned(&[
+                    ("KSFO_DEL", ControllerPosition::Delivery),
+                    ("sfo_1_gnd", ControllerPosition::Ground),
+                    ("OAK_TWR", ControllerPosition::Tower),
+                    ("SFO_TWR", ControllerPosition::Tower),
+                    ("SFO_DEP", ControllerPosition::Approach),
+                    ("OAK_36_CTR", ControllerPosition::Center),
+                    ("SFO_CTR", ControllerPosition::Center),
+                ]),
+            ),
+        ] {
+            assert_eq!(
+                callsigns(&station_prefixes("KSFO", Some("SFO"), &configured)),
+                expected,
+                "{configured:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn builds_prefixes_from_station_ids_and_settings() {
+        assert_eq!(station_prefixes("KSFO", Some("SFO"), &[]), ["KSFO", "SFO"]);
+        assert_eq!(station_prefixes("EGLL", Some("-"), &[]), ["EGLL"]);
+        assert_eq!(
+            station_prefixes("EGLL", None, &["LON".to_string()]),
+            ["EGLL", "LON"]
+        );
+    }
+
+    #[test]
+    fn reads_controller_details() {
+        let online = OnlineController::from_controller(&controller("SFO_TWR", "120.500")).unwrap();
+        assert_eq!(
+            online,
+            OnlineController {
+                callsign: "SFO_TWR".to_string(),
+                name: "SFO_TWR Controller".to_string(),
+                position: ControllerPosition::Tower,
+                frequency: "120.500".to_string(),
+                logon_time: "2024-10-16T10:30:00Z".parse().ok(),
+            }
+        );
+    }
+}
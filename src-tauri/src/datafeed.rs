@@ -0,0 +1,177 @@
+use anyhow::anyhow;
+use log::debug;
+use reqwest::Client;
+use serde::Deserialize;
+use vatsim_utils::models::{FlightPlan, V3ResponseData};
+
+const VATSIM_STATUS_URL: &str = "https://status.vatsim.net/status.json";
+
+#[derive(Debug, Deserialize)]
+struct VatsimStatus {
+    data: VatsimStatusData,
+}
+
+#[derive(Debug, Deserialize)]
+struct VatsimStatusData {
+    v3: Vec<String>,
+}
+
+/// Flight plan filed by a pilot who has not connected yet
+#[derive(Debug, Clone, Deserialize)]
+pub struct Prefile {
+    pub cid: u64,
+    pub callsign: String,
+    pub flight_plan: Option<FlightPlan>,
+}
+
+/// The VATSIM v3 datafeed, including the `prefiles` that `V3ResponseData` leaves out
+#[derive(Debug, Deserialize)]
+pub struct VatsimDatafeed {
+    #[serde(flatten)]
+    pub data: V3ResponseData,
+    #[serde(default)]
+    pub prefiles: Vec<Prefile>,
+}
+
+/// Downloads the VATSIM datafeed from the URL listed in the network status file
+pub struct VatsimDatafeedClient {
+    client: Client,
+    url: String,
+}
+
+impl VatsimDatafeedClient {
+    pub async fn new() -> Result<Self, anyhow::Error> {
+        Self::from_status_url(VATSIM_STATUS_URL).await
+    }
+
+    pub async fn from_status_url(status_url: &str) -> Result<Self, anyhow::Error> {
+        let client = Client::builder().build()?;
+        let status: VatsimStatus = client
+            .get(status_url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let url = status
+            .data
+            .v3
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("No VATSIM datafeed URL in {status_url}"))?;
+        debug!("VATSIM datafeed URL: {url}");
+
+        Ok(Self { client, url })
+    }
+
+    /// Downloads the current datafeed, with pilots and controllers sorted by callsign
+    pub async fn fetch(&self) -> Result<VatsimDatafeed, anyhow::Error> {
+        let mut datafeed: VatsimDatafeed = self
+            .client
+            .get(&self.url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        datafeed
+            .data
+            .pilots
+            .sort_by(|a, b| a.callsign.cmp(&b.callsign));
+        datafeed
+            .data
+            .controllers
+            .sort_by(|a, b| a.callsign.cmp(&b.callsign));
+        Ok(datafeed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::MockServer;
+    use serde_json::json;
+
+    fn datafeed_json() -> String {
+        json!({
+            "general": {
+                "version": 3,
+                "reload": 1,
+                "update": "20241016120000",
+                "update_timestamp": "2024-10-16T12:00:00.0000000Z",
+                "connected_clients": 0,
+                "unique_users": 0
+            },
+            "pilots": [],
+            "controllers": [],
+            "atis": [],
+            "servers": [],
+            "prefiles": [{
+                "cid": 1_234_567,
+                "name": "Test Pilot",
+                "callsign": "UAL123",
+                "flight_plan": {
+                    "flight_rules": "I",
+                    "aircraft": "B738/M-SDE2E3FGHIRWXY/LB1",
+                    "aircraft_faa": "B738/L",
+                    "aircraft_short": "B738",
+                    "departure": "KSFO",
+                    "arrival": "KLAX",
+                    "alternate": "",
+                    "cruise_tas": "450",
+                    "altitude": "35000",
+                    "deptime": "1300",
+                    "enroute_time": "0115",
+                    "fuel_time": "0300",
+                    "remarks": "/V/",
+                    "route": "SSTIK5 SNS",
+                    "revision_id": 1,
+                    "assigned_transponder": "0000"
+                },
+                "last_updated": "2024-10-16T11:55:00.0000000Z"
+            }],
+            "facilities": [],
+            "ratings": [],
+            "pilot_ratings": [],
+            "military_ratings": []
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn reads_datafeed_with_prefiles_from_status_url() {
+        let server = MockServer::start(|path| match path {
+            "/v3/vatsim-data.json" => (200, datafeed_json()),
+            _ => (404, String::new()),
+        })
+        .await;
+        let status = json!({"data": {"v3": [format!("{}/v3/vatsim-data.json", server.url())]}});
+        let status_server = MockServer::start(move |_| (200, status.to_string())).await;
+
+        let client =
+            VatsimDatafeedClient::from_status_url(&format!("{}/status.json", status_server.url()))
+                .await
+                .unwrap();
+        let datafeed = client.fetch().await.unwrap();
+
+        assert_eq!(
+            datafeed.data.general.update_timestamp,
+            "2024-10-16T12:00:00.0000000Z"
+        );
+        assert_eq!(datafeed.prefiles.len(), 1);
+        assert_eq!(datafeed.prefiles[0].callsign, "UAL123");
+        assert_eq!(
+            datafeed.prefiles[0].flight_plan.as_ref().unwrap().departure,
+            "KSFO"
+        );
+        assert_eq!(server.requests(), ["/v3/vatsim-data.json"]);
+    }
+
+    #[tokio::test]
+    async fn fails_without_datafeed_url() {
+        let server = MockServer::start(|_| (200, json!({"data": {"v3": []}}).to_string())).await;
+        assert!(VatsimDatafeedClient::from_status_url(&server.url())
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,370 @@
+use crate::awc::MetarDto;
+use crate::metar::METERS_PER_STATUTE_MILE;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum FlightCategory {
+    #[serde(rename = "LIFR")]
+    LowInstrument,
+    #[serde(rename = "IFR")]
+    Instrument,
+    #[serde(rename = "MVFR")]
+    MarginalVisual,
+    #[serde(rename = "VFR")]
+    Visual,
+}
+
+impl FlightCategory {
+    /// Abbreviation as used by AWC, e.g. `MVFR`
+    pub const fn code(self) -> &'static str {
+        match self {
+            Self::LowInstrument => "LIFR",
+            Self::Instrument => "IFR",
+            Self::MarginalVisual => "MVFR",
+            Self::Visual => "VFR",
+        }
+    }
+}
+
+/// Threshold set used to categorise an observation
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FlightCategoryRules {
+    /// FAA thresholds: ceiling in feet AGL and visibility in statute miles
+    #[default]
+    Faa,
+    /// ICAO-style thresholds in metres, where `CAVOK` is always VFR. The levels are set with
+    /// `icaoFlightCategoryLevels` in the settings.
+    Icao,
+}
+
+/// Lowest ceiling and visibility of a category
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryMinimums {
+    pub ceiling_ft: f64,
+    pub visibility_m: f64,
+}
+
+/// Minimums for VFR, MVFR and IFR under the ICAO rules, anything lower is LIFR
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IcaoCategoryLevels {
+    pub vfr: CategoryMinimums,
+    pub mvfr: CategoryMinimums,
+    pub ifr: CategoryMinimums,
+}
+
+impl IcaoCategoryLevels {
+    pub const DEFAULT: Self = Self {
+        vfr: CategoryMinimums {
+            ceiling_ft: 1500.0,
+            visibility_m: 5000.0,
+        },
+        mvfr: CategoryMinimums {
+            ceiling_ft: 1000.0,
+            visibility_m: 3000.0,
+        },
+        ifr: CategoryMinimums {
+            ceiling_ft: 500.0,
+            visibility_m: 1500.0,
+        },
+    };
+}
+
+impl Default for IcaoCategoryLevels {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// The thresholds of a rule set, resolved from the settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlightCategoryThresholds {
+    /// Minimum ceiling (ft) and visibility for VFR, MVFR and IFR, from highest to lowest
+    levels: [(f64, f64, FlightCategory); 3],
+    visibility_in_meters: bool,
+    /// FAA VFR requires a ceiling and visibility strictly above its minimums
+    strict_visual: bool,
+    cavok_is_visual: bool,
+}
+
+impl FlightCategoryRules {
+    pub const fn thresholds(self, icao: &IcaoCategoryLevels) -> FlightCategoryThresholds {
+        match self {
+            Self::Faa => FlightCategoryThresholds {
+                levels: [
+                    (3000.0, 5.0, FlightCategory::Visual),
+                    (1000.0, 3.0, FlightCategory::MarginalVisual),
+                    (500.0, 1.0, FlightCategory::Instrument),
+                ],
+                visibility_in_meters: false,
+                strict_visual: true,
+                cavok_is_visual: false,
+            },
+            Self::Icao => FlightCategoryThresholds {
+                levels: [
+                    (
+                        icao.vfr.ceiling_ft,
+                        icao.vfr.visibility_m,
+                        FlightCategory::Visual,
+                    ),
+                    (
+                        icao.mvfr.ceiling_ft,
+                        icao.mvfr.visibility_m,
+                        FlightCategory::MarginalVisual,
+                    ),
+                    (
+                        icao.ifr.ceiling_ft,
+                        icao.ifr.visibility_m,
+                        FlightCategory::Instrument,
+                    ),
+                ],
+                visibility_in_meters: true,
+                strict_visual: false,
+                cavok_is_visual: true,
+            },
+        }
+    }
+}
+
+impl Default for FlightCategoryThresholds {
+    fn default() -> Self {
+        FlightCategoryRules::default().thresholds(&IcaoCategoryLevels::DEFAULT)
+    }
+}
+
+pub fn flight_category(
+    metar: &MetarDto,
+    thresholds: &FlightCategoryThresholds,
+) -> Option<FlightCategory> {
+    if thresholds.cavok_is_visual && metar.is_cavok() {
+        return Some(FlightCategory::Visual);
+    }
+
+    let visibility = metar.visibility_sm().map(|v| {
+        if thresholds.visibility_in_meters {
+            v * METERS_PER_STATUTE_MILE
+        } else {
+            v
+        }
+    });
+    let ceiling = metar.ceiling_ft().map(f64::from);
+
+    // Without any visibility or ceiling information there is nothing to categorise
+    if visibility.is_none() && ceiling.is_none() {
+        return None;
+    }
+
+    // The lower categories are inclusive of their minimums
+    let meets = |min_ceiling: f64, min_vis: f64, category: FlightCategory| {
+        let strict = thresholds.strict_visual && category == FlightCategory::Visual;
+        let ceiling_ok = ceiling.is_none_or(|c| {
+            if strict {
+                c > min_ceiling
+            } else {
+                c >= min_ceiling
+            }
+        });
+        let vis_ok = visibility.is_none_or(|v| if strict { v > min_vis } else { v >= min_vis });
+        ceiling_ok && vis_ok
+    };
+
+    Some(
+        thresholds
+            .levels
+            .iter()
+            .find(|(c, v, category)| meets(*c, *v, *category))
+            .map_or(FlightCategory::LowInstrument, |(_, _, category)| *category),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::awc::{Cloud, StringOrF64};
+    use crate::providers::metar_dto_from_raw;
+    use chrono::{TimeZone, Utc};
+
+    fn metar(raw: &str) -> MetarDto {
+        metar_dto_from_raw(raw, Utc.with_ymd_and_hms(2024, 10, 16, 13, 0, 0).unwrap()).unwrap()
+    }
+
+    fn observation(visib: StringOrF64, clouds: &[(&str, i32)], vert_vis: Option<i32>) -> MetarDto {
+        MetarDto {
+            visib,
+            clouds: clouds
+                .iter()
+                .map(|(cover, base)| Cloud {
+                    cover: (*cover).to_string(),
+                    base: Some(*base),
+                })
+                .collect(),
+            vert_vis,
+            ..metar("KSFO 161256Z 28012KT 16/12 A2992")
+        }
+    }
+
+    fn sm(v: f64) -> StringOrF64 {
+        StringOrF64::F64(v)
+    }
+
+    fn faa() -> FlightCategoryThresholds {
+        FlightCategoryRules::Faa.thresholds(&IcaoCategoryLevels::DEFAULT)
+    }
+
+    fn icao() -> FlightCategoryThresholds {
+        FlightCategoryRules::Icao.thresholds(&IcaoCategoryLevels::DEFAULT)
+    }
+
+    #[test]
+    fn categorises_faa_boundaries() {
+        for (visib, clouds, vert_vis, category) in [
+            (
+                sm(10.0),
+                vec![("BKN", 3000)],
+                None,
+                FlightCategory::MarginalVisual,
+            ),
+            (sm(10.0), vec![("BKN", 3100)], None, FlightCategory::Visual),
+            (
+                sm(5.0),
+                vec![("FEW", 10000)],
+                None,
+                FlightCategory::MarginalVisual,
+            ),
+            (
+                StringOrF64::String("10+".to_string()),
+                vec![("SCT", 5000)],
+                None,
+                FlightCategory::Visual,
+            ),
+            (
+                sm(3.0),
+                vec![("OVC", 1000)],
+                None,
+                FlightCategory::MarginalVisual,
+            ),
+            (
+                sm(2.5),
+                vec![("OVC", 1000)],
+                None,
+                FlightCategory::Instrument,
+            ),
+            (
+                sm(10.0),
+                vec![("OVC", 900)],
+                None,
+                FlightCategory::Instrument,
+            ),
+            (sm(1.0), vec![], None, FlightCategory::Instrument),
+            (
+                sm(10.0),
+                vec![("OVC", 400)],
+                None,
+                FlightCategory::LowInstrument,
+            ),
+            (sm(0.75), vec![], None, FlightCategory::LowInstrument),
+            // Scattered layers are not a ceiling, the lowest broken one is
+            (
+                sm(10.0),
+                vec![("SCT", 400), ("BKN", 2500)],
+                None,
+                FlightCategory::MarginalVisual,
+            ),
+        ] {
+            let metar = observation(visib, &clouds, vert_vis);
+            assert_eq!(
+                flight_category(&metar, &faa()),
+                Some(category),
+                "{:?} {clouds:?}",
+                metar.visib
+            );
+        }
+    }
+
+    #[test]
+    fn uses_vertical_visibility_as_ceiling() {
+        let empty = || StringOrF64::String(String::new());
+        assert_eq!(
+            flight_category(&observation(empty(), &[], Some(200)), &faa()),
+            Some(FlightCategory::LowInstrument)
+        );
+        assert_eq!(
+            flight_category(&observation(empty(), &[], Some(500)), &faa()),
+            Some(FlightCategory::Instrument)
+        );
+        assert_eq!(
+            flight_category(&observation(sm(10.0), &[("OVC", 1500)], Some(800)), &faa()),
+            Some(FlightCategory::Instrument)
+        );
+    }
+
+    #[test]
+    fn has_no_category_without_ceiling_or_visibility() {
+        let metar = observation(StringOrF64::String(String::new()), &[], None);
+        assert_eq!(flight_category(&metar, &faa()), None);
+        assert_eq!(flight_category(&metar, &icao()), None);
+    }
+
+    #[test]
+    fn categorises_icao_observations() {
+        let cavok = metar("EGLL 161250Z 25014KT CAVOK 14/08 Q1013");
+        assert_eq!(
+            flight_category(&cavok, &icao()),
+            Some(FlightCategory::Visual)
+        );
+
+        for (raw, category) in [
+            (
+                "EGLL 161250Z 25014KT 9999 BKN015 14/08 Q1013",
+                FlightCategory::Visual,
+            ),
+            (
+                "EGLL 161250Z 25014KT 4000 BR BKN015 14/08 Q1013",
+                FlightCategory::MarginalVisual,
+            ),
+            (
+                "EGLL 161250Z 25014KT 9999 BKN008 14/08 Q1013",
+                FlightCategory::Instrument,
+            ),
+            (
+                "EGLL 161250Z 25014KT 1200 BR OVC004 14/08 Q1013",
+                FlightCategory::LowInstrument,
+            ),
+        ] {
+            assert_eq!(
+                flight_category(&metar(raw), &icao()),
+                Some(category),
+                "{raw}"
+            );
+        }
+    }
+
+    #[test]
+    fn uses_configured_icao_levels() {
+        let levels = IcaoCategoryLevels {
+            vfr: CategoryMinimums {
+                ceiling_ft: 3000.0,
+                visibility_m: 8000.0,
+            },
+            ..IcaoCategoryLevels::DEFAULT
+        };
+        let thresholds = FlightCategoryRules::Icao.thresholds(&levels);
+
+        let metar = metar("EGLL 161250Z 25014KT 9999 BKN020 14/08 Q1013");
+        assert_eq!(
+            flight_category(&metar, &icao()),
+            Some(FlightCategory::Visual)
+        );
+        assert_eq!(
+            flight_category(&metar, &thresholds),
+            Some(FlightCategory::MarginalVisual)
+        );
+
+        let levels: IcaoCategoryLevels = serde_json::from_str(
+            r#"{"vfr":{"ceilingFt":3000,"visibilityM":8000},"mvfr":{"ceilingFt":1000,"visibilityM":3000},"ifr":{"ceilingFt":500,"visibilityM":1500}}"#,
+        )
+        .unwrap();
+        assert_eq!(FlightCategoryRules::Icao.thresholds(&levels), thresholds);
+    }
+}
@@ -0,0 +1,326 @@
+use crate::awc::MetarDto;
+use crate::utils;
+use anyhow::anyhow;
+use chrono::{TimeDelta, Utc};
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Observations older than this are dropped from memory as new ones arrive, and from the file
+/// when it is compacted
+const RETENTION_HOURS: i64 = 24 * 7;
+
+/// Lines the file may hold beyond the retained observations before it is rewritten
+const COMPACT_AFTER_STALE_LINES: usize = 500;
+
+/// Every distinct METAR seen for each station, persisted as one JSON object per line in
+/// `metar_history.jsonl` so new observations can be appended without rewriting the file
+pub struct MetarHistory {
+    /// Defaults to `metar_history.jsonl` in the config directory
+    path: Option<PathBuf>,
+    records: Mutex<Option<HistoryRecords>>,
+}
+
+#[derive(Default)]
+struct HistoryRecords {
+    stations: HashMap<String, Vec<MetarDto>>,
+    /// Lines in the history file, including those since pruned from `stations`
+    file_lines: usize,
+}
+
+impl HistoryRecords {
+    fn len(&self) -> usize {
+        self.stations.values().map(Vec::len).sum()
+    }
+
+    fn prune(&mut self) {
+        let cutoff = Utc::now() - TimeDelta::hours(RETENTION_HOURS);
+        for station in self.stations.values_mut() {
+            let expired = station.partition_point(|m| m.obs_time < cutoff);
+            station.drain(..expired);
+        }
+        self.stations.retain(|_, station| !station.is_empty());
+    }
+}
+
+impl MetarHistory {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            path: None,
+            records: Mutex::new(None),
+        }
+    }
+
+    #[cfg(test)]
+    const fn with_path(path: PathBuf) -> Self {
+        Self {
+            path: Some(path),
+            records: Mutex::new(None),
+        }
+    }
+
+    fn path(&self) -> Option<PathBuf> {
+        self.path.clone().or_else(history_path)
+    }
+
+    /// Adds the observations not already recorded for their station and `obs_time`
+    pub fn record(&self, metars: &[MetarDto]) {
+        let path = self.path();
+        let mut guard = self.records.lock().unwrap();
+        let records = guard.get_or_insert_with(|| load_history(path.as_deref()));
+
+        let new = metars
+            .iter()
+            .filter(|m| {
+                let station = records
+                    .stations
+                    .entry(m.icao_id.to_uppercase())
+                    .or_default();
+                if station.iter().any(|h| h.obs_time == m.obs_time) {
+                    return false;
+                }
+                let index = station.partition_point(|h| h.obs_time < m.obs_time);
+                station.insert(index, (*m).clone());
+                true
+            })
+            .collect::<Vec<_>>();
+        records.prune();
+
+        if !new.is_empty() {
+            debug!("Recording {} new METARs in history", new.len());
+            // Keep the lock while writing so concurrent writes stay in order
+            let Some(path) = path else {
+                warn!("Could not construct path to metar_history.jsonl");
+                return;
+            };
+            if records.file_lines + new.len() > records.len() + COMPACT_AFTER_STALE_LINES {
+                debug!("Compacting METAR history at {path:?}");
+                let all = records.stations.values().flatten().collect::<Vec<_>>();
+                match write_history_file(&path, &all) {
+                    Ok(()) => records.file_lines = all.len(),
+                    Err(e) => warn!("Could not compact METAR history: {e:?}"),
+                }
+            } else {
+                match append_to_history_file(&path, &new) {
+                    Ok(()) => records.file_lines += new.len(),
+                    Err(e) => warn!("Could not write METAR history: {e:?}"),
+                }
+            }
+        }
+        drop(guard);
+    }
+
+    /// Observations for a station from the last `hours`, oldest first
+    pub fn query(&self, station: &str, hours: u32) -> Vec<MetarDto> {
+        let since = Utc::now() - TimeDelta::hours(i64::from(hours).min(RETENTION_HOURS));
+        let path = self.path();
+        let mut records = self.records.lock().unwrap();
+        records
+            .get_or_insert_with(|| load_history(path.as_deref()))
+            .stations
+            .get(&station.to_uppercase())
+            .map(|h| h.iter().filter(|m| m.obs_time >= since).cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+impl Default for MetarHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    dirs::config_local_dir().map(|p| p.join("Mini METARs").join("metar_history.jsonl"))
+}
+
+/// Reads the history file, skipping unreadable lines, and rewrites it if anything was pruned
+fn load_history(path: Option<&Path>) -> HistoryRecords {
+    let mut records = HistoryRecords::default();
+    let Some(path) = path else {
+        debug!("Could not construct path to metar_history.jsonl");
+        return records;
+    };
+    let file = match File::open(path) {
+        Ok(f) => f,
+        Err(e) => {
+            debug!("No METAR history read from {path:?}: {e}");
+            return records;
+        }
+    };
+
+    for line in BufReader::new(file).lines().map_while(Result::ok) {
+        records.file_lines += 1;
+        match serde_json::from_str::<MetarDto>(&line) {
+            Ok(m) => {
+                records
+                    .stations
+                    .entry(m.icao_id.to_uppercase())
+                    .or_default()
+                    .push(m);
+            }
+            Err(e) => debug!("Skipping unreadable METAR history line: {e}"),
+        }
+    }
+
+    for station in records.stations.values_mut() {
+        station.sort_by_key(|m| m.obs_time);
+        station.dedup_by_key(|m| m.obs_time);
+    }
+    records.prune();
+    let kept = records.len();
+    debug!("Loaded {kept} METARs from history at {path:?}");
+
+    if kept < records.file_lines {
+        let all = records.stations.values().flatten().collect::<Vec<_>>();
+        match write_history_file(path, &all) {
+            Ok(()) => records.file_lines = kept,
+            Err(e) => warn!("Could not prune METAR history: {e:?}"),
+        }
+    }
+
+    records
+}
+
+fn write_history_file(path: &Path, metars: &[&MetarDto]) -> Result<(), anyhow::Error> {
+    write_lines(File::create(path)?, metars)
+}
+
+fn append_to_history_file(path: &Path, metars: &[&MetarDto]) -> Result<(), anyhow::Error> {
+    path.parent()
+        .and_then(utils::get_or_create_path)
+        .ok_or_else(|| anyhow!("Could not create directory for {path:?}"))?;
+
+    write_lines(
+        OpenOptions::new().create(true).append(true).open(path)?,
+        metars,
+    )
+}
+
+fn write_lines(file: File, metars: &[&MetarDto]) -> Result<(), anyhow::Error> {
+    let mut writer = BufWriter::new(file);
+    for metar in metars {
+        serde_json::to_writer(&mut writer, metar)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::metar_dto_from_raw;
+    use crate::test_utils::TempDir;
+    use chrono::DateTime;
+    use std::fs;
+    use std::sync::OnceLock;
+
+    /// Fixed for all tests, so the same `hours_ago` gives the same observation
+    fn now() -> DateTime<Utc> {
+        static NOW: OnceLock<DateTime<Utc>> = OnceLock::new();
+        *NOW.get_or_init(Utc::now)
+    }
+
+    fn metar(station: &str, hours_ago: i64) -> MetarDto {
+        let obs_time = now() - TimeDelta::hours(hours_ago);
+        MetarDto {
+            icao_id: station.to_string(),
+            obs_time,
+            ..metar_dto_from_raw(
+                &format!(
+                    "{station} {}Z 28012KT 10SM FEW010 16/12 A2992",
+                    obs_time.format("%d%H%M")
+                ),
+                obs_time,
+            )
+            .unwrap()
+        }
+    }
+
+    fn file_lines(path: &Path) -> usize {
+        fs::read_to_string(path).unwrap().lines().count()
+    }
+
+    #[test]
+    fn records_each_observation_once_in_order() {
+        let dir = TempDir::new("history-dedup");
+        let path = dir.path().join("metar_history.jsonl");
+        let history = MetarHistory::with_path(path.clone());
+
+        history.record(&[metar("KSFO", 1), metar("KSFO", 3), metar("KOAK", 2)]);
+        // Repeated polls return the same observation, possibly with a different station case
+        history.record(&[metar("KSFO", 1), metar("ksfo", 3), metar("KSFO", 2)]);
+
+        let times = history
+            .query("ksfo", 24)
+            .iter()
+            .map(|m| m.obs_time)
+            .collect::<Vec<_>>();
+        assert_eq!(times.len(), 3);
+        assert!(times.windows(2).all(|t| t[0] < t[1]));
+        assert_eq!(history.query("KOAK", 24).len(), 1);
+        assert_eq!(file_lines(&path), 4);
+
+        // A new instance reads back what was written
+        let reloaded = MetarHistory::with_path(path);
+        assert_eq!(reloaded.query("KSFO", 24), history.query("KSFO", 24));
+    }
+
+    #[test]
+    fn queries_the_requested_window() {
+        let dir = TempDir::new("history-query");
+        let history = MetarHistory::with_path(dir.path().join("metar_history.jsonl"));
+        history.record(&[metar("KSFO", 1), metar("KSFO", 5), metar("KSFO", 30)]);
+
+        for (hours, count) in [(0, 0), (2, 1), (6, 2), (48, 3), (u32::MAX, 3)] {
+            assert_eq!(history.query("KSFO", hours).len(), count, "{hours}");
+        }
+        assert!(history.query("KLAX", 48).is_empty());
+    }
+
+    #[test]
+    fn drops_observations_past_retention() {
+        let dir = TempDir::new("history-retention");
+        let path = dir.path().join("metar_history.jsonl");
+        let expired = metar("KSFO", RETENTION_HOURS + 1);
+        let history = MetarHistory::with_path(path.clone());
+        history.record(&[expired.clone(), metar("KSFO", 1)]);
+        assert_eq!(history.query("KSFO", u32::MAX).len(), 1);
+
+        // Pruned from the file when it is next loaded
+        let kept = metar("KSFO", 2);
+        write_history_file(&path, &[&expired, &kept]).unwrap();
+        let reloaded = MetarHistory::with_path(path.clone());
+        assert_eq!(reloaded.query("KSFO", u32::MAX), vec![kept]);
+        assert_eq!(file_lines(&path), 1);
+    }
+
+    #[test]
+    fn compacts_the_file_once_enough_lines_are_stale() {
+        let dir = TempDir::new("history-compact");
+        let path = dir.path().join("metar_history.jsonl");
+        let history = MetarHistory::with_path(path.clone());
+        history.record(&[metar("KSFO", 1)]);
+
+        // Duplicates of an observation already recorded, e.g. written by another instance
+        let duplicate = metar("KSFO", 1);
+        let add_stale_lines = |count: usize| {
+            append_to_history_file(&path, &vec![&duplicate; count]).unwrap();
+            history.records.lock().unwrap().as_mut().unwrap().file_lines += count;
+        };
+
+        add_stale_lines(COMPACT_AFTER_STALE_LINES);
+        history.record(&[metar("KSFO", 2)]);
+        assert_eq!(file_lines(&path), COMPACT_AFTER_STALE_LINES + 2);
+
+        add_stale_lines(1);
+        history.record(&[metar("KSFO", 3)]);
+        assert_eq!(file_lines(&path), 3);
+        assert_eq!(history.query("KSFO", 24).len(), 3);
+    }
+}
@@ -0,0 +1,281 @@
+use anyhow::bail;
+use serde_json::Value;
+use std::path::Path;
+
+/// Header cells skipped when importing a CSV list
+const LIST_HEADERS: &[&str] = &["ID", "ICAO", "FAA", "STATION", "AIRPORT", "IDENT"];
+
+/// Controller client files station lists can be imported from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// vATIS profile export, stations listed by `identifier`
+    VatisProfile,
+    /// Sector file (`.sct`) or sector file extension (`.ese`)
+    EuroScope,
+    /// One ID per line or comma separated, optionally with more CSV columns after the ID
+    List,
+}
+
+impl ImportFormat {
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .as_deref()
+        {
+            Some("json") => Self::VatisProfile,
+            Some("sct" | "ese") => Self::EuroScope,
+            _ => Self::List,
+        }
+    }
+}
+
+/// Station IDs found in `contents`, uppercase and without duplicates, in file order
+pub fn parse_station_ids(
+    format: ImportFormat,
+    contents: &str,
+) -> Result<Vec<String>, anyhow::Error> {
+    let ids = match format {
+        ImportFormat::VatisProfile => vatis_station_ids(contents)?,
+        ImportFormat::EuroScope => euroscope_station_ids(contents),
+        ImportFormat::List => list_station_ids(contents),
+    };
+
+    let mut unique = Vec::with_capacity(ids.len());
+    for id in ids.into_iter().map(|id| id.to_uppercase()) {
+        if !unique.contains(&id) {
+            unique.push(id);
+        }
+    }
+    if unique.is_empty() {
+        bail!("No station IDs found");
+    }
+    Ok(unique)
+}
+
+/// vATIS 4 lists `stations`, earlier versions `composites`, each with an `identifier`. Keys are
+/// matched case-insensitively, as the casing changed between versions.
+fn vatis_station_ids(contents: &str) -> Result<Vec<String>, anyhow::Error> {
+    let profile: Value = serde_json::from_str(contents)?;
+    let Some(profile) = profile.as_object() else {
+        bail!("vATIS profile is not a JSON object");
+    };
+
+    Ok(profile
+        .iter()
+        .filter(|(k, _)| k.eq_ignore_ascii_case("stations") || k.eq_ignore_ascii_case("composites"))
+        .filter_map(|(_, v)| v.as_array())
+        .flatten()
+        .filter_map(Value::as_object)
+        .filter_map(|station| {
+            station
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case("identifier"))
+                .and_then(|(_, v)| v.as_str())
+                .map(ToString::to_string)
+        })
+        .collect())
+}
+
+/// Airports from the `[AIRPORT]` section of a sector file, and from the `SID`/`STAR` lines of
+/// the `[SIDSSTARS]` section of an extension file
+fn euroscope_station_ids(contents: &str) -> Vec<String> {
+    let mut section = String::new();
+    let mut ids = vec![];
+
+    for line in contents.lines() {
+        let line = line.split(';').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            section = line.to_uppercase();
+            continue;
+        }
+
+        let id = match section.as_str() {
+            "[AIRPORT]" => line.split_whitespace().next(),
+            "[SIDSSTARS]" => line
+                .split(':')
+                .nth(1)
+                .filter(|_| line.starts_with("SID:") || line.starts_with("STAR:")),
+            _ => None,
+        };
+        if let Some(id) = id.map(str::trim).filter(|id| is_station_id(id)) {
+            ids.push(id.to_string());
+        }
+    }
+
+    ids
+}
+
+fn list_station_ids(contents: &str) -> Vec<String> {
+    let lines: Vec<&str> = contents
+        .lines()
+        .map(|l| l.split('#').next().unwrap_or_default())
+        .filter(|l| !l.trim().is_empty())
+        .collect();
+    let is_csv = is_csv(&lines);
+
+    lines
+        .into_iter()
+        .flat_map(|line| {
+            // CSV rows have the ID in the first column, plain lists may have several IDs per line
+            if is_csv {
+                line.split(',').take(1).collect::<Vec<_>>()
+            } else {
+                line.split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+                    .collect()
+            }
+        })
+        .map(|id| id.trim().trim_matches('"'))
+        .filter(|id| is_station_id(id) && !LIST_HEADERS.contains(&id.to_uppercase().as_str()))
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// A list is read as CSV if it starts with a header row, or if every row has columns after the
+/// first that are not station IDs. Otherwise commas just separate IDs.
+fn is_csv(lines: &[&str]) -> bool {
+    let rows: Vec<Vec<&str>> = lines
+        .iter()
+        .map(|l| l.split(',').map(|c| c.trim().trim_matches('"')).collect())
+        .collect();
+    let Some(first) = rows.first() else {
+        return false;
+    };
+
+    let has_header = first.len() > 1 && LIST_HEADERS.contains(&first[0].to_uppercase().as_str());
+    has_header
+        || rows.iter().all(|row| {
+            row.iter()
+                .skip(1)
+                .any(|c| !c.is_empty() && !is_station_id(c))
+        })
+}
+
+fn is_station_id(id: &str) -> bool {
+    (3..=4).contains(&id.len()) && id.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(format: ImportFormat, contents: &str) -> Vec<String> {
+        parse_station_ids(format, contents).unwrap()
+    }
+
+    #[test]
+    fn detects_format_from_extension() {
+        assert_eq!(
+            ImportFormat::from_path(Path::new("EDDF.json")),
+            ImportFormat::VatisProfile
+        );
+        assert_eq!(
+            ImportFormat::from_path(Path::new("London.SCT")),
+            ImportFormat::EuroScope
+        );
+        assert_eq!(
+            ImportFormat::from_path(Path::new("London.ese")),
+            ImportFormat::EuroScope
+        );
+        assert_eq!(
+            ImportFormat::from_path(Path::new("stations.csv")),
+            ImportFormat::List
+        );
+        assert_eq!(
+            ImportFormat::from_path(Path::new("stations")),
+            ImportFormat::List
+        );
+    }
+
+    #[test]
+    fn reads_vatis_stations() {
+        let profile = r#"{"name":"NorCal","stations":[{"identifier":"KSFO","name":"San Francisco"},{"identifier":"koak"},{"name":"No identifier"}]}"#;
+        assert_eq!(ids(ImportFormat::VatisProfile, profile), ["KSFO", "KOAK"]);
+    }
+
+    #[test]
+    fn reads_legacy_vatis_composites() {
+        let profile = r#"{"Name":"NorCal","Composites":[{"Identifier":"KSJC"},{"Identifier":"KSFO"},{"Identifier":"KSJC"}]}"#;
+        assert_eq!(ids(ImportFormat::VatisProfile, profile), ["KSJC", "KSFO"]);
+    }
+
+    #[test]
+    fn rejects_invalid_vatis_profiles() {
+        assert!(parse_station_ids(ImportFormat::VatisProfile, "not json").is_err());
+        assert!(parse_station_ids(ImportFormat::VatisProfile, r#"["KSFO"]"#).is_err());
+        assert!(parse_station_ids(ImportFormat::VatisProfile, r#"{"stations":[]}"#).is_err());
+    }
+
+    #[test]
+    fn reads_sector_file_airports() {
+        let sct = "\
+[INFO]
+London
+EGLL
+[AIRPORT]
+; Heathrow
+EGLL 118.500 N051.28.39.000 W000.27.41.000 D
+egkk 124.225 N051.08.53.000 W000.11.25.000 D ; Gatwick
+[RUNWAY]
+09L 27R 089 269 N051.28.39.000 W000.27.41.000 N051.28.39.000 W000.26.41.000 EGLL
+";
+        assert_eq!(ids(ImportFormat::EuroScope, sct), ["EGLL", "EGKK"]);
+    }
+
+    #[test]
+    fn reads_extension_file_sids_and_stars() {
+        let ese = "\
+[POSITIONS]
+EDDF_TWR:Frankfurt Tower:119.900:TF:T:EDDF:TWR:-:-:2001:2077
+[SIDSSTARS]
+SID:EDDF:25C:ANEKI1L:DF155 ANEKI
+STAR:EDDM:26L:ROKIL1A:ROKIL
+; SID:EDDS:07:X:Y
+OTHER:EDDH:05:X:Y
+";
+        assert_eq!(ids(ImportFormat::EuroScope, ese), ["EDDF", "EDDM"]);
+    }
+
+    #[test]
+    fn reads_plain_lists() {
+        assert_eq!(
+            ids(ImportFormat::List, "KSFO KOAK\nsjc # San Jose\n\nKSFO\n"),
+            ["KSFO", "KOAK", "SJC"]
+        );
+        assert_eq!(
+            ids(ImportFormat::List, "KSFO, KOAK, KSJC"),
+            ["KSFO", "KOAK", "KSJC"]
+        );
+        assert_eq!(
+            ids(ImportFormat::List, "KSFO,KOAK\nKSJC;KLAX"),
+            ["KSFO", "KOAK", "KSJC", "KLAX"]
+        );
+    }
+
+    #[test]
+    fn reads_first_column_of_csv_lists() {
+        assert_eq!(
+            ids(
+                ImportFormat::List,
+                "ICAO,Name\nKSFO,San Francisco Intl\n\"KOAK\",Oakland\n"
+            ),
+            ["KSFO", "KOAK"]
+        );
+        assert_eq!(
+            ids(
+                ImportFormat::List,
+                "KSFO,San Francisco Intl,KOAK\nKSJC,San Jose"
+            ),
+            ["KSFO", "KSJC"]
+        );
+    }
+
+    #[test]
+    fn rejects_lists_without_ids() {
+        assert!(parse_station_ids(ImportFormat::List, "# Nothing here\n").is_err());
+        assert!(parse_station_ids(ImportFormat::List, "ICAO,Name\n").is_err());
+    }
+}
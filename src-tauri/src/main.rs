@@ -2,28 +2,64 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use crate::awc::{MetarDto, Station};
+use crate::alerts::{check_altimeter_changes, check_atis_change};
+use crate::api::run_api_server;
+use crate::atis::{combined_runway_summary, parse_atis_text, parse_code_from_text, AtisInfo};
+use crate::awc::{MetarDto, Station, TafDto};
+use crate::callsigns::{AtisCallsignRules, CallsignMatcher};
+use crate::consistency::{check_consistency, StationConsistency};
+use crate::controllers::{controllers_for_prefixes, station_prefixes, OnlineController};
+use crate::flight_category::{flight_category, FlightCategory, FlightCategoryThresholds};
+use crate::metar::{parse_metar, MetarReport};
+use crate::networks::{AtisNetworkKind, NetworkAtis, NetworkAtisData};
 use crate::profiles::read_profile_from_file;
+use crate::runways::{runway_winds, RunwayWindsResponse};
+use crate::scheduler::run_metar_scheduler;
 use crate::settings::{
     get_appstate_settings, get_latest_profile_path, read_settings_or_default, set_appstate_settings,
 };
-use crate::state::{AppState, VatsimDataFetch};
+use crate::state::{
+    run_client_initialization, run_vatsim_datafeed_refresh, AppState, ClientStatusResponse,
+};
+use crate::taf::{TafForecast, TafReport};
+use crate::traffic::{traffic_counts, TrafficCounts};
 use crate::update::check_for_updates;
-use anyhow::anyhow;
+use chrono::Utc;
 use log::{debug, error, info, trace, warn};
-use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, LazyLock};
-use std::time::Duration;
+use std::collections::BTreeMap;
+use std::sync::Arc;
 use tauri::plugin::TauriPlugin;
-use tauri::{Runtime, State, WebviewWindowBuilder};
+use tauri::{AppHandle, Runtime, State, WebviewWindowBuilder};
 use tauri_plugin_log::{Target, TargetKind};
-use vatsim_utils::models::{Atis, V3ResponseData};
 
+mod alerts;
+mod api;
+mod atis;
 mod awc;
+mod callsigns;
+mod cli;
+mod client_holder;
+mod consistency;
+mod controllers;
+mod datafeed;
+mod flight_category;
+mod history;
+mod importers;
+mod metar;
+mod networks;
+mod profile_migrations;
+mod profile_watcher;
 mod profiles;
+mod providers;
+mod runways;
+mod scheduler;
 mod settings;
 mod state;
+mod taf;
+#[cfg(test)]
+mod test_utils;
+mod traffic;
 mod update;
 mod utils;
 mod window;
@@ -60,20 +96,44 @@ fn build_logger<R: Runtime>() -> TauriPlugin<R> {
 }
 
 fn main() {
+    // Headless mode prints to stdout instead of opening a window. Release builds on Windows are
+    // GUI subsystem executables, so run it from a debug build or redirect its output there.
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.iter().any(|a| a == cli::CLI_FLAG) {
+        std::process::exit(cli::run(args));
+    }
+
     tauri::Builder::default()
         .plugin(build_logger())
         .manage(Arc::new(AppState::new()))
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
         .invoke_handler(tauri::generate_handler![
             fetch_metar,
+            get_metar_history,
+            subscribe_metar,
+            unsubscribe_metar,
+            decode_metar,
+            fetch_taf,
             lookup_station,
+            get_runway_winds,
             get_atis,
+            check_atis_consistency,
+            get_controllers,
+            get_traffic_counts,
             initialize_datafeed,
+            get_client_status,
             profiles::load_profile,
             profiles::save_current_profile,
             profiles::save_profile_as,
+            profiles::list_profiles,
+            profiles::load_library_profile,
+            profiles::rename_profile,
+            profiles::duplicate_profile,
+            profiles::delete_profile,
+            profiles::import_profile,
             settings::load_settings,
             settings::load_settings_initial,
             settings::save_settings
@@ -102,6 +162,11 @@ fn main() {
             #[cfg(not(target_os = "windows"))]
             let mut height = 64.0;
 
+            tauri::async_runtime::spawn(run_client_initialization(app.handle().clone()));
+            tauri::async_runtime::spawn(run_metar_scheduler(app.handle().clone()));
+            tauri::async_runtime::spawn(run_vatsim_datafeed_refresh(app.handle().clone()));
+            tauri::async_runtime::spawn(run_api_server(app.handle().clone()));
+
             let handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
                 debug!("Starting version update check");
@@ -154,6 +219,26 @@ struct FetchMetarResponse {
     metar: MetarDto,
     wind_string: String,
     altimeter: Altimeter,
+    decoded: Option<MetarReport>,
+    flight_category: Option<FlightCategory>,
+}
+
+impl FetchMetarResponse {
+    fn new(metar: MetarDto, thresholds: &FlightCategoryThresholds) -> Self {
+        Self {
+            wind_string: metar.wind_string(),
+            decoded: metar
+                .decode()
+                .inspect_err(|e| debug!("Could not decode METAR for {}: {e:?}", metar.icao_id))
+                .ok(),
+            flight_category: flight_category(&metar, thresholds),
+            altimeter: Altimeter {
+                in_hg: metar.altimeter_in_hg(),
+                hpa: metar.altimeter_hpa(),
+            },
+            metar,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Copy)]
@@ -166,136 +251,448 @@ struct Altimeter {
 #[tauri::command]
 async fn initialize_datafeed(state: State<'_, Arc<AppState>>) -> Result<(), String> {
     debug!("Initializing VATSIM datafeed");
-    let new_data = Some(VatsimDataFetch::new(fetch_vatsim_data(&state).await));
-    *state.latest_vatsim_data.lock().unwrap() = new_data;
+    state.refresh_vatsim_data().await;
     Ok(())
 }
 
+#[tauri::command]
+fn get_client_status(state: State<'_, Arc<AppState>>) -> ClientStatusResponse {
+    state.client_status()
+}
+
 #[tauri::command]
 async fn fetch_metar(
     id: &str,
+    app: AppHandle,
     state: State<'_, Arc<AppState>>,
 ) -> Result<FetchMetarResponse, String> {
-    if let Ok(client) = &state.get_awc_client().await {
-        let ret = client
-            .fetch_metar(id)
-            .await
-            .map_err(|e| format!("Error fetching METAR for : {e:?}"))
-            .map(|m| FetchMetarResponse {
-                wind_string: m.wind_string(),
-                altimeter: Altimeter {
-                    in_hg: m.altimeter_in_hg(),
-                    hpa: m.altimeter_hpa(),
-                },
-                metar: m,
-            });
+    let thresholds = state.flight_category_thresholds();
+
+    match state.get_weather_provider().await {
+        Ok(client) => {
+            let ret = client
+                .fetch_metar(id)
+                .await
+                .map_err(|e| format!("Error fetching METAR for : {e:?}"))
+                .inspect(|m| {
+                    state.metar_history.record(std::slice::from_ref(m));
+                    check_altimeter_changes(&app, &state, std::slice::from_ref(m));
+                })
+                .map(|m| FetchMetarResponse::new(m, &thresholds))
+                .inspect(|m| state.station_data.update_metars(std::slice::from_ref(m)));
+
+            match &ret {
+                Ok(_m) => debug!("Successfully retrieved metar for {id}"),
+                Err(e) => debug!("{e:?}"),
+            }
 
-        match &ret {
-            Ok(_m) => debug!("Successfully retrieved metar for {id}"),
-            Err(e) => debug!("{e:?}"),
+            ret
         }
+        Err(e) => {
+            error!("Fetch Metar Command error: {e}");
+            Err(e.to_string())
+        }
+    }
+}
+
+#[tauri::command]
+fn get_metar_history(
+    id: &str,
+    hours: u32,
+    state: State<'_, Arc<AppState>>,
+) -> Vec<FetchMetarResponse> {
+    let thresholds = state.flight_category_thresholds();
+    state
+        .metar_history
+        .query(id, hours)
+        .into_iter()
+        .map(|m| FetchMetarResponse::new(m, &thresholds))
+        .collect()
+}
+
+#[tauri::command]
+fn subscribe_metar(
+    id: &str,
+    min_altimeter_change_hpa: Option<f64>,
+    state: State<'_, Arc<AppState>>,
+) {
+    state
+        .altimeter_tracker
+        .set_min_notify_change(id, min_altimeter_change_hpa);
+    state.metar_scheduler.subscribe(id);
+}
 
-        ret
-    } else {
-        const E: &str = "AWC Api Client not initialized";
-        error!("Fetch Metar Command error: {E}",);
-        Err(E.to_string())
+#[tauri::command]
+fn unsubscribe_metar(id: &str, state: State<'_, Arc<AppState>>) {
+    // Other rows may still show the station
+    if state.metar_scheduler.unsubscribe(id) {
+        state.altimeter_tracker.set_min_notify_change(id, None);
+        state.station_data.remove(id);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FetchTafResponse {
+    taf: TafDto,
+    decoded: Option<TafReport>,
+    current: Option<TafForecast>,
+    /// `current` in TAF form, for display in the station row
+    current_summary: Option<String>,
+}
+
+#[tauri::command]
+async fn fetch_taf(id: &str, state: State<'_, Arc<AppState>>) -> Result<FetchTafResponse, String> {
+    match state.get_awc_client().await {
+        Ok(client) => {
+            let ret = client
+                .fetch_taf(id)
+                .await
+                .map_err(|e| format!("Error fetching TAF for {id}: {e:?}"))
+                .map(|t| {
+                    let decoded = t
+                        .decode()
+                        .inspect_err(|e| debug!("Could not decode TAF for {id}: {e:?}"))
+                        .ok();
+                    let current = decoded.as_ref().and_then(|d| d.forecast_at(Utc::now()));
+                    FetchTafResponse {
+                        current_summary: current.as_ref().map(TafForecast::summary),
+                        current,
+                        decoded,
+                        taf: t,
+                    }
+                });
+
+            match &ret {
+                Ok(_) => debug!("Successfully retrieved TAF for {id}"),
+                Err(e) => debug!("{e:?}"),
+            }
+
+            ret
+        }
+        Err(e) => {
+            error!("Fetch TAF Command error: {e}");
+            Err(e.to_string())
+        }
     }
 }
 
+#[tauri::command]
+fn decode_metar(raw: &str) -> Result<MetarReport, String> {
+    parse_metar(raw).map_err(|e| format!("Error decoding METAR: {e:?}"))
+}
+
 #[tauri::command]
 async fn lookup_station(id: &str, state: State<'_, Arc<AppState>>) -> Result<Station, String> {
     debug!("Starting Lookup Station Command");
-    if let Ok(client) = &state.get_awc_client().await {
-        let ret = client
-            .lookup_station(id)
-            .map_err(|e| format!("Error looking up station {id}: {e:?}"));
-
-        match &ret {
-            Ok(s) => debug!("Lookup for {id} returned {s:?}"),
-            Err(e) => debug!("Lookup for {id} returned {e}"),
-        }
+    match state.get_awc_client().await {
+        Ok(client) => {
+            let ret = client
+                .lookup_station(id)
+                .map_err(|e| format!("Error looking up station {id}: {e:?}"));
+
+            match &ret {
+                Ok(s) => debug!("Lookup for {id} returned {s:?}"),
+                Err(e) => debug!("Lookup for {id} returned {e}"),
+            }
 
-        ret
-    } else {
-        const E: &str = "AWC Api Client not initialized";
-        error!("Fetch Metar Command error: {E}");
-        Err(E.to_string())
+            ret
+        }
+        Err(e) => {
+            error!("Fetch Metar Command error: {e}");
+            Err(e.to_string())
+        }
     }
 }
 
+#[tauri::command]
+async fn get_runway_winds(
+    id: &str,
+    state: State<'_, Arc<AppState>>,
+) -> Result<RunwayWindsResponse, String> {
+    debug!("Starting Get Runway Winds Command");
+    let client = state.get_awc_client().await.map_err(|e| {
+        error!("Get Runway Winds Command error: {e}");
+        e.to_string()
+    })?;
+    let database = state.get_runway_database().await.map_err(|e| {
+        error!("Get Runway Winds Command error: {e}");
+        e.to_string()
+    })?;
+
+    let station = client
+        .lookup_station(id)
+        .map_err(|e| format!("Error looking up station {id}: {e:?}"))?;
+    let runways = database
+        .runways_for(&station.icao_id)
+        .or_else(|| database.runways_for(&station.faa_id))
+        .ok_or_else(|| format!("No runway data found for {id}"))?;
+    let metar = client
+        .fetch_metar(&station.icao_id)
+        .await
+        .map_err(|e| format!("Error fetching METAR for {id}: {e:?}"))?;
+
+    let ret = runway_winds(&station.icao_id, &metar, runways);
+    debug!("Runway winds for {id}: {ret:?}");
+    Ok(ret)
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct FetchAtisResponse {
     pub letter: String,
     pub texts: Vec<String>,
+    pub decoded: Vec<AtisInfo>,
+    /// Runways in use across all of the station's ATIS, e.g. `ARR 27L/28R DEP 1L`
+    pub runway_summary: Option<String>,
+    /// Seconds since the network feed the ATIS was read from was generated
+    pub data_age_secs: Option<i64>,
 }
 
 #[tauri::command]
 async fn get_atis(
     icao_id: &str,
+    network: Option<AtisNetworkKind>,
+    rules: Option<AtisCallsignRules>,
+    app: AppHandle,
     state: State<'_, Arc<AppState>>,
 ) -> Result<FetchAtisResponse, String> {
-    if datafeed_is_stale(&state) {
-        debug!("Datafeed is stale, fetching new data");
-        let new_data = Some(VatsimDataFetch::new(fetch_vatsim_data(&state).await));
-        *state.latest_vatsim_data.lock().unwrap() = new_data;
+    let matcher = CallsignMatcher::new(icao_id, &rules.unwrap_or_default())
+        .map_err(|e| format!("Invalid ATIS callsign pattern for {icao_id}: {e}"))?;
+    let network = network.unwrap_or_default();
+    let ret = fetch_network_atis(&state, network)
+        .await
+        .map(|data| FetchAtisResponse {
+            data_age_secs: data
+                .updated_at
+                .map(|t| (Utc::now() - t).num_seconds().max(0)),
+            ..find_atis(&data.atis, &matcher)
+        })
+        .map_err(|e| {
+            warn!("Get Atis Command error: {e}");
+            e.to_string()
+        });
+
+    if let Ok(atis) = &ret {
+        check_atis_change(&app, &state, network, icao_id, &atis.letter, &atis.texts);
+        state.station_data.update_atis(icao_id, atis);
     }
+    ret
+}
 
-    if let Some(fetch) = &*state.latest_vatsim_data.lock().unwrap() {
-        fetch.data.as_ref().map_or_else(
-            |_| Err("Could not retrieve datafeed".to_string()),
-            |datafeed| {
-                let found_atis: Vec<&Atis> = datafeed
-                    .atis
-                    .iter()
-                    .filter(|a| a.callsign.starts_with(icao_id))
-                    .collect();
+async fn fetch_network_atis(
+    state: &AppState,
+    kind: AtisNetworkKind,
+) -> Result<NetworkAtisData, anyhow::Error> {
+    let network = state.get_atis_network(kind).await?;
+    trace!("Fetching ATIS from {}", network.name());
+    network.fetch_atis(state).await
+}
 
-                trace!(
-                    "Found {} atis for {} with callsign(s): {:?}",
-                    found_atis.len(),
-                    icao_id,
-                    found_atis
-                        .iter()
-                        .map(|a| &a.callsign)
-                        .cloned()
-                        .collect::<Vec<_>>()
-                );
+#[tauri::command]
+async fn check_atis_consistency(
+    ids: Vec<String>,
+    network: Option<AtisNetworkKind>,
+    rules: Option<BTreeMap<String, AtisCallsignRules>>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<StationConsistency>, String> {
+    debug!("Starting Check ATIS Consistency Command");
+    let rules = rules.unwrap_or_default();
+    let matchers = ids
+        .iter()
+        .map(|id| {
+            CallsignMatcher::new(id, rules.get(id).unwrap_or(&AtisCallsignRules::default()))
+                .map_err(|e| format!("Invalid ATIS callsign pattern for {id}: {e}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let network_atis = fetch_network_atis(&state, network.unwrap_or_default())
+        .await
+        .map_err(|e| {
+            error!("Check ATIS Consistency Command error: {e}");
+            e.to_string()
+        })?
+        .atis;
+
+    let atis = ids
+        .iter()
+        .zip(&matchers)
+        .map(|(id, matcher)| (id, find_atis(&network_atis, matcher)))
+        .filter(|(_, atis)| !atis.decoded.is_empty())
+        .collect::<Vec<_>>();
+    if atis.is_empty() {
+        return Ok(vec![]);
+    }
 
-                let letter_str: String = match found_atis.len() {
-                    0 => "-".to_string(),
-                    1 => parse_atis_code(found_atis[0]),
-                    _ => format!(
-                        "{}/{}",
-                        filter_callsign_and_parse(&found_atis, "_A_"),
-                        filter_callsign_and_parse(&found_atis, "_D_")
-                    ),
-                };
-
-                Ok(FetchAtisResponse {
-                    letter: letter_str,
-                    texts: found_atis
-                        .iter()
-                        .filter_map(|a| a.text_atis.as_ref().map(|t| t.join(" ")))
-                        .collect(),
-                })
-            },
-        )
-    } else {
-        const E: &str = "Could not retrieve datafeed";
-        warn!("Get Atis Command error: {E}");
-        Err(E.to_string())
+    let client = state.get_weather_provider().await.map_err(|e| {
+        error!("Check ATIS Consistency Command error: {e}");
+        e.to_string()
+    })?;
+    let station_ids = atis.iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>();
+    let metars = client
+        .fetch_metars(&station_ids)
+        .await
+        .map_err(|e| format!("Error fetching METARs: {e:?}"))?;
+
+    let ret = atis
+        .into_iter()
+        .filter_map(|(id, atis)| {
+            let metar = metars.iter().find(|m| m.icao_id.eq_ignore_ascii_case(id))?;
+            Some(StationConsistency {
+                station: metar.icao_id.clone(),
+                discrepancies: check_consistency(&atis.decoded, metar),
+                atis_letter: atis.letter,
+                metar_obs_time: metar.obs_time,
+            })
+        })
+        .collect::<Vec<_>>();
+    debug!("ATIS consistency: {ret:?}");
+    Ok(ret)
+}
+
+#[tauri::command]
+async fn get_controllers(
+    id: &str,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<OnlineController>, String> {
+    debug!("Starting Get Controllers Command");
+    let station = match state.get_awc_client().await {
+        Ok(client) => client.lookup_station(id).ok(),
+        Err(e) => {
+            debug!("Could not look up station {id} for controllers: {e}");
+            None
+        }
+    };
+
+    let icao_id = station
+        .as_ref()
+        .map_or_else(|| id.to_uppercase(), |s| s.icao_id.clone());
+    let configured = state
+        .settings
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|s| s.controller_prefixes(&icao_id).to_vec())
+        .unwrap_or_default();
+    let prefixes = station_prefixes(
+        &icao_id,
+        station.as_ref().map(|s| s.faa_id.as_str()),
+        &configured,
+    );
+
+    state.refresh_vatsim_data_if_stale().await;
+    let ret = state
+        .latest_vatsim_data
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|fetch| fetch.data.as_ref().ok())
+        .map(|datafeed| controllers_for_prefixes(datafeed, &prefixes))
+        .ok_or_else(|| "Could not retrieve datafeed".to_string());
+
+    match &ret {
+        Ok(c) => debug!(
+            "Found {} controllers for {id} with prefixes {prefixes:?}",
+            c.len()
+        ),
+        Err(e) => warn!("Get Controllers Command error: {e}"),
+    }
+    ret
+}
+
+#[tauri::command]
+async fn get_traffic_counts(
+    id: &str,
+    state: State<'_, Arc<AppState>>,
+) -> Result<TrafficCounts, String> {
+    debug!("Starting Get Traffic Counts Command");
+    let client = state.get_awc_client().await.map_err(|e| {
+        error!("Get Traffic Counts Command error: {e}");
+        e.to_string()
+    })?;
+    let station = client
+        .lookup_station(id)
+        .map_err(|e| format!("Error looking up station {id}: {e:?}"))?;
+
+    state.refresh_vatsim_data_if_stale().await;
+    let ret = state
+        .latest_vatsim_data
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|fetch| {
+            fetch
+                .data
+                .as_ref()
+                .ok()
+                .map(|datafeed| traffic_counts(datafeed, &fetch.prefiles, &station))
+        })
+        .ok_or_else(|| "Could not retrieve datafeed".to_string());
+
+    match &ret {
+        Ok(t) => debug!(
+            "Traffic for {id}: {} departures, {} arrivals, {} prefiles",
+            t.departures,
+            t.arrivals.len(),
+            t.prefiles
+        ),
+        Err(e) => warn!("Get Traffic Counts Command error: {e}"),
+    }
+    ret
+}
+
+fn find_atis(network_atis: &[NetworkAtis], matcher: &CallsignMatcher) -> FetchAtisResponse {
+    let found_atis: Vec<&NetworkAtis> =
+        network_atis.iter().filter(|a| matcher.matches(a)).collect();
+
+    trace!(
+        "Found {} atis for {} with callsign(s): {:?}",
+        found_atis.len(),
+        matcher.station(),
+        found_atis
+            .iter()
+            .map(|a| &a.callsign)
+            .cloned()
+            .collect::<Vec<_>>()
+    );
+
+    let letter_str: String = match found_atis.len() {
+        0 => "-".to_string(),
+        1 => parse_atis_code(found_atis[0]),
+        _ => format!(
+            "{}/{}",
+            filter_callsign_and_parse(&found_atis, |a| matcher.is_arrival(a)),
+            filter_callsign_and_parse(&found_atis, |a| matcher.is_departure(a))
+        ),
+    };
+
+    let decoded = found_atis
+        .iter()
+        .filter_map(|a| a.text_atis.as_deref().map(parse_atis_text))
+        .collect::<Vec<_>>();
+
+    FetchAtisResponse {
+        letter: letter_str,
+        texts: found_atis
+            .iter()
+            .filter_map(|a| a.text_atis.as_ref().map(|t| t.join(" ")))
+            .collect(),
+        runway_summary: combined_runway_summary(&decoded),
+        decoded,
+        data_age_secs: None,
     }
 }
 
-fn filter_callsign_and_parse(atises: &[&Atis], pat: &str) -> String {
+fn filter_callsign_and_parse(
+    atises: &[&NetworkAtis],
+    predicate: impl Fn(&NetworkAtis) -> bool,
+) -> String {
     atises
         .iter()
-        .find(|s| s.callsign.contains(pat))
+        .find(|a| predicate(a))
         .map_or_else(|| "-".to_string(), |a| parse_atis_code(a))
 }
 
-fn parse_atis_code(atis: &Atis) -> String {
+fn parse_atis_code(atis: &NetworkAtis) -> String {
     match (&atis.atis_code, &atis.text_atis) {
         (Some(code), Some(text_lines)) => {
             // Check for special case that letter in ATIS text has advanced but `atis_code` field has not yet
@@ -322,77 +719,3 @@ fn parse_atis_code(atis: &Atis) -> String {
         _ => "-".to_string(),
     }
 }
-
-fn parse_code_from_text(text_lines: &[String]) -> Option<char> {
-    static INFO_CHAR_REGEX: LazyLock<Regex> =
-        LazyLock::new(|| Regex::new(r"(?:INFO|INFORMATION) ([A-Z])(?:\W|$)").unwrap());
-    static INFO_WORD_REGEX: LazyLock<Regex> =
-        LazyLock::new(|| Regex::new(r"(?:INFO|INFORMATION) ([A-Z]+)(?:\W|$)").unwrap());
-
-    let joined = text_lines.join(" ");
-    INFO_CHAR_REGEX.captures(&joined).map_or_else(
-        || {
-            INFO_WORD_REGEX
-                .captures(&joined)
-                .and_then(|c| nato_to_char(&c[1]))
-        },
-        |c| c[1].chars().next(),
-    )
-}
-
-fn nato_to_char(str: &str) -> Option<char> {
-    match str.to_uppercase().as_str() {
-        "ALPHA" => Some('A'),
-        "BRAVO" => Some('B'),
-        "CHARLIE" => Some('C'),
-        "DELTA" => Some('D'),
-        "ECHO" => Some('E'),
-        "FOXTROT" => Some('F'),
-        "GOLF" => Some('G'),
-        "HOTEL" => Some('H'),
-        "INDIA" => Some('I'),
-        "JULIET" => Some('J'),
-        "KILO" => Some('K'),
-        "LIMA" => Some('L'),
-        "MIKE" => Some('M'),
-        "NOVEMBER" => Some('N'),
-        "OSCAR" => Some('O'),
-        "PAPA" => Some('P'),
-        "QUEBEC" => Some('Q'),
-        "ROMEO" => Some('R'),
-        "SIERRA" => Some('S'),
-        "TANGO" => Some('T'),
-        "UNIFORM" => Some('U'),
-        "VICTOR" => Some('V'),
-        "WHISKEY" => Some('W'),
-        "XRAY" => Some('X'),
-        "X-RAY" => Some('X'),
-        "YANKEE" => Some('Y'),
-        "ZULU" => Some('Z'),
-        _ => None,
-    }
-}
-
-fn datafeed_is_stale(state: &State<'_, Arc<AppState>>) -> bool {
-    state
-        .latest_vatsim_data
-        .lock()
-        .unwrap()
-        .as_ref()
-        .map_or_else(
-            || true,
-            |fetch| fetch.fetched_time.elapsed() > Duration::from_secs(30),
-        )
-}
-
-async fn fetch_vatsim_data(
-    state: &State<'_, Arc<AppState>>,
-) -> Result<V3ResponseData, anyhow::Error> {
-    if let Ok(client) = state.get_vatsim_client().await {
-        client.get_v3_data().await.map_err(Into::into)
-    } else {
-        const E: &str = "VATSIM API client not initialized";
-        error!("Error fetching VATSIM data: {E}");
-        Err(anyhow!(E))
-    }
-}
@@ -0,0 +1,998 @@
+use crate::awc::MBAR_TO_INHG_FACTOR;
+use anyhow::bail;
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, TimeZone, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+pub const METERS_PER_STATUTE_MILE: f64 = 1609.344;
+
+static TIME_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d{2})(\d{2})(\d{2})Z$").unwrap());
+static WIND_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d{3}|VRB)(\d{2,3})(?:G(\d{2,3}))?(KT|MPS|KMH)$").unwrap());
+static WIND_VARIABLE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d{3})V(\d{3})$").unwrap());
+static VIS_METERS_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d{4})(NDV)?$").unwrap());
+static VIS_DIRECTIONAL_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d{4})(N|NE|E|SE|S|SW|W|NW)$").unwrap());
+static VIS_SM_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([PM])?(\d{1,2})?(?:(\d)/(\d{1,2}))?SM$").unwrap());
+static RVR_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^R(\d{2}[LCR]?)/([PM])?(\d{4})(?:V([PM])?(\d{4}))?(FT)?/?([UDN])?$").unwrap()
+});
+static WEATHER_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(-|\+|VC)?(MI|PR|BC|DR|BL|SH|TS|FZ)?((?:DZ|RA|SN|SG|IC|PL|GR|GS|UP|BR|FG|FU|VA|DU|SA|HZ|PY|PO|SQ|FC|SS|DS)*)$").unwrap()
+});
+static CLOUD_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(FEW|SCT|BKN|OVC)(\d{3}|///)(CB|TCU|///)?$").unwrap());
+static VERT_VIS_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^VV(\d{3}|///)$").unwrap());
+static TEMP_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(M?\d{2})/(M?\d{2})?$").unwrap());
+static ALTIMETER_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^([AQ])(\d{4})$").unwrap());
+static TREND_TIME_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(FM|TL|AT)(\d{2})(\d{2})$").unwrap());
+
+/// A METAR decoded from its raw text form
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetarReport {
+    pub report_type: Option<ReportType>,
+    pub station: String,
+    pub time: Option<ObservationTime>,
+    pub auto: bool,
+    pub corrected: bool,
+    #[serde(flatten)]
+    pub conditions: WeatherConditions,
+    pub directional_visibility: Vec<DirectionalVisibility>,
+    pub runway_visual_ranges: Vec<RunwayVisualRange>,
+    pub temperature: Option<i32>,
+    pub dewpoint: Option<i32>,
+    pub altimeter: Option<AltimeterSetting>,
+    pub trends: Vec<Trend>,
+    pub remarks: Option<String>,
+    pub unparsed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReportType {
+    Metar,
+    Speci,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObservationTime {
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+}
+
+impl ObservationTime {
+    /// Full observation time, taking the month and year from the nearest match to `reference`
+    pub fn resolve(&self, reference: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        resolve_day_time(reference, self.day, self.hour, self.minute)
+    }
+}
+
+/// Groups that can appear both in the body of a report and in trend or forecast change groups
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeatherConditions {
+    pub wind: Option<Wind>,
+    pub visibility: Option<Visibility>,
+    pub weather: Vec<WeatherGroup>,
+    pub clouds: Vec<CloudLayer>,
+    pub vertical_visibility: Option<i32>,
+    pub cavok: bool,
+    pub no_significant_weather: bool,
+}
+
+impl WeatherConditions {
+    /// The conditions re-encoded in METAR form, e.g. `28012G22KT 6000 -SHRA BKN014`
+    pub fn code(&self) -> String {
+        let mut groups = vec![];
+        groups.extend(self.wind.map(|w| w.code()));
+        if self.cavok {
+            groups.push("CAVOK".to_string());
+        }
+        groups.extend(self.visibility.map(|v| v.code()));
+        groups.extend(self.weather.iter().map(WeatherGroup::code));
+        if self.no_significant_weather {
+            groups.push("NSW".to_string());
+        }
+        groups.extend(self.clouds.iter().map(CloudLayer::code));
+        groups.extend(
+            self.vertical_visibility
+                .map(|v| format!("VV{:03}", v / 100)),
+        );
+        groups.join(" ")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Wind {
+    /// `None` when the direction is reported as variable (`VRB`)
+    pub direction: Option<i32>,
+    pub speed: i32,
+    pub gust: Option<i32>,
+    pub unit: SpeedUnit,
+    pub variable_from: Option<i32>,
+    pub variable_to: Option<i32>,
+}
+
+impl Wind {
+    pub fn speed_knots(&self) -> i32 {
+        self.unit.to_knots(self.speed)
+    }
+
+    pub fn gust_knots(&self) -> Option<i32> {
+        self.gust.map(|g| self.unit.to_knots(g))
+    }
+
+    /// The wind re-encoded in METAR form, e.g. `VRB03KT` or `28012G22KT`
+    pub fn code(&self) -> String {
+        let direction = self
+            .direction
+            .map_or_else(|| "VRB".to_string(), |d| format!("{d:03}"));
+        let gust = self.gust.map(|g| format!("G{g:02}")).unwrap_or_default();
+        let unit = match self.unit {
+            SpeedUnit::Knots => "KT",
+            SpeedUnit::MetersPerSecond => "MPS",
+            SpeedUnit::KilometersPerHour => "KMH",
+        };
+        format!("{direction}{:02}{gust}{unit}", self.speed)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpeedUnit {
+    #[serde(rename = "KT")]
+    Knots,
+    #[serde(rename = "MPS")]
+    MetersPerSecond,
+    #[serde(rename = "KMH")]
+    KilometersPerHour,
+}
+
+impl SpeedUnit {
+    #[allow(clippy::cast_possible_truncation)]
+    fn to_knots(self, value: i32) -> i32 {
+        match self {
+            Self::Knots => value,
+            Self::MetersPerSecond => (f64::from(value) * 1.943_84).round() as i32,
+            Self::KilometersPerHour => (f64::from(value) * 0.539_957).round() as i32,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Visibility {
+    pub distance: f64,
+    pub unit: DistanceUnit,
+    pub modifier: Option<ValueModifier>,
+    pub no_directional_variation: bool,
+}
+
+impl Visibility {
+    pub fn statute_miles(&self) -> f64 {
+        match self.unit {
+            DistanceUnit::StatuteMiles => self.distance,
+            DistanceUnit::Meters => self.distance / METERS_PER_STATUTE_MILE,
+            DistanceUnit::Feet => self.distance * 0.3048 / METERS_PER_STATUTE_MILE,
+        }
+    }
+
+    /// The visibility re-encoded in METAR form, e.g. `0800`, `P6SM` or `1 1/2SM`
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn code(&self) -> String {
+        let modifier = match self.modifier {
+            Some(ValueModifier::LessThan) => "M",
+            Some(ValueModifier::GreaterThan) => "P",
+            None => "",
+        };
+        match self.unit {
+            DistanceUnit::Meters => format!("{modifier}{:04}", self.distance.round() as i32),
+            DistanceUnit::Feet => format!("{modifier}{}FT", self.distance.round() as i32),
+            DistanceUnit::StatuteMiles => {
+                let whole = self.distance.trunc() as i32;
+                let mut numerator = ((self.distance - self.distance.trunc()) * 16.0).round() as i32;
+                let mut denominator = 16;
+                while numerator > 0 && numerator % 2 == 0 {
+                    numerator /= 2;
+                    denominator /= 2;
+                }
+                match (whole, numerator) {
+                    (_, 0) => format!("{modifier}{whole}SM"),
+                    (0, _) => format!("{modifier}{numerator}/{denominator}SM"),
+                    _ => format!("{modifier}{whole} {numerator}/{denominator}SM"),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DistanceUnit {
+    #[serde(rename = "m")]
+    Meters,
+    #[serde(rename = "SM")]
+    StatuteMiles,
+    #[serde(rename = "ft")]
+    Feet,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ValueModifier {
+    LessThan,
+    GreaterThan,
+}
+
+impl ValueModifier {
+    fn from_code(code: Option<&str>) -> Option<Self> {
+        match code {
+            Some("M") => Some(Self::LessThan),
+            Some("P") => Some(Self::GreaterThan),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectionalVisibility {
+    pub meters: i32,
+    pub direction: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunwayVisualRange {
+    pub runway: String,
+    pub range: i32,
+    pub range_modifier: Option<ValueModifier>,
+    pub variable_range: Option<i32>,
+    pub variable_range_modifier: Option<ValueModifier>,
+    pub unit: DistanceUnit,
+    pub tendency: Option<RvrTendency>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RvrTendency {
+    Up,
+    Down,
+    NoChange,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeatherGroup {
+    pub intensity: Intensity,
+    pub descriptor: Option<String>,
+    pub phenomena: Vec<String>,
+}
+
+impl WeatherGroup {
+    /// The group re-encoded in METAR form, e.g. `-SHRA`
+    pub fn code(&self) -> String {
+        let intensity = match self.intensity {
+            Intensity::Light => "-",
+            Intensity::Moderate => "",
+            Intensity::Heavy => "+",
+            Intensity::Vicinity => "VC",
+        };
+        format!(
+            "{intensity}{}{}",
+            self.descriptor.as_deref().unwrap_or_default(),
+            self.phenomena.concat()
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Intensity {
+    Light,
+    Moderate,
+    Heavy,
+    Vicinity,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CloudLayer {
+    pub cover: String,
+    /// Cloud base in feet above ground level
+    pub base: Option<i32>,
+    pub cloud_type: Option<String>,
+}
+
+impl CloudLayer {
+    /// The layer re-encoded in METAR form, e.g. `BKN014CB`
+    pub fn code(&self) -> String {
+        format!(
+            "{}{}{}",
+            self.cover,
+            self.base
+                .map(|b| format!("{:03}", b / 100))
+                .unwrap_or_default(),
+            self.cloud_type.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "unit", content = "value")]
+pub enum AltimeterSetting {
+    #[serde(rename = "inHg")]
+    InHg(f64),
+    #[serde(rename = "hPa")]
+    Hpa(f64),
+}
+
+impl AltimeterSetting {
+    /// Unrounded, so converting back to inHg gives the reported value
+    pub fn hpa(&self) -> f64 {
+        match *self {
+            Self::InHg(v) => v / MBAR_TO_INHG_FACTOR,
+            Self::Hpa(v) => v,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Trend {
+    pub kind: TrendKind,
+    pub from: Option<String>,
+    pub until: Option<String>,
+    pub at: Option<String>,
+    pub conditions: WeatherConditions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrendKind {
+    #[serde(rename = "NOSIG")]
+    NoSignificantChange,
+    #[serde(rename = "BECMG")]
+    Becoming,
+    #[serde(rename = "TEMPO")]
+    Temporary,
+}
+
+impl TrendKind {
+    fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "NOSIG" => Some(Self::NoSignificantChange),
+            "BECMG" => Some(Self::Becoming),
+            "TEMPO" => Some(Self::Temporary),
+            _ => None,
+        }
+    }
+}
+
+/// Decodes a raw METAR or SPECI, e.g. `MetarDto::raw_ob`
+pub fn parse_metar(raw: &str) -> Result<MetarReport, anyhow::Error> {
+    let raw = raw.trim().trim_end_matches('=');
+    let (body, remarks) = raw.split_once(" RMK").map_or((raw, None), |(b, r)| {
+        (b, Some(r.trim().to_string()).filter(|r| !r.is_empty()))
+    });
+    let tokens = body.split_whitespace().collect::<Vec<_>>();
+
+    let mut report = MetarReport {
+        remarks,
+        ..MetarReport::default()
+    };
+    let mut i = 0;
+
+    match tokens.first() {
+        Some(&"METAR") => {
+            report.report_type = Some(ReportType::Metar);
+            i += 1;
+        }
+        Some(&"SPECI") => {
+            report.report_type = Some(ReportType::Speci);
+            i += 1;
+        }
+        _ => {}
+    }
+
+    match tokens.get(i) {
+        Some(station) if station.len() == 4 && station.chars().all(char::is_alphanumeric) => {
+            report.station = (*station).to_string();
+            i += 1;
+        }
+        _ => bail!("Could not find station identifier in METAR: {raw}"),
+    }
+
+    if let Some(c) = tokens.get(i).and_then(|t| TIME_REGEX.captures(t)) {
+        report.time = Some(ObservationTime {
+            day: c[1].parse()?,
+            hour: c[2].parse()?,
+            minute: c[3].parse()?,
+        });
+        i += 1;
+    }
+
+    while i < tokens.len() {
+        let token = tokens[i];
+
+        if TrendKind::from_code(token).is_some() {
+            break;
+        }
+
+        let consumed = parse_conditions_group(&mut report.conditions, &tokens, i);
+        if consumed > 0 {
+            i += consumed;
+            continue;
+        }
+
+        match token {
+            "AUTO" => report.auto = true,
+            "COR" | "CCA" | "CCB" => report.corrected = true,
+            _ => {
+                if let Some(c) = VIS_DIRECTIONAL_REGEX.captures(token) {
+                    report.directional_visibility.push(DirectionalVisibility {
+                        meters: c[1].parse()?,
+                        direction: c[2].to_string(),
+                    });
+                } else if let Some(rvr) = parse_rvr(token) {
+                    report.runway_visual_ranges.push(rvr);
+                } else if let Some(c) = TEMP_REGEX.captures(token) {
+                    report.temperature = Some(parse_signed_temp(&c[1]));
+                    report.dewpoint = c.get(2).map(|d| parse_signed_temp(d.as_str()));
+                } else if let Some(c) = ALTIMETER_REGEX.captures(token) {
+                    // Some stations report both forms, prefer the first one seen
+                    if report.altimeter.is_none() {
+                        let value = c[2].parse::<f64>()?;
+                        report.altimeter = Some(if &c[1] == "A" {
+                            AltimeterSetting::InHg(value / 100.0)
+                        } else {
+                            AltimeterSetting::Hpa(value)
+                        });
+                    }
+                } else {
+                    report.unparsed.push(token.to_string());
+                }
+            }
+        }
+        i += 1;
+    }
+
+    report.trends = parse_trends(&tokens[i..]);
+
+    Ok(report)
+}
+
+/// Parses trend groups (`NOSIG`, `BECMG`, `TEMPO`) following the body of a METAR
+fn parse_trends(tokens: &[&str]) -> Vec<Trend> {
+    let mut trends: Vec<Trend> = vec![];
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let token = tokens[i];
+        if let Some(kind) = TrendKind::from_code(token) {
+            trends.push(Trend {
+                kind,
+                from: None,
+                until: None,
+                at: None,
+                conditions: WeatherConditions::default(),
+            });
+            i += 1;
+            continue;
+        }
+
+        let Some(trend) = trends.last_mut() else {
+            i += 1;
+            continue;
+        };
+
+        if let Some(c) = TREND_TIME_REGEX.captures(token) {
+            let time = format!("{}{}", &c[2], &c[3]);
+            match &c[1] {
+                "FM" => trend.from = Some(time),
+                "TL" => trend.until = Some(time),
+                _ => trend.at = Some(time),
+            }
+            i += 1;
+        } else {
+            i += parse_conditions_group(&mut trend.conditions, tokens, i).max(1);
+        }
+    }
+
+    trends
+}
+
+/// Tries to parse the token at `i` as one of the shared weather groups, returning the number of
+/// tokens consumed (0 if the token is not a recognised group)
+pub fn parse_conditions_group(
+    conditions: &mut WeatherConditions,
+    tokens: &[&str],
+    i: usize,
+) -> usize {
+    let token = tokens[i];
+
+    if let Some(wind) = parse_wind(token) {
+        conditions.wind = Some(wind);
+        return 1;
+    }
+
+    if let Some(c) = WIND_VARIABLE_REGEX.captures(token) {
+        if let Some(wind) = conditions.wind.as_mut() {
+            wind.variable_from = c[1].parse().ok();
+            wind.variable_to = c[2].parse().ok();
+            return 1;
+        }
+    }
+
+    match token {
+        "CAVOK" => {
+            conditions.cavok = true;
+            return 1;
+        }
+        "NSW" => {
+            conditions.no_significant_weather = true;
+            return 1;
+        }
+        "SKC" | "CLR" | "NSC" | "NCD" => {
+            conditions.clouds.push(CloudLayer {
+                cover: token.to_string(),
+                base: None,
+                cloud_type: None,
+            });
+            return 1;
+        }
+        _ => {}
+    }
+
+    // Statute mile visibility can be split into a whole number and a fraction, e.g. `1 1/2SM`
+    if let Some(next) = tokens.get(i + 1) {
+        if token.len() == 1 && token.chars().all(|c| c.is_ascii_digit()) {
+            if let Some(mut vis) = parse_visibility(next) {
+                if vis.unit == DistanceUnit::StatuteMiles && vis.distance < 1.0 {
+                    vis.distance += token.parse::<f64>().unwrap_or_default();
+                    conditions.visibility = Some(vis);
+                    return 2;
+                }
+            }
+        }
+    }
+
+    if let Some(vis) = parse_visibility(token) {
+        conditions.visibility = Some(vis);
+        return 1;
+    }
+
+    if let Some(c) = CLOUD_REGEX.captures(token) {
+        conditions.clouds.push(CloudLayer {
+            cover: c[1].to_string(),
+            base: c[2].parse::<i32>().ok().map(|b| b * 100),
+            cloud_type: c
+                .get(3)
+                .map(|t| t.as_str().to_string())
+                .filter(|t| t != "///"),
+        });
+        return 1;
+    }
+
+    if let Some(c) = VERT_VIS_REGEX.captures(token) {
+        conditions.vertical_visibility = c[1].parse::<i32>().ok().map(|v| v * 100);
+        return 1;
+    }
+
+    if let Some(weather) = parse_weather(token) {
+        conditions.weather.push(weather);
+        return 1;
+    }
+
+    0
+}
+
+pub fn parse_wind(token: &str) -> Option<Wind> {
+    WIND_REGEX.captures(token).map(|c| Wind {
+        direction: c[1].parse().ok(),
+        speed: c[2].parse().unwrap_or_default(),
+        gust: c.get(3).and_then(|g| g.as_str().parse().ok()),
+        unit: match &c[4] {
+            "MPS" => SpeedUnit::MetersPerSecond,
+            "KMH" => SpeedUnit::KilometersPerHour,
+            _ => SpeedUnit::Knots,
+        },
+        variable_from: None,
+        variable_to: None,
+    })
+}
+
+fn parse_visibility(token: &str) -> Option<Visibility> {
+    if let Some(c) = VIS_METERS_REGEX.captures(token) {
+        return Some(Visibility {
+            distance: c[1].parse().ok()?,
+            unit: DistanceUnit::Meters,
+            modifier: None,
+            no_directional_variation: c.get(2).is_some(),
+        });
+    }
+
+    let c = VIS_SM_REGEX.captures(token)?;
+    let whole = c.get(2).and_then(|w| w.as_str().parse::<f64>().ok());
+    let fraction = match (c.get(3), c.get(4)) {
+        (Some(n), Some(d)) => {
+            let d = d.as_str().parse::<f64>().ok()?;
+            Some(n.as_str().parse::<f64>().ok()? / d)
+        }
+        _ => None,
+    };
+    if whole.is_none() && fraction.is_none() {
+        return None;
+    }
+
+    Some(Visibility {
+        distance: whole.unwrap_or_default() + fraction.unwrap_or_default(),
+        unit: DistanceUnit::StatuteMiles,
+        modifier: ValueModifier::from_code(c.get(1).map(|m| m.as_str())),
+        no_directional_variation: false,
+    })
+}
+
+fn parse_rvr(token: &str) -> Option<RunwayVisualRange> {
+    let c = RVR_REGEX.captures(token)?;
+    Some(RunwayVisualRange {
+        runway: c[1].to_string(),
+        range: c[3].parse().ok()?,
+        range_modifier: ValueModifier::from_code(c.get(2).map(|m| m.as_str())),
+        variable_range: c.get(5).and_then(|v| v.as_str().parse().ok()),
+        variable_range_modifier: ValueModifier::from_code(c.get(4).map(|m| m.as_str())),
+        unit: if c.get(6).is_some() {
+            DistanceUnit::Feet
+        } else {
+            DistanceUnit::Meters
+        },
+        tendency: c.get(7).map(|t| match t.as_str() {
+            "U" => RvrTendency::Up,
+            "D" => RvrTendency::Down,
+            _ => RvrTendency::NoChange,
+        }),
+    })
+}
+
+fn parse_weather(token: &str) -> Option<WeatherGroup> {
+    let c = WEATHER_REGEX.captures(token)?;
+    let descriptor = c.get(2).map(|d| d.as_str().to_string());
+    let phenomena = c[3]
+        .as_bytes()
+        .chunks(2)
+        .filter_map(|p| std::str::from_utf8(p).ok().map(ToString::to_string))
+        .collect::<Vec<_>>();
+
+    // A bare intensity or an empty token is not a weather group, but `TS` and `VCSH` are
+    if descriptor.is_none() && phenomena.is_empty() {
+        return None;
+    }
+
+    Some(WeatherGroup {
+        intensity: match c.get(1).map(|i| i.as_str()) {
+            Some("-") => Intensity::Light,
+            Some("+") => Intensity::Heavy,
+            Some("VC") => Intensity::Vicinity,
+            _ => Intensity::Moderate,
+        },
+        descriptor,
+        phenomena,
+    })
+}
+
+fn parse_signed_temp(s: &str) -> i32 {
+    s.strip_prefix('M').map_or_else(
+        || s.parse().unwrap_or_default(),
+        |t| -t.parse::<i32>().unwrap_or_default(),
+    )
+}
+
+/// Resolves a day-of-month/hour/minute group to the candidate in the previous, current or next
+/// month of `reference` that is closest to it. Hour `24` is treated as midnight of the next day.
+pub fn resolve_day_time(
+    reference: DateTime<Utc>,
+    day: u32,
+    hour: u32,
+    minute: u32,
+) -> Option<DateTime<Utc>> {
+    let month_start = NaiveDate::from_ymd_opt(reference.year(), reference.month(), 1)?;
+    [
+        month_start.checked_sub_months(Months::new(1)),
+        Some(month_start),
+        month_start.checked_add_months(Months::new(1)),
+    ]
+    .into_iter()
+    .flatten()
+    .filter_map(|m| {
+        let date = m.with_day(day)?;
+        let time = if hour == 24 {
+            date.and_hms_opt(0, minute, 0)? + Duration::days(1)
+        } else {
+            date.and_hms_opt(hour, minute, 0)?
+        };
+        Some(Utc.from_utc_datetime(&time))
+    })
+    .min_by_key(|t| (*t - reference).num_seconds().abs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KSFO: &str = "KSFO 161256Z 28012KT 10SM FEW010 16/12 A2992 RMK AO2 SLP131 T01610122";
+    const EGLL: &str = "EGLL 161250Z 25014G25KT 220V290 CAVOK 14/08 Q1013 NOSIG";
+    const KDEN: &str = "KDEN 161253Z VRB03KT 10SM CLR 05/M03 A3012 RMK AO2";
+    const UUEE: &str = "METAR UUEE 161300Z 18005MPS CAVOK M10/M15 Q1020 R24L/290050 NOSIG=";
+    const EDDF: &str =
+        "EDDF 161250Z 24008KT 0350 R25L/0600N R25C/P2000 R07R/0450V0800U FG VV001 08/08 Q1021 BECMG 1500";
+    const KLAX: &str =
+        "SPECI KLAX 161305Z AUTO COR 25006KT 1 1/2SM R25R/2400V4000FT/U BR OVC004 M01/ A2990";
+    const EHAM: &str = "EHAM 161255Z 22015KT 9999 -RA BKN012 12/11 Q1005 TEMPO 4000 RA BKN008 BECMG FM1330 22020G30KT";
+
+    fn parse(raw: &str) -> MetarReport {
+        parse_metar(raw).unwrap()
+    }
+
+    #[test]
+    fn decodes_station_time_and_report_type() {
+        for (raw, report_type, station, (day, hour, minute), auto, corrected) in [
+            (KSFO, None, "KSFO", (16, 12, 56), false, false),
+            (
+                UUEE,
+                Some(ReportType::Metar),
+                "UUEE",
+                (16, 13, 0),
+                false,
+                false,
+            ),
+            (
+                KLAX,
+                Some(ReportType::Speci),
+                "KLAX",
+                (16, 13, 5),
+                true,
+                true,
+            ),
+        ] {
+            let report = parse(raw);
+            assert_eq!(report.report_type, report_type, "{raw}");
+            assert_eq!(report.station, station, "{raw}");
+            assert_eq!(
+                report.time,
+                Some(ObservationTime { day, hour, minute }),
+                "{raw}"
+            );
+            assert_eq!(report.auto, auto, "{raw}");
+            assert_eq!(report.corrected, corrected, "{raw}");
+        }
+    }
+
+    #[test]
+    fn decodes_wind() {
+        for (raw, direction, speed_knots, gust_knots, variable) in [
+            (KSFO, Some(280), 12, None, None),
+            (EGLL, Some(250), 14, Some(25), Some((220, 290))),
+            (KDEN, None, 3, None, None),
+            (UUEE, Some(180), 10, None, None),
+        ] {
+            let wind = parse(raw).conditions.wind.unwrap();
+            assert_eq!(wind.direction, direction, "{raw}");
+            assert_eq!(wind.speed_knots(), speed_knots, "{raw}");
+            assert_eq!(wind.gust_knots(), gust_knots, "{raw}");
+            assert_eq!(wind.variable_from.zip(wind.variable_to), variable, "{raw}");
+        }
+    }
+
+    #[test]
+    fn decodes_temperature_and_altimeter() {
+        for (raw, temperature, dewpoint, altimeter) in [
+            (KSFO, Some(16), Some(12), AltimeterSetting::InHg(29.92)),
+            (EGLL, Some(14), Some(8), AltimeterSetting::Hpa(1013.0)),
+            (KDEN, Some(5), Some(-3), AltimeterSetting::InHg(30.12)),
+            (UUEE, Some(-10), Some(-15), AltimeterSetting::Hpa(1020.0)),
+            (KLAX, Some(-1), None, AltimeterSetting::InHg(29.90)),
+        ] {
+            let report = parse(raw);
+            assert_eq!(report.temperature, temperature, "{raw}");
+            assert_eq!(report.dewpoint, dewpoint, "{raw}");
+            assert_eq!(report.altimeter, Some(altimeter), "{raw}");
+        }
+    }
+
+    #[test]
+    fn converts_altimeter_settings() {
+        assert!((AltimeterSetting::Hpa(1013.0).hpa() - 1013.0).abs() < f64::EPSILON);
+        let hpa = AltimeterSetting::InHg(29.92).hpa();
+        assert!((hpa - 1013.2).abs() < 0.05, "{hpa}");
+        assert!((hpa * MBAR_TO_INHG_FACTOR - 29.92).abs() < 1e-9, "{hpa}");
+    }
+
+    #[test]
+    fn decodes_cavok() {
+        for raw in [EGLL, UUEE] {
+            let conditions = parse(raw).conditions;
+            assert!(conditions.cavok, "{raw}");
+            assert_eq!(conditions.visibility, None, "{raw}");
+            assert!(conditions.clouds.is_empty(), "{raw}");
+        }
+        assert!(!parse(KSFO).conditions.cavok);
+    }
+
+    #[test]
+    fn decodes_visibility_clouds_and_weather() {
+        let report = parse(EDDF);
+        assert_eq!(
+            report.conditions.visibility.map(|v| (v.distance, v.unit)),
+            Some((350.0, DistanceUnit::Meters))
+        );
+        assert_eq!(report.conditions.weather[0].code(), "FG");
+        assert_eq!(report.conditions.vertical_visibility, Some(100));
+
+        let report = parse(KLAX);
+        assert_eq!(
+            report.conditions.visibility.map(|v| (v.distance, v.unit)),
+            Some((1.5, DistanceUnit::StatuteMiles))
+        );
+        assert_eq!(
+            report.conditions.clouds,
+            [CloudLayer {
+                cover: "OVC".to_string(),
+                base: Some(400),
+                cloud_type: None,
+            }]
+        );
+
+        let report = parse(EHAM);
+        assert_eq!(report.conditions.weather[0].intensity, Intensity::Light);
+        assert_eq!(report.conditions.weather[0].code(), "-RA");
+    }
+
+    #[test]
+    fn decodes_runway_visual_range() {
+        let rvr = |runway: &str, range, range_modifier, variable_range, unit, tendency| {
+            RunwayVisualRange {
+                runway: runway.to_string(),
+                range,
+                range_modifier,
+                variable_range,
+                variable_range_modifier: None,
+                unit,
+                tendency,
+            }
+        };
+
+        assert_eq!(
+            parse(EDDF).runway_visual_ranges,
+            [
+                rvr(
+                    "25L",
+                    600,
+                    None,
+                    None,
+                    DistanceUnit::Meters,
+                    Some(RvrTendency::NoChange)
+                ),
+                rvr(
+                    "25C",
+                    2000,
+                    Some(ValueModifier::GreaterThan),
+                    None,
+                    DistanceUnit::Meters,
+                    None
+                ),
+                rvr(
+                    "07R",
+                    450,
+                    None,
+                    Some(800),
+                    DistanceUnit::Meters,
+                    Some(RvrTendency::Up)
+                ),
+            ]
+        );
+        assert_eq!(
+            parse(KLAX).runway_visual_ranges,
+            [rvr(
+                "25R",
+                2400,
+                None,
+                Some(4000),
+                DistanceUnit::Feet,
+                Some(RvrTendency::Up)
+            )]
+        );
+    }
+
+    #[test]
+    fn decodes_trends() {
+        let kinds = |raw| parse(raw).trends.iter().map(|t| t.kind).collect::<Vec<_>>();
+        assert_eq!(kinds(EGLL), [TrendKind::NoSignificantChange]);
+        assert_eq!(kinds(KSFO), []);
+
+        let trends = parse(EHAM).trends;
+        assert_eq!(trends.len(), 2);
+        assert_eq!(trends[0].kind, TrendKind::Temporary);
+        assert_eq!(
+            trends[0].conditions.visibility.map(|v| v.distance),
+            Some(4000.0)
+        );
+        assert_eq!(trends[0].conditions.weather[0].code(), "RA");
+        assert_eq!(trends[0].conditions.clouds[0].base, Some(800));
+        assert_eq!(trends[1].kind, TrendKind::Becoming);
+        assert_eq!(trends[1].from.as_deref(), Some("1330"));
+        assert_eq!(
+            trends[1].conditions.wind.and_then(|w| w.gust_knots()),
+            Some(30)
+        );
+
+        // Trend groups are not applied to the current conditions
+        let report = parse(EDDF);
+        assert_eq!(
+            report.conditions.visibility.map(|v| v.distance),
+            Some(350.0)
+        );
+        assert_eq!(
+            report.trends[0].conditions.visibility.map(|v| v.distance),
+            Some(1500.0)
+        );
+    }
+
+    #[test]
+    fn stops_decoding_at_remarks() {
+        let report = parse(KSFO);
+        assert_eq!(report.remarks.as_deref(), Some("AO2 SLP131 T01610122"));
+        assert!(report.unparsed.is_empty(), "{:?}", report.unparsed);
+
+        let report = parse("KSFO 161256Z 28012KT 10SM CLR 16/12 A2992 RMK A3001 Q1013 28030KT");
+        assert_eq!(report.altimeter, Some(AltimeterSetting::InHg(29.92)));
+        assert_eq!(report.conditions.wind.map(|w| w.speed), Some(12));
+        assert_eq!(parse("KSFO 161256Z 28012KT RMK").remarks, None);
+    }
+
+    #[test]
+    fn re_encodes_conditions() {
+        for (raw, code) in [
+            (
+                "KSFO 161256Z 28012G22KT 250V310 10SM FEW010 BKN025CB 16/12 A2992",
+                "28012G22KT 10SM FEW010 BKN025CB",
+            ),
+            (
+                "KLAX 161305Z VRB03KT 1 1/2SM -RA BR OVC004 12/11 A2990",
+                "VRB03KT 1 1/2SM -RA BR OVC004",
+            ),
+            (
+                "KDEN 161253Z 36005KT M1/4SM FZFG VV002 M05/M06 A3012",
+                "36005KT M1/4SM FZFG VV002",
+            ),
+            (
+                "UUEE 161300Z 18005MPS CAVOK M10/M15 Q1020",
+                "18005MPS CAVOK",
+            ),
+            (
+                "EDDF 161250Z 24008KT 0350 FG NSC 08/08 Q1021",
+                "24008KT 0350 FG NSC",
+            ),
+        ] {
+            assert_eq!(parse(raw).conditions.code(), code, "{raw}");
+        }
+    }
+
+    #[test]
+    fn keeps_unknown_groups() {
+        let report = parse(UUEE);
+        assert_eq!(report.unparsed, ["R24L/290050"]);
+    }
+
+    #[test]
+    fn rejects_text_without_station() {
+        assert!(parse_metar("").is_err());
+        assert!(parse_metar("161256Z 28012KT 10SM").is_err());
+    }
+}
@@ -0,0 +1,300 @@
+use crate::atis::parse_code_from_text;
+use crate::state::AppState;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::debug;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use vatsim_utils::models::Atis;
+
+const IVAO_WHAZZUP_URL: &str = "https://api.ivao.aero/v2/tracker/whazzup";
+/// The whazzup feed is regenerated every 15 seconds
+const IVAO_WHAZZUP_MAX_AGE: Duration = Duration::from_secs(15);
+
+/// An ATIS as reported by a network, independent of the network's feed format
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkAtis {
+    pub callsign: String,
+    pub atis_code: Option<String>,
+    pub text_atis: Option<Vec<String>>,
+}
+
+impl From<&Atis> for NetworkAtis {
+    fn from(atis: &Atis) -> Self {
+        Self {
+            callsign: atis.callsign.clone(),
+            atis_code: atis.atis_code.clone(),
+            text_atis: atis.text_atis.clone(),
+        }
+    }
+}
+
+/// All ATIS online on a network at the time its feed was generated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkAtisData {
+    pub atis: Vec<NetworkAtis>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// A flight simulation network that ATIS letters and texts can be read from
+#[async_trait]
+pub trait AtisNetwork: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// All ATIS currently online on the network
+    async fn fetch_atis(&self, state: &AppState) -> Result<NetworkAtisData, anyhow::Error>;
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AtisNetworkKind {
+    #[default]
+    Vatsim,
+    Ivao,
+}
+
+/// Reads ATIS from the VATSIM datafeed shared with the rest of the app
+pub struct VatsimNetwork;
+
+#[async_trait]
+impl AtisNetwork for VatsimNetwork {
+    fn name(&self) -> &'static str {
+        "VATSIM"
+    }
+
+    async fn fetch_atis(&self, state: &AppState) -> Result<NetworkAtisData, anyhow::Error> {
+        state.refresh_vatsim_data_if_stale().await;
+        state
+            .latest_vatsim_data
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|fetch| {
+                fetch.data.as_ref().ok().map(|datafeed| NetworkAtisData {
+                    atis: datafeed.atis.iter().map(NetworkAtis::from).collect(),
+                    updated_at: fetch.update_time,
+                })
+            })
+            .ok_or_else(|| anyhow!("Could not retrieve datafeed"))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Whazzup {
+    updated_at: Option<DateTime<Utc>>,
+    clients: WhazzupClients,
+}
+
+#[derive(Debug, Deserialize)]
+struct WhazzupClients {
+    #[serde(default)]
+    atcs: Vec<WhazzupAtc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WhazzupAtc {
+    callsign: String,
+    atis: Option<WhazzupAtis>,
+}
+
+/// `revision` counts edits of the text rather than giving its letter, so the letter is read from
+/// the text itself
+#[derive(Debug, Deserialize)]
+struct WhazzupAtis {
+    #[serde(default)]
+    lines: Vec<String>,
+}
+
+/// Reads ATIS from the IVAO whazzup v2 feed, where the ATIS is attached to a controller
+/// position rather than being a separate connection
+pub struct IvaoWhazzup {
+    client: Client,
+    url: String,
+    latest: Mutex<Option<(Instant, NetworkAtisData)>>,
+}
+
+impl IvaoWhazzup {
+    pub fn try_new() -> Result<Self, anyhow::Error> {
+        Self::with_url(IVAO_WHAZZUP_URL)
+    }
+
+    fn with_url(url: &str) -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            client: Client::builder().build()?,
+            url: url.to_string(),
+            latest: Mutex::new(None),
+        })
+    }
+
+    fn cached(&self) -> Option<NetworkAtisData> {
+        self.latest
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|(fetched, _)| fetched.elapsed() < IVAO_WHAZZUP_MAX_AGE)
+            .map(|(_, atis)| atis.clone())
+    }
+}
+
+#[async_trait]
+impl AtisNetwork for IvaoWhazzup {
+    fn name(&self) -> &'static str {
+        "IVAO"
+    }
+
+    async fn fetch_atis(&self, _state: &AppState) -> Result<NetworkAtisData, anyhow::Error> {
+        if let Some(atis) = self.cached() {
+            return Ok(atis);
+        }
+
+        debug!("Fetching IVAO whazzup");
+        let whazzup: Whazzup = self
+            .client
+            .get(&self.url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let atis = whazzup
+            .clients
+            .atcs
+            .into_iter()
+            .filter_map(|atc| {
+                let atis = atc.atis.filter(|a| !a.lines.is_empty())?;
+                // IVAO texts are mixed case, e.g. `Information C recorded at 1200z`
+                let uppercase = atis
+                    .lines
+                    .iter()
+                    .map(|l| l.to_uppercase())
+                    .collect::<Vec<_>>();
+                Some(NetworkAtis {
+                    callsign: atc.callsign,
+                    atis_code: parse_code_from_text(&uppercase).map(String::from),
+                    text_atis: Some(atis.lines),
+                })
+            })
+            .collect::<Vec<_>>();
+        let data = NetworkAtisData {
+            atis,
+            updated_at: whazzup.updated_at,
+        };
+
+        *self.latest.lock().unwrap() = Some((Instant::now(), data.clone()));
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datafeed::VatsimDatafeedClient;
+    use crate::state::VatsimDataFetch;
+    use crate::test_utils::MockServer;
+
+    const VATSIM_DATAFEED: &str = include_str!("../fixtures/networks/vatsim-data.json");
+    const IVAO_WHAZZUP: &str = include_str!("../fixtures/networks/ivao-whazzup.json");
+
+    fn network_atis(callsign: &str, code: Option<&str>, text: &[&str]) -> NetworkAtis {
+        NetworkAtis {
+            callsign: callsign.to_string(),
+            atis_code: code.map(ToString::to_string),
+            text_atis: Some(text.iter().map(ToString::to_string).collect()),
+        }
+    }
+
+    #[tokio::test]
+    async fn reads_vatsim_atis_from_datafeed() {
+        let server = MockServer::start(|path| match path {
+            "/v3/vatsim-data.json" => (200, VATSIM_DATAFEED.to_string()),
+            _ => (404, String::new()),
+        })
+        .await;
+        let status = serde_json::json!({
+            "data": {"v3": [format!("{}/v3/vatsim-data.json", server.url())]}
+        });
+        let status_server = MockServer::start(move |_| (200, status.to_string())).await;
+        let datafeed = VatsimDatafeedClient::from_status_url(&status_server.url())
+            .await
+            .unwrap()
+            .fetch()
+            .await
+            .unwrap();
+
+        let state = AppState::new();
+        *state.latest_vatsim_data.lock().unwrap() = Some(VatsimDataFetch::new(Ok(datafeed)));
+        let data = VatsimNetwork.fetch_atis(&state).await.unwrap();
+
+        assert_eq!(data.updated_at, "2024-10-16T12:00:05.1234567Z".parse().ok());
+        assert_eq!(
+            data.atis,
+            [
+                network_atis(
+                    "KSFO_A_ATIS",
+                    Some("C"),
+                    &[
+                        "SFO ARR INFO C 1156Z. 28012KT 10SM FEW010 16/12 A2992.",
+                        "LDG RWY 28L 28R. ADVS YOU HAVE INFO C."
+                    ]
+                ),
+                network_atis(
+                    "KSFO_D_ATIS",
+                    Some("K"),
+                    &["SFO DEP INFO K 1156Z. DEP RWY 1L 1R. ADVS YOU HAVE INFO K."]
+                ),
+                network_atis(
+                    "EDDF_ATIS",
+                    None,
+                    &["FRANKFURT INFORMATION DELTA, MET REPORT TIME 1150Z. RUNWAY IN USE 25C."]
+                ),
+            ]
+        );
+        assert_eq!(server.requests(), ["/v3/vatsim-data.json"]);
+    }
+
+    #[tokio::test]
+    async fn reads_ivao_atis_letter_from_text() {
+        let server = MockServer::start(|path| match path {
+            "/v2/tracker/whazzup" => (200, IVAO_WHAZZUP.to_string()),
+            _ => (404, String::new()),
+        })
+        .await;
+        let ivao = IvaoWhazzup::with_url(&format!("{}/v2/tracker/whazzup", server.url())).unwrap();
+        let state = AppState::new();
+        let data = ivao.fetch_atis(&state).await.unwrap();
+
+        assert_eq!(data.updated_at, "2024-10-16T12:00:15.482Z".parse().ok());
+        // Controllers without an ATIS, or with an empty one, are left out
+        assert_eq!(
+            data.atis,
+            [
+                network_atis(
+                    "LFPG_TWR",
+                    Some("C"),
+                    &[
+                        "LFPG_TWR",
+                        "Charles de Gaulle Tower",
+                        "Information C recorded at 1200z",
+                        "Landing runway 27L, takeoff runway 26R",
+                    ]
+                ),
+                network_atis(
+                    "EGLL_APP",
+                    None,
+                    &[
+                        "EGLL_APP",
+                        "London Director",
+                        "Expect vectors for ILS approach"
+                    ]
+                ),
+            ]
+        );
+
+        // Served from the cache while the feed is fresh
+        ivao.fetch_atis(&state).await.unwrap();
+        assert_eq!(server.requests(), ["/v2/tracker/whazzup"]);
+    }
+}
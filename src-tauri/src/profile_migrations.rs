@@ -0,0 +1,223 @@
+use anyhow::{anyhow, bail};
+use serde_json::{json, Map, Value};
+
+/// Version written to profiles saved by this build. Bump it together with a new entry in
+/// `MIGRATIONS` whenever a profile field is renamed, restructured or removed.
+pub const CURRENT_PROFILE_VERSION: u64 = 2;
+
+type Migration = fn(&mut Map<String, Value>) -> Result<(), anyhow::Error>;
+
+/// `MIGRATIONS[n]` upgrades a profile from version `n` to version `n + 1`
+const MIGRATIONS: &[Migration] = &[migrate_v0_to_v1, migrate_v1_to_v2];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigratedProfile {
+    pub value: Value,
+    /// Version the profile was read as, before any migrations
+    pub from_version: u64,
+}
+
+impl MigratedProfile {
+    pub const fn was_migrated(&self) -> bool {
+        self.from_version < CURRENT_PROFILE_VERSION
+    }
+
+    /// Saved by a newer build, so fields this build doesn't know about would be dropped on save
+    pub const fn is_newer(&self) -> bool {
+        self.from_version > CURRENT_PROFILE_VERSION
+    }
+}
+
+/// Version of raw profile JSON, 0 for profiles written before versions were added
+pub fn profile_version(value: &Value) -> Result<u64, anyhow::Error> {
+    match value.get("version") {
+        None | Some(Value::Null) => Ok(0),
+        Some(v) => v
+            .as_u64()
+            .ok_or_else(|| anyhow!("Invalid profile version {v}")),
+    }
+}
+
+/// Upgrades raw profile JSON to `CURRENT_PROFILE_VERSION` one version at a time. Profiles from a
+/// newer build are returned unchanged.
+pub fn migrate_profile(mut value: Value) -> Result<MigratedProfile, anyhow::Error> {
+    let from_version = profile_version(&value)?;
+    let Some(profile) = value.as_object_mut() else {
+        bail!("Profile is not a JSON object");
+    };
+
+    for (version, migration) in MIGRATIONS
+        .iter()
+        .enumerate()
+        .skip(usize::try_from(from_version)?)
+    {
+        migration(profile)
+            .map_err(|e| anyhow!("Could not migrate profile from version {version}: {e}"))?;
+        profile.insert("version".to_string(), json!(version + 1));
+    }
+
+    Ok(MigratedProfile {
+        value,
+        from_version,
+    })
+}
+
+/// Unversioned profiles gained fields over time with serde defaults. Write those defaults out so
+/// the profile reads the same in every version from here on.
+fn migrate_v0_to_v1(profile: &mut Map<String, Value>) -> Result<(), anyhow::Error> {
+    if !profile.get("stations").is_some_and(Value::is_array) {
+        bail!("Missing station list");
+    }
+
+    let defaults = [
+        ("showInput", json!(true)),
+        ("showTitlebar", json!(true)),
+        ("window", Value::Null),
+        ("units", json!("inHg")),
+        ("hideAirportIfMissingAtis", json!(false)),
+        ("atisChangeAlert", json!("None")),
+        ("atisChangeSound", json!("Chime")),
+        ("network", json!("Vatsim")),
+        ("atisCallsignRules", json!({})),
+    ];
+    for (key, default) in defaults {
+        profile.entry(key).or_insert(default);
+    }
+
+    if let Some(window) = profile.get_mut("window").and_then(Value::as_object_mut) {
+        window.entry("scaleFactor").or_insert(json!(1.0));
+    }
+
+    Ok(())
+}
+
+/// Stations changed from bare IDs to entries with per-station options, and the profile-wide
+/// `atisCallsignRules` map moved into the entries
+fn migrate_v1_to_v2(profile: &mut Map<String, Value>) -> Result<(), anyhow::Error> {
+    let mut rules = match profile.remove("atisCallsignRules") {
+        Some(Value::Object(rules)) => rules,
+        _ => Map::new(),
+    };
+    let Some(stations) = profile.get_mut("stations").and_then(Value::as_array_mut) else {
+        bail!("Missing station list");
+    };
+
+    for station in stations {
+        let Value::String(id) = station else {
+            continue;
+        };
+        let mut entry = Map::new();
+        entry.insert("id".to_string(), json!(id));
+        if let Some(station_rules) = rules.remove(id.as_str()) {
+            entry.insert("atisCallsignRules".to_string(), station_rules);
+        }
+        *station = Value::Object(entry);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! fixture {
+        ($name:literal) => {
+            serde_json::from_str::<Value>(include_str!(concat!(
+                "../fixtures/profiles/",
+                $name,
+                ".json"
+            )))
+            .unwrap()
+        };
+    }
+
+    fn assert_migrates(input: Value, expected: &Value, from_version: u64) {
+        let migrated = migrate_profile(input).unwrap();
+        assert_eq!(migrated.from_version, from_version);
+        assert_eq!(&migrated.value, expected);
+        assert_eq!(
+            profile_version(&migrated.value).unwrap(),
+            CURRENT_PROFILE_VERSION
+        );
+    }
+
+    #[test]
+    fn migrates_v0_minimal() {
+        assert_migrates(fixture!("v0_minimal"), &fixture!("v0_minimal.v2"), 0);
+    }
+
+    #[test]
+    fn migrates_v0_window_and_units() {
+        assert_migrates(
+            fixture!("v0_window_units"),
+            &fixture!("v0_window_units.v2"),
+            0,
+        );
+    }
+
+    #[test]
+    fn migrates_v0_atis_alerts() {
+        assert_migrates(
+            fixture!("v0_atis_alerts"),
+            &fixture!("v0_atis_alerts.v2"),
+            0,
+        );
+    }
+
+    #[test]
+    fn migrates_v0_network_rules() {
+        assert_migrates(
+            fixture!("v0_network_rules"),
+            &fixture!("v0_network_rules.v2"),
+            0,
+        );
+    }
+
+    #[test]
+    fn migrates_v1_station_ids() {
+        for (v1, v2) in [
+            (fixture!("v0_minimal.v1"), fixture!("v0_minimal.v2")),
+            (
+                fixture!("v0_window_units.v1"),
+                fixture!("v0_window_units.v2"),
+            ),
+            (fixture!("v0_atis_alerts.v1"), fixture!("v0_atis_alerts.v2")),
+        ] {
+            assert_migrates(v1, &v2, 1);
+        }
+    }
+
+    #[test]
+    fn migrates_v1_callsign_rules_into_stations() {
+        assert_migrates(
+            fixture!("v0_network_rules.v1"),
+            &fixture!("v0_network_rules.v2"),
+            1,
+        );
+    }
+
+    #[test]
+    fn current_version_is_unchanged() {
+        let profile = fixture!("v0_network_rules.v2");
+        let migrated = migrate_profile(profile.clone()).unwrap();
+        assert!(!migrated.was_migrated());
+        assert_eq!(migrated.value, profile);
+    }
+
+    #[test]
+    fn newer_version_is_unchanged() {
+        let profile =
+            json!({"version": CURRENT_PROFILE_VERSION + 1, "name": "Next", "stations": []});
+        let migrated = migrate_profile(profile.clone()).unwrap();
+        assert!(migrated.is_newer());
+        assert_eq!(migrated.value, profile);
+    }
+
+    #[test]
+    fn rejects_invalid_profiles() {
+        assert!(migrate_profile(json!({"version": "1", "stations": []})).is_err());
+        assert!(migrate_profile(json!({"name": "No stations"})).is_err());
+        assert!(migrate_profile(json!(["KSFO"])).is_err());
+    }
+}
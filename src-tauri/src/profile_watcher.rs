@@ -0,0 +1,133 @@
+use crate::profiles::read_profile_from_file;
+use crate::state::AppState;
+use crate::window::apply_window_state;
+use anyhow::bail;
+use log::{debug, warn};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+pub const PROFILE_CHANGED_EVENT: &str = "profile-changed";
+
+/// Editors and file sync tools often write a file in several steps
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(500);
+
+struct WatchedProfile {
+    path: PathBuf,
+    /// Contents last loaded or saved by the app, so its own writes don't trigger a reload
+    contents: Option<Vec<u8>>,
+    _watcher: RecommendedWatcher,
+}
+
+/// Watches the loaded profile and reloads it when it is changed outside the app
+pub struct ProfileWatcher {
+    watched: Mutex<Option<WatchedProfile>>,
+}
+
+impl ProfileWatcher {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            watched: Mutex::new(None),
+        }
+    }
+
+    /// Starts watching `path` in place of the previously watched profile. Watching the path
+    /// already watched only records its current contents as seen.
+    pub fn watch(&self, app: &AppHandle, path: &Path) -> Result<(), anyhow::Error> {
+        let mut watched = self.watched.lock().unwrap();
+        if let Some(current) = watched.as_mut().filter(|w| w.path == path) {
+            current.contents = std::fs::read(path).ok();
+            return Ok(());
+        }
+
+        // Watch the directory, as many editors replace the file rather than writing to it
+        let Some(dir) = path.parent() else {
+            bail!("Profile {path:?} has no parent directory");
+        };
+        let file_name = path.file_name().map(ToOwned::to_owned);
+        let (tx, rx) = unbounded_channel();
+        let mut watcher =
+            notify::recommended_watcher(move |res: notify::Result<Event>| match res {
+                Ok(event) => {
+                    if event
+                        .paths
+                        .iter()
+                        .any(|p| p.file_name() == file_name.as_deref())
+                    {
+                        let _ = tx.send(());
+                    }
+                }
+                Err(e) => warn!("Profile watcher error: {e:?}"),
+            })?;
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+
+        debug!("Watching profile {path:?}");
+        *watched = Some(WatchedProfile {
+            path: path.to_path_buf(),
+            contents: std::fs::read(path).ok(),
+            _watcher: watcher,
+        });
+        drop(watched);
+
+        tauri::async_runtime::spawn(run_profile_reload(app.clone(), path.to_path_buf(), rx));
+        Ok(())
+    }
+
+    /// Whether `path` is still watched and its contents differ from those last seen
+    fn take_change(&self, path: &Path) -> bool {
+        let mut watched = self.watched.lock().unwrap();
+        let Some(current) = watched.as_mut().filter(|w| w.path == path) else {
+            return false;
+        };
+        let Ok(contents) = std::fs::read(path) else {
+            // Deleted or mid-replace, the next event will pick up the new file
+            return false;
+        };
+        if current.contents.as_ref() == Some(&contents) {
+            return false;
+        }
+
+        current.contents = Some(contents);
+        true
+    }
+}
+
+impl Default for ProfileWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Ends when another profile is watched, as that drops the watcher sending to `changes`
+async fn run_profile_reload(app: AppHandle, path: PathBuf, mut changes: UnboundedReceiver<()>) {
+    while changes.recv().await.is_some() {
+        tokio::time::sleep(RELOAD_DEBOUNCE).await;
+        while changes.try_recv().is_ok() {}
+
+        let Some(state) = app.try_state::<Arc<AppState>>() else {
+            continue;
+        };
+        if !state.profile_watcher.take_change(&path) {
+            continue;
+        }
+
+        debug!("Profile {path:?} changed on disk, reloading");
+        match read_profile_from_file(&path) {
+            Ok(profile) => {
+                if let Some(window) = &profile.window {
+                    if let Err(e) = apply_window_state(&app, window) {
+                        warn!("Could not apply window state of reloaded profile: {e:?}");
+                    }
+                }
+                if let Err(e) = app.emit(PROFILE_CHANGED_EVENT, profile) {
+                    warn!("Could not emit profile change: {e:?}");
+                }
+            }
+            Err(e) => warn!("Could not reload changed profile {path:?}: {e}"),
+        }
+    }
+}
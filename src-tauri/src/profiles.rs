@@ -1,22 +1,38 @@
+use crate::callsigns::AtisCallsignRules;
+use crate::importers::{parse_station_ids, ImportFormat};
+use crate::networks::AtisNetworkKind;
+use crate::profile_migrations::{migrate_profile, profile_version, CURRENT_PROFILE_VERSION};
 use crate::settings::{
     get_appstate_settings, get_latest_profile_path, read_settings_or_default,
-    set_latest_profile_path,
+    replace_recent_profile_path, set_latest_profile_path,
 };
+use crate::state::AppState;
 use crate::window::{
     apply_window_state, get_window_state, set_always_on_top_settings_checked, WindowState,
 };
 use crate::{utils, MAIN_WINDOW_LABEL};
-use log::debug;
-use serde::{Deserialize, Serialize};
+use anyhow::bail;
+use chrono::{DateTime, Utc};
+use log::{debug, info, warn};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::ffi::{OsStr, OsString};
+use std::fs::{File, OpenOptions};
+use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
-use tauri::{AppHandle, Manager, PhysicalPosition, PhysicalSize, Wry};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, PhysicalPosition, PhysicalSize, State, Wry};
 use tauri_plugin_dialog::{DialogExt, FileDialogBuilder};
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Profile {
+    /// Format version, see `profile_migrations`
+    #[serde(default = "current_profile_version")]
+    pub version: u64,
     pub name: String,
-    pub stations: Vec<String>,
+    #[serde(deserialize_with = "deserialize_stations")]
+    pub stations: Vec<StationEntry>,
     #[serde(default = "true_bool")]
     pub show_input: bool,
     #[serde(default = "true_bool")]
@@ -26,6 +42,90 @@ pub struct Profile {
     pub units: AltimeterUnits,
     #[serde(default = "false_bool")]
     pub hide_airport_if_missing_atis: bool,
+    #[serde(default)]
+    pub atis_change_alert: AtisChangeAlert,
+    #[serde(default)]
+    pub atis_change_sound: AtisChangeSound,
+    /// Network the ATIS letters and texts are read from
+    #[serde(default)]
+    pub network: AtisNetworkKind,
+}
+
+/// A row of the window, with options overriding the profile-wide ones for that station
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StationEntry {
+    /// Station ID as entered, ICAO or FAA
+    pub id: String,
+    /// Shown instead of the station ID
+    pub alias: Option<String>,
+    pub units: Option<AltimeterUnits>,
+    pub atis_callsign_rules: Option<AtisCallsignRules>,
+    #[serde(default)]
+    pub alerts: StationAlerts,
+    /// Kept in the profile without showing a row or fetching data
+    #[serde(default)]
+    pub hidden: bool,
+    /// Shown even when airports without an ATIS are hidden
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+impl StationEntry {
+    pub fn new(id: String) -> Self {
+        Self {
+            id,
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StationAlerts {
+    /// Overrides `Profile::atis_change_alert`
+    pub atis_change: Option<AtisChangeAlert>,
+    /// Altimeter notifications are only raised for changes of at least this much between
+    /// consecutive observations
+    pub min_altimeter_change_hpa: Option<f64>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StationEntryOrId {
+    Entry(StationEntry),
+    Id(String),
+}
+
+/// Profiles older than version 2 list bare station IDs
+fn deserialize_stations<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<StationEntry>, D::Error> {
+    Ok(Vec::<StationEntryOrId>::deserialize(deserializer)?
+        .into_iter()
+        .map(|s| match s {
+            StationEntryOrId::Entry(entry) => entry,
+            StationEntryOrId::Id(id) => StationEntry::new(id),
+        })
+        .collect())
+}
+
+impl Profile {
+    fn new(name: String, stations: Vec<StationEntry>) -> Self {
+        Self {
+            version: CURRENT_PROFILE_VERSION,
+            name,
+            stations,
+            show_input: true,
+            show_titlebar: true,
+            window: None,
+            units: AltimeterUnits::default(),
+            hide_airport_if_missing_atis: false,
+            atis_change_alert: AtisChangeAlert::default(),
+            atis_change_sound: AtisChangeSound::default(),
+            network: AtisNetworkKind::default(),
+        }
+    }
 }
 
 const fn true_bool() -> bool {
@@ -36,7 +136,11 @@ const fn false_bool() -> bool {
     false
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+const fn current_profile_version() -> u64 {
+    CURRENT_PROFILE_VERSION
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AltimeterUnits {
     #[default]
     #[allow(non_camel_case_types)]
@@ -45,6 +149,23 @@ pub enum AltimeterUnits {
     hPa,
 }
 
+/// What the UI does when a station's ATIS letter changes
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AtisChangeAlert {
+    #[default]
+    None,
+    Sound,
+    Notification,
+    SoundAndNotification,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AtisChangeSound {
+    #[default]
+    Chime,
+    Beep,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProfileWindowState {
@@ -67,12 +188,67 @@ fn get_or_create_profiles_path() -> Option<PathBuf> {
     profiles_path().and_then(|p| utils::get_or_create_path(&p))
 }
 
+/// Reads a profile, upgrading older versions in memory only. The file is left as is until the
+/// profile is saved, so older builds sharing it can still read it.
 pub fn read_profile_from_file(path: &Path) -> Result<Profile, anyhow::Error> {
-    utils::deserialize_from_file(path)
+    let migrated = migrate_profile(utils::deserialize_from_file(path)?)?;
+    if migrated.is_newer() {
+        warn!(
+            "Profile {path:?} is version {}, newer than supported version {CURRENT_PROFILE_VERSION}",
+            migrated.from_version
+        );
+    } else if migrated.was_migrated() {
+        info!(
+            "Read profile {path:?} as version {CURRENT_PROFILE_VERSION} from version {}",
+            migrated.from_version
+        );
+    }
+
+    Ok(serde_json::from_value(migrated.value)?)
+}
+
+fn backup_path(path: &Path, version: u64) -> PathBuf {
+    let mut backup = OsString::from(path.as_os_str());
+    backup.push(format!(".v{version}.bak"));
+    PathBuf::from(backup)
+}
+
+/// Copies a profile about to be overwritten in a newer version to `.v<version>.bak`, keeping any
+/// backup already there from an earlier upgrade
+fn backup_before_upgrade(path: &Path, version: u64) -> Result<(), anyhow::Error> {
+    let backup = backup_path(path, version);
+    match OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&backup)
+    {
+        Ok(mut file) => {
+            std::io::copy(&mut File::open(path)?, &mut file)?;
+            info!("Upgrading profile {path:?} from version {version}, original kept at {backup:?}");
+        }
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+            debug!(
+                "Upgrading profile {path:?} from version {version}, keeping existing {backup:?}"
+            );
+        }
+        Err(e) => return Err(e.into()),
+    }
+    Ok(())
 }
 
 fn write_profile_to_file(path: &Path, profile: &Profile) -> Result<(), anyhow::Error> {
     debug!("Writing profile to {path:?}");
+    if let Ok(existing) = utils::deserialize_from_file(path) {
+        let version = profile_version(&existing)?;
+        // Saving would drop the fields a newer version added, so leave those files alone
+        if version > CURRENT_PROFILE_VERSION {
+            bail!("{path:?} was saved by a newer version of Mini METARs (profile version {version}), save it under a different name instead");
+        }
+        if version < profile.version {
+            backup_before_upgrade(path, version)?;
+        }
+    }
+
     utils::serialize_to_file(path, profile)
 }
 
@@ -143,6 +319,7 @@ pub fn load_profile_from_path(app: &AppHandle, path: &PathBuf) -> Result<Profile
 #[tauri::command(async)]
 pub fn save_current_profile(mut profile: Profile, app: AppHandle) -> Result<(), String> {
     debug!("Starting Save Current Profile Command");
+    profile.version = CURRENT_PROFILE_VERSION;
     profile.window = get_window_state(&app);
     let last_profile_path = get_latest_profile_path(&app);
     if let Some(path) = last_profile_path {
@@ -155,6 +332,7 @@ pub fn save_current_profile(mut profile: Profile, app: AppHandle) -> Result<(),
 #[tauri::command(async)]
 pub fn save_profile_as(mut profile: Profile, app: AppHandle) -> Result<(), String> {
     debug!("Starting Save Current Profile As Command");
+    profile.version = CURRENT_PROFILE_VERSION;
     profile.window = get_window_state(&app);
     let window = app.get_webview_window(MAIN_WINDOW_LABEL);
     let settings = get_appstate_settings(&app).unwrap_or_else(read_settings_or_default);
@@ -186,3 +364,448 @@ fn save_profile(profile: &Profile, path: &PathBuf, app: &AppHandle) -> Result<()
         }
     }
 }
+
+/// A profile in the profiles directory, identified by its file name without `.json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileSummary {
+    pub name: String,
+    /// The file as found on disk, to load the profile with `load_library_profile`
+    pub path: PathBuf,
+    pub station_count: usize,
+    pub modified: Option<DateTime<Utc>>,
+}
+
+fn library_dir() -> Result<PathBuf, String> {
+    get_or_create_profiles_path().ok_or_else(|| "Could not find profiles directory".to_string())
+}
+
+fn is_profile_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("json"))
+}
+
+/// Path of the profile called `name` in the profiles directory `dir`. An existing profile is
+/// found whatever the case of its extension, a new one gets `.json`.
+fn library_profile_path(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let name = name.trim();
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(format!("Invalid profile name {name:?}"));
+    }
+
+    let existing = std::fs::read_dir(dir).ok().and_then(|entries| {
+        entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .find(|p| is_profile_file(p) && p.file_stem() == Some(OsStr::new(name)))
+    });
+    Ok(existing.unwrap_or_else(|| dir.join(format!("{name}.json"))))
+}
+
+fn existing_library_profile_path(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let path = library_profile_path(dir, name)?;
+    if path.is_file() {
+        Ok(path)
+    } else {
+        Err(format!("Profile {name} does not exist"))
+    }
+}
+
+fn new_library_profile_path(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let path = library_profile_path(dir, name)?;
+    if path.try_exists().unwrap_or(true) {
+        Err(format!("Profile {name} already exists"))
+    } else {
+        Ok(path)
+    }
+}
+
+fn profile_summary(path: &Path) -> Option<ProfileSummary> {
+    let name = path.file_stem()?.to_string_lossy().to_string();
+    let profile: Value = utils::deserialize_from_file(path)
+        .inspect_err(|e| debug!("Skipping unreadable profile {path:?}: {e}"))
+        .ok()?;
+
+    Some(ProfileSummary {
+        name,
+        path: path.to_path_buf(),
+        station_count: profile
+            .get("stations")
+            .and_then(Value::as_array)
+            .map_or(0, Vec::len),
+        modified: path
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .map(DateTime::<Utc>::from),
+    })
+}
+
+/// Profiles in the profiles directory, most recently used first and the rest by name
+#[tauri::command(async)]
+pub fn list_profiles(app: AppHandle) -> Result<Vec<ProfileSummary>, String> {
+    debug!("Starting List Profiles Command");
+    let mut profiles = std::fs::read_dir(library_dir()?)
+        .map_err(|e| e.to_string())?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|p| is_profile_file(p))
+        .filter_map(|p| profile_summary(&p))
+        .collect::<Vec<_>>();
+
+    let recent = get_appstate_settings(&app)
+        .map(|s| s.recent_profiles().to_vec())
+        .unwrap_or_default();
+    profiles.sort_by_cached_key(|p| {
+        (
+            recent
+                .iter()
+                .position(|r| *r == p.path)
+                .unwrap_or(usize::MAX),
+            p.name.to_lowercase(),
+        )
+    });
+
+    Ok(profiles)
+}
+
+/// Loads a profile by the path `list_profiles` returned for it
+#[tauri::command(async)]
+pub fn load_library_profile(path: PathBuf, app: AppHandle) -> Result<Profile, String> {
+    debug!("Starting Load Library Profile Command for {path:?}");
+    if path.parent() != Some(library_dir()?.as_path()) || !is_profile_file(&path) {
+        return Err(format!("{path:?} is not in the profiles directory"));
+    }
+    load_profile_from_path(&app, &path)
+}
+
+#[tauri::command(async)]
+pub fn rename_profile(name: &str, new_name: &str, app: AppHandle) -> Result<(), String> {
+    debug!("Starting Rename Profile Command from {name} to {new_name}");
+    let dir = library_dir()?;
+    let from = existing_library_profile_path(&dir, name)?;
+    let to = new_library_profile_path(&dir, new_name)?;
+    let loaded = get_latest_profile_path(&app).is_some_and(|p| p == from);
+    std::fs::rename(&from, &to).map_err(|e| e.to_string())?;
+    replace_recent_profile_path(&app, &from, Some(&to));
+    if loaded {
+        // Keep saving to and watching the loaded profile under its new name
+        set_latest_profile_path(&app, &to);
+    }
+    Ok(())
+}
+
+#[tauri::command(async)]
+pub fn duplicate_profile(name: &str, new_name: &str) -> Result<(), String> {
+    debug!("Starting Duplicate Profile Command from {name} to {new_name}");
+    let dir = library_dir()?;
+    let from = existing_library_profile_path(&dir, name)?;
+    let to = new_library_profile_path(&dir, new_name)?;
+    std::fs::copy(from, to).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command(async)]
+pub fn delete_profile(name: &str, app: AppHandle) -> Result<(), String> {
+    debug!("Starting Delete Profile Command for {name}");
+    let path = existing_library_profile_path(&library_dir()?, name)?;
+    std::fs::remove_file(&path).map_err(|e| e.to_string())?;
+    replace_recent_profile_path(&app, &path, None);
+    Ok(())
+}
+
+/// Picks a name not yet used in the profiles directory `dir`, adding a number if needed
+fn unused_library_profile_name(dir: &Path, name: &str) -> Result<String, String> {
+    (1..100)
+        .map(|n| {
+            if n == 1 {
+                name.to_string()
+            } else {
+                format!("{name} ({n})")
+            }
+        })
+        .find(|n| new_library_profile_path(dir, n).is_ok())
+        .ok_or_else(|| format!("Profile {name} already exists"))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportProfileResponse {
+    pub name: String,
+    pub profile: Profile,
+    /// IDs from the file that did not match a known station, and were left out of the profile
+    pub unresolved: Vec<String>,
+}
+
+/// Creates a profile in the profiles directory from the stations of a vATIS profile, EuroScope
+/// sector file or plain list, and loads it
+#[tauri::command]
+pub async fn import_profile(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<ImportProfileResponse, String> {
+    debug!("Starting Import Profile Command");
+    let window = app.get_webview_window(MAIN_WINDOW_LABEL);
+    let settings = get_appstate_settings(&app).unwrap_or_else(read_settings_or_default);
+    set_always_on_top_settings_checked(window.as_ref(), &settings, false)?;
+
+    // The dialog blocks until closed, so keep it off the async runtime's worker threads
+    let dialog_app = app.clone();
+    let pick_response = tauri::async_runtime::spawn_blocking(move || {
+        dialog_app
+            .dialog()
+            .file()
+            .add_filter("Station lists", &["json", "sct", "ese", "csv", "txt"])
+            .blocking_pick_file()
+    })
+    .await;
+
+    set_always_on_top_settings_checked(window.as_ref(), &settings, true)?;
+
+    let path = pick_response
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Could not pick file".to_string())?
+        .path;
+    let contents = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let ids = parse_station_ids(ImportFormat::from_path(&path), &contents)
+        .map_err(|e| format!("Could not read stations from {path:?}: {e}"))?;
+
+    let client = state.get_awc_client().await.map_err(|e| e.to_string())?;
+    let mut stations = vec![];
+    let mut unresolved = vec![];
+    for id in ids {
+        match client.lookup_station(&id) {
+            Ok(station) => stations.push(StationEntry::new(station.icao_id)),
+            Err(e) => {
+                debug!("Could not resolve imported station {id}: {e}");
+                unresolved.push(id);
+            }
+        }
+    }
+    if stations.is_empty() {
+        return Err(format!(
+            "None of the stations in {path:?} could be found: {}",
+            unresolved.join(", ")
+        ));
+    }
+
+    let file_stem = path.file_stem().map_or_else(
+        || "Imported".to_string(),
+        |s| s.to_string_lossy().to_string(),
+    );
+    let dir = library_dir()?;
+    let name = unused_library_profile_name(&dir, &file_stem)?;
+    let profile_path = new_library_profile_path(&dir, &name)?;
+    write_profile_to_file(&profile_path, &Profile::new(name.clone(), stations))
+        .map_err(|e| e.to_string())?;
+    info!(
+        "Imported profile {name} from {path:?}, {} stations not found",
+        unresolved.len()
+    );
+
+    Ok(ImportProfileResponse {
+        profile: load_profile_from_path(&app, &profile_path)?,
+        name,
+        unresolved,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempDir;
+    use serde_json::json;
+    use std::fs;
+
+    const V0_PROFILE: &str = include_str!("../fixtures/profiles/v0_minimal.json");
+
+    fn profile() -> Profile {
+        Profile::new(
+            "Bay Area".to_string(),
+            vec![StationEntry::new("KSFO".to_string())],
+        )
+    }
+
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn reads_station_ids_and_entries() {
+        let profile: Profile = serde_json::from_value(json!({
+            "name": "Mixed",
+            "stations": [
+                "KSFO",
+                {"id": "KOAK"},
+                {
+                    "id": "EGLL",
+                    "alias": "LHR",
+                    "units": "hPa",
+                    "atisCallsignRules": {"aliases": ["LON"]},
+                    "alerts": {"atisChange": "Sound", "minAltimeterChangeHpa": 1.5},
+                    "hidden": true,
+                    "pinned": true
+                }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(
+            profile.stations,
+            [
+                StationEntry::new("KSFO".to_string()),
+                StationEntry::new("KOAK".to_string()),
+                StationEntry {
+                    id: "EGLL".to_string(),
+                    alias: Some("LHR".to_string()),
+                    units: Some(AltimeterUnits::hPa),
+                    atis_callsign_rules: Some(AtisCallsignRules {
+                        aliases: vec!["LON".to_string()],
+                        ..AtisCallsignRules::default()
+                    }),
+                    alerts: StationAlerts {
+                        atis_change: Some(AtisChangeAlert::Sound),
+                        min_altimeter_change_hpa: Some(1.5),
+                    },
+                    hidden: true,
+                    pinned: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_station_entries() {
+        for stations in [json!([1]), json!([{"alias": "SFO"}]), json!("KSFO")] {
+            let profile = json!({"name": "Invalid", "stations": stations});
+            assert!(
+                serde_json::from_value::<Profile>(profile).is_err(),
+                "{stations}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_profile_names_outside_library() {
+        let dir = TempDir::new("profile-names");
+        for name in [
+            "",
+            "  ",
+            "..",
+            ".hidden",
+            "../Bay Area",
+            "Bay/Area",
+            "Bay\\Area",
+        ] {
+            assert!(library_profile_path(dir.path(), name).is_err(), "{name:?}");
+        }
+        assert_eq!(
+            library_profile_path(dir.path(), " Bay Area ").unwrap(),
+            dir.path().join("Bay Area.json")
+        );
+    }
+
+    #[test]
+    fn finds_existing_profiles_whatever_the_extension_case() {
+        let dir = TempDir::new("profile-extension");
+        let upper = dir.path().join("Bay Area.JSON");
+        fs::write(&upper, V0_PROFILE).unwrap();
+        fs::write(dir.path().join("Notes.txt"), "").unwrap();
+
+        assert_eq!(library_profile_path(dir.path(), "Bay Area").unwrap(), upper);
+        assert_eq!(
+            existing_library_profile_path(dir.path(), "Bay Area").unwrap(),
+            upper
+        );
+        assert!(new_library_profile_path(dir.path(), "Bay Area").is_err());
+
+        // Other extensions are not profiles
+        assert!(existing_library_profile_path(dir.path(), "Notes").is_err());
+        assert_eq!(
+            new_library_profile_path(dir.path(), "Notes").unwrap(),
+            dir.path().join("Notes.json")
+        );
+    }
+
+    #[test]
+    fn numbers_imported_profile_names_until_unused() {
+        let dir = TempDir::new("profile-numbering");
+        assert_eq!(
+            unused_library_profile_name(dir.path(), "Bay Area").unwrap(),
+            "Bay Area"
+        );
+
+        fs::write(dir.path().join("Bay Area.json"), V0_PROFILE).unwrap();
+        assert_eq!(
+            unused_library_profile_name(dir.path(), "Bay Area").unwrap(),
+            "Bay Area (2)"
+        );
+
+        fs::write(dir.path().join("Bay Area (2).JSON"), V0_PROFILE).unwrap();
+        assert_eq!(
+            unused_library_profile_name(dir.path(), "Bay Area").unwrap(),
+            "Bay Area (3)"
+        );
+        assert!(unused_library_profile_name(dir.path(), "..").is_err());
+    }
+
+    #[test]
+    fn backs_up_older_profile_once_when_saving() {
+        let dir = TempDir::new("profile-backup");
+        let path = dir.path().join("Bay Area.json");
+        let backup = dir.path().join("Bay Area.json.v0.bak");
+        fs::write(&path, V0_PROFILE).unwrap();
+
+        write_profile_to_file(&path, &profile()).unwrap();
+        assert_eq!(fs::read_to_string(&backup).unwrap(), V0_PROFILE);
+        assert_eq!(
+            read_profile_from_file(&path).unwrap().version,
+            CURRENT_PROFILE_VERSION
+        );
+
+        // Saving in the current version needs no backup
+        write_profile_to_file(&path, &profile()).unwrap();
+        // Nor is the first backup replaced after an older build saved over the file again
+        let resaved = V0_PROFILE.replace("KSJC", "KHWD");
+        fs::write(&path, &resaved).unwrap();
+        write_profile_to_file(&path, &profile()).unwrap();
+
+        assert_eq!(fs::read_to_string(&backup).unwrap(), V0_PROFILE);
+        assert_eq!(
+            file_names(dir.path()),
+            ["Bay Area.json", "Bay Area.json.v0.bak"]
+        );
+    }
+
+    #[test]
+    fn writes_new_profiles_without_backup() {
+        let dir = TempDir::new("profile-new");
+        let path = dir.path().join("New.json");
+        write_profile_to_file(&path, &profile()).unwrap();
+        assert_eq!(file_names(dir.path()), ["New.json"]);
+    }
+
+    #[test]
+    fn refuses_to_overwrite_newer_profile() {
+        let dir = TempDir::new("profile-newer");
+        let path = dir.path().join("Future.json");
+        let newer = json!({
+            "version": CURRENT_PROFILE_VERSION + 1,
+            "name": "Future",
+            "stations": [],
+            "addedLater": true
+        })
+        .to_string();
+        fs::write(&path, &newer).unwrap();
+
+        let error = write_profile_to_file(&path, &profile()).unwrap_err();
+        assert!(error.to_string().contains("newer version"), "{error}");
+        assert_eq!(fs::read_to_string(&path).unwrap(), newer);
+        assert_eq!(file_names(dir.path()), ["Future.json"]);
+    }
+}
@@ -0,0 +1,381 @@
+use crate::awc::{Cloud, MetarDto, StringOrF64, StringOrI32};
+use crate::metar::{parse_metar, ReportType, WeatherGroup};
+use anyhow::{anyhow, bail};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::debug;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+const NOAA_TGFTP_BASE_URL: &str = "https://tgftp.nws.noaa.gov";
+const VATSIM_METAR_BASE_URL: &str = "https://metar.vatsim.net";
+
+/// A source of current METARs
+#[async_trait]
+pub trait WeatherProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn fetch_metar(&self, station_id: &str) -> Result<MetarDto, anyhow::Error>;
+
+    async fn fetch_metars(&self, station_ids: &[&str]) -> Result<Vec<MetarDto>, anyhow::Error>;
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WeatherProviderKind {
+    /// aviationweather.gov JSON API
+    #[default]
+    Awc,
+    /// NOAA `stations/*.TXT` files
+    NoaaTgftp,
+    /// `metar.vatsim.net` plain text endpoint
+    VatsimMetar,
+}
+
+impl WeatherProviderKind {
+    /// Creates a plain text provider, `None` for AWC which is backed by the shared station client
+    pub fn plain_text_provider(
+        self,
+        base_url: Option<&str>,
+    ) -> Result<Option<PlainTextProvider>, anyhow::Error> {
+        let (name, default_base_url, format) = match self {
+            Self::Awc => return Ok(None),
+            Self::NoaaTgftp => (
+                "NOAA tgftp",
+                NOAA_TGFTP_BASE_URL,
+                PlainTextFormat::NoaaTgftp,
+            ),
+            Self::VatsimMetar => (
+                "VATSIM METAR",
+                VATSIM_METAR_BASE_URL,
+                PlainTextFormat::Vatsim,
+            ),
+        };
+
+        Ok(Some(PlainTextProvider {
+            name,
+            client: Client::builder().build()?,
+            base_url: base_url
+                .unwrap_or(default_base_url)
+                .trim_end_matches('/')
+                .to_string(),
+            format,
+        }))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PlainTextFormat {
+    /// One file per station, a timestamp line followed by the raw METAR
+    NoaaTgftp,
+    /// Raw METARs one per line, with comma separated IDs accepted in a single request
+    Vatsim,
+}
+
+/// Provider for sources that only serve raw METAR text, decoded with `parse_metar`. Station
+/// metadata (name, position, elevation) is not available from these sources and is left empty.
+pub struct PlainTextProvider {
+    name: &'static str,
+    client: Client,
+    base_url: String,
+    format: PlainTextFormat,
+}
+
+impl PlainTextProvider {
+    async fn fetch_text(&self, url: &str) -> Result<String, anyhow::Error> {
+        debug!("Fetching METAR text from {url}");
+        Ok(self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?)
+    }
+
+    async fn fetch_raw(&self, station_ids: &[&str]) -> Result<Vec<String>, anyhow::Error> {
+        match self.format {
+            PlainTextFormat::NoaaTgftp => {
+                // A missing file for one station should not fail the whole batch
+                let mut raws = vec![];
+                for id in station_ids {
+                    match self
+                        .fetch_text(&format!(
+                            "{}/data/observations/metar/stations/{}.TXT",
+                            self.base_url,
+                            id.to_uppercase()
+                        ))
+                        .await
+                    {
+                        Ok(text) => raws.push(text.lines().skip(1).collect::<Vec<_>>().join(" ")),
+                        Err(e) if station_ids.len() > 1 => {
+                            debug!("Could not fetch METAR for {id} from {}: {e:?}", self.name);
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+                Ok(raws)
+            }
+            PlainTextFormat::Vatsim => {
+                let text = self
+                    .fetch_text(&format!(
+                        "{}/metar.php?id={}",
+                        self.base_url,
+                        station_ids.join(",").to_uppercase()
+                    ))
+                    .await?;
+                Ok(text.lines().map(ToString::to_string).collect())
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl WeatherProvider for PlainTextProvider {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    async fn fetch_metar(&self, station_id: &str) -> Result<MetarDto, anyhow::Error> {
+        if station_id.starts_with('@') || station_id.len() > 4 || station_id.contains(',') {
+            bail!("Invalid station ID, must be a single ICAO ID")
+        }
+
+        self.fetch_metars(&[station_id])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("No METARs found in result list"))
+    }
+
+    async fn fetch_metars(&self, station_ids: &[&str]) -> Result<Vec<MetarDto>, anyhow::Error> {
+        let now = Utc::now();
+        Ok(self
+            .fetch_raw(station_ids)
+            .await?
+            .iter()
+            .map(|raw| raw.trim())
+            .filter(|raw| !raw.is_empty())
+            .filter_map(|raw| {
+                metar_dto_from_raw(raw, now)
+                    .inspect_err(|e| debug!("Could not decode METAR from {}: {e:?}", self.name))
+                    .ok()
+            })
+            .collect())
+    }
+}
+
+/// Builds the same shape of METAR that AWC returns from the raw text alone
+pub fn metar_dto_from_raw(raw: &str, reference: DateTime<Utc>) -> Result<MetarDto, anyhow::Error> {
+    let report = parse_metar(raw)?;
+    let obs_time = report
+        .time
+        .and_then(|t| t.resolve(reference))
+        .ok_or_else(|| anyhow!("Could not determine observation time of METAR: {raw}"))?;
+    let conditions = &report.conditions;
+
+    let visib = if conditions.cavok {
+        StringOrF64::String("6+".to_string())
+    } else {
+        conditions
+            .visibility
+            .map_or(StringOrF64::String(String::new()), |v| {
+                let sm = v.statute_miles();
+                if sm >= 6.0 {
+                    StringOrF64::String("6+".to_string())
+                } else {
+                    StringOrF64::F64((sm * 100.0).round() / 100.0)
+                }
+            })
+    };
+
+    let mut clouds = conditions
+        .clouds
+        .iter()
+        .map(|c| Cloud {
+            cover: c.cover.clone(),
+            base: c.base,
+        })
+        .collect::<Vec<_>>();
+    if conditions.cavok {
+        clouds.push(Cloud {
+            cover: "CAVOK".to_string(),
+            base: None,
+        });
+    }
+
+    let wx_string = conditions
+        .weather
+        .iter()
+        .map(WeatherGroup::code)
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Ok(MetarDto {
+        icao_id: report.station.clone(),
+        receipt_time: Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        obs_time,
+        report_time: obs_time.format("%Y-%m-%d %H:%M:%S").to_string(),
+        temp: report.temperature.map(f64::from),
+        dewp: report.dewpoint.map(f64::from),
+        wdir: conditions.wind.map(|w| {
+            w.direction
+                .map_or_else(|| StringOrI32::String("VRB".to_string()), StringOrI32::I32)
+        }),
+        wspd: conditions.wind.map(|w| w.speed_knots()),
+        wgst: conditions.wind.and_then(|w| w.gust_knots()),
+        visib,
+        // AWC reports altimeter in hPa, a missing value is displayed as blank by the frontend
+        altim: report.altimeter.map_or(0.0, |a| a.hpa()),
+        slp: None,
+        qc_field: 0,
+        wx_string: Some(wx_string).filter(|w| !w.is_empty()),
+        pres_tend: None,
+        max_t: None,
+        min_t: None,
+        max_t24: None,
+        min_t24: None,
+        precip: None,
+        pcp3hr: None,
+        pcp6hr: None,
+        pcp24hr: None,
+        snow: None,
+        vert_vis: conditions.vertical_visibility,
+        metar_type: match report.report_type {
+            Some(ReportType::Speci) => "SPECI",
+            _ => "METAR",
+        }
+        .to_string(),
+        raw_ob: raw.to_string(),
+        most_recent: Some(1),
+        lat: 0.0,
+        lon: 0.0,
+        elev: 0,
+        prior: None,
+        name: report.station,
+        clouds,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::MockServer;
+
+    const KSFO: &str = "KSFO 161256Z 28012KT 10SM FEW010 16/12 A2992 RMK AO2 SLP131";
+    const EGLL: &str = "EGLL 161250Z 25014KT CAVOK 14/08 Q1013 NOSIG";
+
+    fn provider(kind: WeatherProviderKind, server: &MockServer) -> PlainTextProvider {
+        // Trailing slashes on configured URLs are ignored
+        kind.plain_text_provider(Some(&format!("{}/", server.url())))
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn fetches_noaa_station_files() {
+        let server = MockServer::start(|path| match path {
+            "/data/observations/metar/stations/KSFO.TXT" => {
+                (200, format!("2024/10/16 12:56\n{KSFO}\n"))
+            }
+            _ => (404, String::new()),
+        })
+        .await;
+        let provider = provider(WeatherProviderKind::NoaaTgftp, &server);
+
+        let metars = provider.fetch_metars(&["ksfo", "KXXX"]).await.unwrap();
+        assert_eq!(
+            server.requests(),
+            [
+                "/data/observations/metar/stations/KSFO.TXT",
+                "/data/observations/metar/stations/KXXX.TXT"
+            ]
+        );
+        assert_eq!(metars.len(), 1);
+        assert_eq!(metars[0].icao_id, "KSFO");
+        assert_eq!(metars[0].raw_ob, KSFO);
+        assert_eq!(metars[0].wspd, Some(12));
+        assert!((metars[0].altimeter_in_hg() - 29.92).abs() < f64::EPSILON);
+
+        // A single missing station is an error rather than an empty batch
+        assert!(provider.fetch_metar("KXXX").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn fetches_vatsim_metars_in_one_request() {
+        let server = MockServer::start(|path| match path {
+            "/metar.php?id=KSFO,EGLL,KXXX" => (200, format!("{KSFO}\n{EGLL}\nNo METAR\n")),
+            _ => (404, String::new()),
+        })
+        .await;
+        let provider = provider(WeatherProviderKind::VatsimMetar, &server);
+
+        let metars = provider
+            .fetch_metars(&["KSFO", "egll", "KXXX"])
+            .await
+            .unwrap();
+        assert_eq!(server.requests(), ["/metar.php?id=KSFO,EGLL,KXXX"]);
+        assert_eq!(
+            metars
+                .iter()
+                .map(|m| m.icao_id.as_str())
+                .collect::<Vec<_>>(),
+            ["KSFO", "EGLL"]
+        );
+        assert!((metars[1].altimeter_hpa() - 1013.0).abs() < f64::EPSILON);
+    }
+
+    #[tokio::test]
+    async fn reports_http_errors() {
+        let server = MockServer::start(|_| (500, String::new())).await;
+        let provider = provider(WeatherProviderKind::VatsimMetar, &server);
+        assert!(provider.fetch_metars(&["KSFO"]).await.is_err());
+    }
+
+    #[test]
+    fn uses_default_base_urls() {
+        assert!(WeatherProviderKind::Awc
+            .plain_text_provider(None)
+            .unwrap()
+            .is_none());
+        let noaa = WeatherProviderKind::NoaaTgftp
+            .plain_text_provider(None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(noaa.base_url, NOAA_TGFTP_BASE_URL);
+        let vatsim = WeatherProviderKind::VatsimMetar
+            .plain_text_provider(None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(vatsim.base_url, VATSIM_METAR_BASE_URL);
+    }
+
+    #[test]
+    fn builds_metar_from_raw_text() {
+        let reference = DateTime::parse_from_rfc3339("2024-10-16T13:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let metar = metar_dto_from_raw(EGLL, reference).unwrap();
+        assert_eq!(
+            metar.obs_time,
+            DateTime::parse_from_rfc3339("2024-10-16T12:50:00Z").unwrap()
+        );
+        assert_eq!(metar.visib, StringOrF64::String("6+".to_string()));
+        assert_eq!(metar.clouds.len(), 1);
+        assert_eq!(metar.clouds[0].cover, "CAVOK");
+        assert_eq!(metar.temp, Some(14.0));
+
+        let metar = metar_dto_from_raw(
+            "KSFO 161256Z VRB03KT 1/2SM FG VV002 M01/M02 A2992",
+            reference,
+        )
+        .unwrap();
+        assert_eq!(metar.wdir, Some(StringOrI32::String("VRB".to_string())));
+        assert_eq!(metar.visib, StringOrF64::F64(0.5));
+        assert_eq!(metar.wx_string.as_deref(), Some("FG"));
+        assert_eq!(metar.vert_vis, Some(200));
+        assert_eq!(metar.temp, Some(-1.0));
+
+        assert!(metar_dto_from_raw("KSFO 28012KT A2992", reference).is_err());
+    }
+}
@@ -0,0 +1,449 @@
+use crate::awc::{MetarDto, StringOrI32};
+use crate::utils;
+use anyhow::anyhow;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+
+const OURAIRPORTS_RUNWAYS_URL: &str =
+    "https://davidmegginson.github.io/ourairports-data/runways.csv";
+
+/// Runway idents are rounded to the nearest 10 degrees, anything further off is not a usable
+/// magnetic heading (e.g. a misnumbered runway or a helipad)
+const MAX_IDENT_VARIATION_DEG: f64 = 30.0;
+
+/// A single row of an OurAirports-style `runways.csv`
+#[derive(Debug, Deserialize)]
+struct RunwayRecord {
+    airport_ident: String,
+    length_ft: Option<i32>,
+    width_ft: Option<i32>,
+    surface: Option<String>,
+    lighted: Option<u8>,
+    closed: Option<u8>,
+    le_ident: Option<String>,
+    #[serde(rename = "le_heading_degT")]
+    le_heading_deg_t: Option<f64>,
+    he_ident: Option<String>,
+    #[serde(rename = "he_heading_degT")]
+    he_heading_deg_t: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Runway {
+    pub length_ft: Option<i32>,
+    pub width_ft: Option<i32>,
+    pub surface: Option<String>,
+    pub lighted: bool,
+    pub closed: bool,
+    pub ends: Vec<RunwayEnd>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunwayEnd {
+    pub ident: String,
+    pub true_heading: Option<f64>,
+}
+
+impl RunwayEnd {
+    /// Magnetic heading implied by the runway number, e.g. `27L` gives 270
+    fn ident_heading(&self) -> Option<f64> {
+        let digits = self
+            .ident
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .collect::<String>();
+        digits
+            .parse::<u32>()
+            .ok()
+            .filter(|n| (1..=36).contains(n))
+            .map(|n| f64::from(n * 10))
+    }
+}
+
+/// Runways keyed by the uppercase airport ident
+pub struct RunwayDatabase {
+    runways: HashMap<String, Vec<Runway>>,
+}
+
+impl RunwayDatabase {
+    pub fn from_reader<R: Read>(reader: R) -> Self {
+        let mut runways: HashMap<String, Vec<Runway>> = HashMap::new();
+        for record in csv::Reader::from_reader(reader).deserialize::<RunwayRecord>() {
+            let record = match record {
+                Ok(r) => r,
+                Err(e) => {
+                    debug!("Skipping unreadable runway record: {e}");
+                    continue;
+                }
+            };
+
+            let ends = [
+                (record.le_ident, record.le_heading_deg_t),
+                (record.he_ident, record.he_heading_deg_t),
+            ]
+            .into_iter()
+            .filter_map(|(ident, true_heading)| {
+                ident.filter(|i| !i.is_empty()).map(|ident| RunwayEnd {
+                    ident,
+                    true_heading,
+                })
+            })
+            .collect::<Vec<_>>();
+
+            runways
+                .entry(record.airport_ident.to_uppercase())
+                .or_default()
+                .push(Runway {
+                    length_ft: record.length_ft,
+                    width_ft: record.width_ft,
+                    surface: record.surface,
+                    lighted: record.lighted == Some(1),
+                    closed: record.closed == Some(1),
+                    ends,
+                });
+        }
+
+        Self { runways }
+    }
+
+    /// Loads a user-supplied `runways.csv` from the config directory, downloading a copy into that
+    /// location first if none exists
+    pub async fn load_or_download() -> Result<Self, anyhow::Error> {
+        let path =
+            runways_path().ok_or_else(|| anyhow!("Could not construct path to runways.csv"))?;
+
+        if !path.try_exists().unwrap_or_default() {
+            debug!("No runways.csv found at {path:?}, downloading from OurAirports");
+            let bytes = reqwest::get(OURAIRPORTS_RUNWAYS_URL)
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?;
+            if path.parent().and_then(utils::get_or_create_path).is_some() {
+                if let Err(e) = fs::write(&path, &bytes) {
+                    warn!("Could not save runways.csv to {path:?}: {e}");
+                }
+            }
+            return Ok(Self::from_reader(&bytes[..]));
+        }
+
+        debug!("Reading runways from {path:?}");
+        Ok(Self::from_reader(fs::File::open(path)?))
+    }
+
+    pub fn runways_for(&self, ident: &str) -> Option<&[Runway]> {
+        self.runways.get(&ident.to_uppercase()).map(Vec::as_slice)
+    }
+}
+
+fn runways_path() -> Option<PathBuf> {
+    dirs::config_local_dir().map(|p| p.join("Mini METARs").join("runways.csv"))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunwayWindComponents {
+    pub runway: String,
+    pub true_heading: f64,
+    pub magnetic_heading: f64,
+    pub length_ft: Option<i32>,
+    /// Negative values are a tailwind
+    pub headwind: Option<f64>,
+    /// Positive values are from the right, negative from the left
+    pub crosswind: Option<f64>,
+    pub gust_headwind: Option<f64>,
+    pub gust_crosswind: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunwayWindsResponse {
+    pub station: String,
+    pub wind_string: String,
+    /// Estimated from the difference between runway numbers and true headings, east positive
+    pub magnetic_variation: Option<f64>,
+    pub runways: Vec<RunwayWindComponents>,
+    pub favoured_runway: Option<String>,
+}
+
+/// Estimates the local magnetic variation as the mean difference between each runway end's true
+/// heading and the magnetic heading implied by its number
+pub fn estimate_magnetic_variation(runways: &[Runway]) -> Option<f64> {
+    let variations = runways
+        .iter()
+        .flat_map(|r| &r.ends)
+        .filter_map(|e| {
+            let true_heading = e.true_heading?;
+            let variation = normalize_relative(true_heading - e.ident_heading()?);
+            (variation.abs() <= MAX_IDENT_VARIATION_DEG).then_some(variation)
+        })
+        .collect::<Vec<_>>();
+
+    if variations.is_empty() {
+        None
+    } else {
+        #[allow(clippy::cast_precision_loss)]
+        let mean = variations.iter().sum::<f64>() / variations.len() as f64;
+        Some(mean.round())
+    }
+}
+
+/// METAR winds are reported relative to true north, so components are computed against each
+/// runway's true heading. Where the data has no true heading, it is derived from the runway
+/// number and the estimated magnetic variation.
+pub fn runway_winds(station: &str, metar: &MetarDto, runways: &[Runway]) -> RunwayWindsResponse {
+    let variation = estimate_magnetic_variation(runways);
+    let wind_dir = match metar.wdir {
+        Some(StringOrI32::I32(d)) => Some(f64::from(d)),
+        _ => None,
+    };
+    let wind_speed = metar.wspd.map(f64::from);
+    let wind_gust = metar.wgst.map(f64::from);
+
+    let components = runways
+        .iter()
+        .filter(|r| !r.closed)
+        .flat_map(|r| r.ends.iter().map(move |e| (r, e)))
+        .filter_map(|(runway, end)| {
+            let true_heading = end.true_heading.or_else(|| {
+                end.ident_heading()
+                    .map(|h| h + variation.unwrap_or_default())
+            })?;
+            let (headwind, crosswind) = wind_components(wind_dir, wind_speed, true_heading);
+            let (gust_headwind, gust_crosswind) =
+                wind_components(wind_dir, wind_gust, true_heading);
+            Some(RunwayWindComponents {
+                runway: end.ident.clone(),
+                true_heading: normalize_heading(true_heading),
+                magnetic_heading: normalize_heading(true_heading - variation.unwrap_or_default()),
+                length_ft: runway.length_ft,
+                headwind,
+                crosswind,
+                gust_headwind,
+                gust_crosswind,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let favoured_runway = components
+        .iter()
+        .filter(|c| c.headwind.is_some())
+        .max_by(|a, b| {
+            a.headwind
+                .partial_cmp(&b.headwind)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.length_ft.cmp(&b.length_ft))
+        })
+        .map(|c| c.runway.clone());
+
+    RunwayWindsResponse {
+        station: station.to_string(),
+        wind_string: metar.wind_string(),
+        magnetic_variation: variation,
+        runways: components,
+        favoured_runway,
+    }
+}
+
+/// Returns `(headwind, crosswind)` rounded to one decimal place. Calm and variable winds have no
+/// meaningful components.
+fn wind_components(
+    wind_dir: Option<f64>,
+    wind_speed: Option<f64>,
+    runway_heading: f64,
+) -> (Option<f64>, Option<f64>) {
+    match (wind_dir, wind_speed) {
+        (Some(dir), Some(speed)) if speed > 0.0 => {
+            let angle = (dir - runway_heading).to_radians();
+            (
+                Some((speed * angle.cos() * 10.0).round() / 10.0),
+                Some((speed * angle.sin() * 10.0).round() / 10.0),
+            )
+        }
+        _ => (None, None),
+    }
+}
+
+fn normalize_heading(heading: f64) -> f64 {
+    heading.rem_euclid(360.0)
+}
+
+fn normalize_relative(angle: f64) -> f64 {
+    (angle + 180.0).rem_euclid(360.0) - 180.0
+}
+
+#[cfg(test)]
+// Components are rounded to one decimal place, so exact comparisons are meaningful
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+    use crate::providers::metar_dto_from_raw;
+    use chrono::{TimeZone, Utc};
+
+    const RUNWAYS_CSV: &str = "\
+id,airport_ident,length_ft,width_ft,surface,lighted,closed,le_ident,le_heading_degT,he_ident,he_heading_degT
+1,KSFO,11870,200,ASP,1,0,10L,117,28R,297
+2,KSFO,10602,200,ASP,1,0,10R,117,28L,297
+3,KSFO,7650,200,ASP,1,0,01R,27,19L,207
+4,KSFO,0,0,ASP,0,0,H1,,,
+5,ksea,8500,150,CON,1,0,18R,185,36L,5
+6,KSEA,9000,150,CON,1,0,18L,,36R,
+7,KSEA,3000,75,ASP,0,1,13,,31,
+8,EGKK,10364,148,ASP,1,0,08R,78,26L,258
+9,EGKK,8415,148,ASP,1,0,08L,,26R,
+10,EGKK,2000,50,GRS,0,0,02,150,20,330
+";
+
+    fn database() -> RunwayDatabase {
+        RunwayDatabase::from_reader(RUNWAYS_CSV.as_bytes())
+    }
+
+    fn winds(station: &str, raw: &str) -> RunwayWindsResponse {
+        let metar =
+            metar_dto_from_raw(raw, Utc.with_ymd_and_hms(2024, 10, 16, 13, 0, 0).unwrap()).unwrap();
+        runway_winds(station, &metar, database().runways_for(station).unwrap())
+    }
+
+    fn components<'a>(response: &'a RunwayWindsResponse, runway: &str) -> &'a RunwayWindComponents {
+        response
+            .runways
+            .iter()
+            .find(|c| c.runway == runway)
+            .unwrap()
+    }
+
+    #[test]
+    fn reads_runways_by_airport() {
+        let database = database();
+        assert_eq!(database.runways_for("ksfo").unwrap().len(), 4);
+        assert_eq!(database.runways_for("KSEA").unwrap().len(), 3);
+        assert!(database.runways_for("KLAX").is_none());
+
+        let runway = &database.runways_for("KSFO").unwrap()[0];
+        assert_eq!(runway.length_ft, Some(11870));
+        assert!(runway.lighted && !runway.closed);
+        assert_eq!(
+            runway.ends,
+            vec![
+                RunwayEnd {
+                    ident: "10L".to_string(),
+                    true_heading: Some(117.0),
+                },
+                RunwayEnd {
+                    ident: "28R".to_string(),
+                    true_heading: Some(297.0),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn estimates_magnetic_variation_from_runway_numbers() {
+        let database = database();
+        for (station, variation) in [
+            ("KSFO", Some(17.0)),
+            // 005 true for 36L is 5 degrees east of 360, not 355 west
+            ("KSEA", Some(5.0)),
+            // The misnumbered 02/20 is too far off to count
+            ("EGKK", Some(-2.0)),
+        ] {
+            assert_eq!(
+                estimate_magnetic_variation(database.runways_for(station).unwrap()),
+                variation,
+                "{station}"
+            );
+        }
+
+        let no_headings = &database.runways_for("KSEA").unwrap()[1..];
+        assert_eq!(estimate_magnetic_variation(no_headings), None);
+    }
+
+    #[test]
+    fn computes_head_and_crosswind_components() {
+        let response = winds("KSFO", "KSFO 161256Z 29715G25KT 10SM FEW010 16/12 A2992");
+        assert_eq!(response.station, "KSFO");
+        assert_eq!(response.wind_string, "29715G25KT");
+        assert_eq!(response.magnetic_variation, Some(17.0));
+
+        for (runway, magnetic_heading, headwind, crosswind, gust_headwind, gust_crosswind) in [
+            ("28R", 280.0, 15.0, 0.0, 25.0, 0.0),
+            // Tailwind
+            ("10L", 100.0, -15.0, 0.0, -25.0, 0.0),
+            // Wind from the right
+            ("19L", 190.0, 0.0, 15.0, 0.0, 25.0),
+            // Wind from the left
+            ("01R", 10.0, 0.0, -15.0, 0.0, -25.0),
+        ] {
+            let c = components(&response, runway);
+            assert_eq!(c.magnetic_heading, magnetic_heading, "{runway}");
+            assert_eq!(c.headwind, Some(headwind), "{runway}");
+            assert_eq!(c.crosswind, Some(crosswind), "{runway}");
+            assert_eq!(c.gust_headwind, Some(gust_headwind), "{runway}");
+            assert_eq!(c.gust_crosswind, Some(gust_crosswind), "{runway}");
+        }
+
+        let response = winds("KSFO", "KSFO 161256Z 32720KT 10SM FEW010 16/12 A2992");
+        let c = components(&response, "28R");
+        assert_eq!(c.headwind, Some(17.3));
+        assert_eq!(c.crosswind, Some(10.0));
+        assert_eq!(c.gust_headwind, None);
+        assert_eq!(c.gust_crosswind, None);
+    }
+
+    #[test]
+    fn picks_the_runway_with_most_headwind() {
+        // 28L and 28R have the same headwind, the longer one wins
+        let response = winds("KSFO", "KSFO 161256Z 29715KT 10SM FEW010 16/12 A2992");
+        assert_eq!(response.favoured_runway.as_deref(), Some("28R"));
+
+        let response = winds("KSFO", "KSFO 161256Z 11010KT 10SM FEW010 16/12 A2992");
+        assert_eq!(response.favoured_runway.as_deref(), Some("10L"));
+    }
+
+    #[test]
+    fn derives_headings_from_runway_numbers() {
+        let response = winds("KSEA", "KSEA 161253Z 03510KT 10SM FEW010 12/08 A3001");
+        assert_eq!(response.magnetic_variation, Some(5.0));
+        // Closed runways are left out
+        assert_eq!(
+            response
+                .runways
+                .iter()
+                .map(|c| c.runway.as_str())
+                .collect::<Vec<_>>(),
+            ["18R", "36L", "18L", "36R"]
+        );
+
+        let c = components(&response, "36R");
+        assert_eq!(c.true_heading, 5.0);
+        assert_eq!(c.magnetic_heading, 0.0);
+        assert_eq!(c.headwind, Some(8.7));
+        assert_eq!(c.crosswind, Some(5.0));
+        assert_eq!(response.favoured_runway.as_deref(), Some("36R"));
+    }
+
+    #[test]
+    fn has_no_components_for_calm_or_variable_wind() {
+        for raw in [
+            "KSFO 161256Z 00000KT 10SM FEW010 16/12 A2992",
+            "KSFO 161256Z VRB03KT 10SM FEW010 16/12 A2992",
+        ] {
+            let response = winds("KSFO", raw);
+            assert_eq!(response.runways.len(), 6, "{raw}");
+            assert!(
+                response
+                    .runways
+                    .iter()
+                    .all(|c| c.headwind.is_none() && c.crosswind.is_none()),
+                "{raw}"
+            );
+            assert_eq!(response.favoured_runway, None, "{raw}");
+        }
+    }
+}
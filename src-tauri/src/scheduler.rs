@@ -0,0 +1,169 @@
+use crate::alerts::check_altimeter_changes;
+use crate::state::AppState;
+use crate::FetchMetarResponse;
+use log::{debug, error, trace, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+
+pub const METARS_UPDATED_EVENT: &str = "metars-updated";
+const POLL_INTERVAL: Duration = Duration::from_mins(2);
+
+/// Rows subscribe one at a time when a profile is loaded, so wait briefly after a wake-up to
+/// collect them into a single request
+const SUBSCRIBE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetarsUpdatedPayload {
+    pub metars: Vec<FetchMetarResponse>,
+    /// Subscribed stations that were not included in the batch response
+    pub missing: Vec<String>,
+}
+
+/// Tracks the stations the frontend wants METARs for, so they can be fetched together. Several
+/// rows can show the same station, so subscriptions are counted per station.
+pub struct MetarScheduler {
+    stations: Mutex<BTreeMap<String, usize>>,
+    wake: Notify,
+}
+
+impl MetarScheduler {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            stations: Mutex::new(BTreeMap::new()),
+            wake: Notify::const_new(),
+        }
+    }
+
+    /// Adds a subscription and wakes the scheduler, so the new row gets its first METAR from an
+    /// immediate batch poll
+    pub fn subscribe(&self, id: &str) {
+        let mut stations = self.stations.lock().unwrap();
+        let count = stations.entry(id.to_uppercase()).or_default();
+        *count += 1;
+        debug!("Subscribed to METARs for {id} ({count} subscriptions)");
+        drop(stations);
+
+        self.wake.notify_one();
+    }
+
+    /// Removes a subscription, returning true if it was the last one for the station
+    pub fn unsubscribe(&self, id: &str) -> bool {
+        let id = id.to_uppercase();
+        let mut stations = self.stations.lock().unwrap();
+        let Some(count) = stations.get_mut(&id) else {
+            return false;
+        };
+
+        *count -= 1;
+        if *count > 0 {
+            return false;
+        }
+        stations.remove(&id);
+        drop(stations);
+
+        debug!("Unsubscribed from METARs for {id}");
+        true
+    }
+
+    fn stations(&self) -> Vec<String> {
+        self.stations.lock().unwrap().keys().cloned().collect()
+    }
+}
+
+impl Default for MetarScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Polls all subscribed stations in one AWC request every `POLL_INTERVAL`, or shortly after a new
+/// station is subscribed, and emits the results to the frontend
+pub async fn run_metar_scheduler(app: AppHandle) {
+    let Some(state) = app.try_state::<Arc<AppState>>().map(|s| Arc::clone(&s)) else {
+        error!("Could not get app state, METAR scheduler not started");
+        return;
+    };
+
+    loop {
+        tokio::select! {
+            () = tokio::time::sleep(POLL_INTERVAL) => {},
+            () = state.metar_scheduler.wake.notified() => {
+                tokio::time::sleep(SUBSCRIBE_DEBOUNCE).await;
+            },
+        }
+
+        let stations = state.metar_scheduler.stations();
+        if stations.is_empty() {
+            continue;
+        }
+
+        match poll_metars(&app, &state, &stations).await {
+            Ok(payload) => {
+                trace!(
+                    "Emitting {} METARs, missing: {:?}",
+                    payload.metars.len(),
+                    payload.missing
+                );
+                if let Err(e) = app.emit(METARS_UPDATED_EVENT, payload) {
+                    warn!("Could not emit METAR update: {e:?}");
+                }
+            }
+            Err(e) => warn!("Error polling METARs: {e}"),
+        }
+    }
+}
+
+async fn poll_metars(
+    app: &AppHandle,
+    state: &AppState,
+    stations: &[String],
+) -> Result<MetarsUpdatedPayload, anyhow::Error> {
+    let client = state.get_weather_provider().await?;
+
+    debug!("Fetching batched METARs for {} stations", stations.len());
+    let ids = stations.iter().map(String::as_str).collect::<Vec<_>>();
+    let metars = client.fetch_metars(&ids).await?;
+    state.metar_history.record(&metars);
+    check_altimeter_changes(app, state, &metars);
+
+    let missing = stations
+        .iter()
+        .filter(|s| !metars.iter().any(|m| m.icao_id.eq_ignore_ascii_case(s)))
+        .cloned()
+        .collect();
+
+    let thresholds = state.flight_category_thresholds();
+    let metars = metars
+        .into_iter()
+        .map(|m| FetchMetarResponse::new(m, &thresholds))
+        .collect::<Vec<_>>();
+    state.station_data.update_metars(&metars);
+
+    Ok(MetarsUpdatedPayload { metars, missing })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_subscriptions_per_station() {
+        let scheduler = MetarScheduler::new();
+        scheduler.subscribe("KSFO");
+        scheduler.subscribe("ksfo");
+        scheduler.subscribe("KOAK");
+        assert_eq!(scheduler.stations(), ["KOAK", "KSFO"]);
+
+        assert!(!scheduler.unsubscribe("KSFO"));
+        assert_eq!(scheduler.stations(), ["KOAK", "KSFO"]);
+        assert!(scheduler.unsubscribe("KSFO"));
+        assert_eq!(scheduler.stations(), ["KOAK"]);
+        assert!(!scheduler.unsubscribe("KSFO"));
+    }
+}
@@ -1,14 +1,21 @@
+use crate::flight_category::{FlightCategoryRules, FlightCategoryThresholds, IcaoCategoryLevels};
 use crate::profiles::{load_profile_from_path, Profile};
+use crate::providers::WeatherProviderKind;
 use crate::state::AppState;
 use crate::utils;
 use crate::utils::deserialize_from_file;
 use anyhow::anyhow;
-use log::debug;
+use log::{debug, warn};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tauri::{AppHandle, Manager};
 
+const DEFAULT_API_BIND_ADDRESS: &str = "127.0.0.1";
+const DEFAULT_API_PORT: u16 = 47_470;
+const MAX_RECENT_PROFILES: usize = 10;
+
 const fn true_bool() -> bool {
     true
 }
@@ -19,10 +26,31 @@ pub struct Settings {
     #[serde(default = "true_bool")]
     load_most_recent_profile_on_open: bool,
     most_recent_profile: Option<PathBuf>,
+    /// Most recently used profiles, newest first
+    #[serde(default)]
+    recent_profiles: Vec<PathBuf>,
     #[serde(default = "true_bool")]
     always_on_top: bool,
     #[serde(default = "true_bool")]
     auto_resize: bool,
+    #[serde(default)]
+    flight_category_rules: FlightCategoryRules,
+    #[serde(default)]
+    icao_flight_category_levels: IcaoCategoryLevels,
+    #[serde(default)]
+    weather_provider: WeatherProviderKind,
+    weather_base_url: Option<String>,
+    #[serde(default)]
+    notify_altimeter_changes: bool,
+    /// Extra callsign prefixes of controllers shown for a station, keyed by ICAO ID,
+    /// e.g. `KSFO: ["NCT", "OAK"]`
+    #[serde(default)]
+    controller_prefixes: BTreeMap<String, Vec<String>>,
+    /// Serve station data as JSON over HTTP and WebSocket on `api_bind_address:api_port`
+    #[serde(default)]
+    api_enabled: bool,
+    api_bind_address: Option<String>,
+    api_port: Option<u16>,
 }
 
 impl Settings {
@@ -30,14 +58,93 @@ impl Settings {
         Self {
             load_most_recent_profile_on_open: true,
             most_recent_profile: None,
+            recent_profiles: Vec::new(),
             always_on_top: true,
             auto_resize: true,
+            flight_category_rules: FlightCategoryRules::Faa,
+            icao_flight_category_levels: IcaoCategoryLevels::DEFAULT,
+            weather_provider: WeatherProviderKind::Awc,
+            weather_base_url: None,
+            notify_altimeter_changes: false,
+            controller_prefixes: BTreeMap::new(),
+            api_enabled: false,
+            api_bind_address: None,
+            api_port: None,
         }
     }
 
     pub const fn always_on_top(&self) -> bool {
         self.always_on_top
     }
+
+    pub const fn flight_category_thresholds(&self) -> FlightCategoryThresholds {
+        self.flight_category_rules
+            .thresholds(&self.icao_flight_category_levels)
+    }
+
+    pub const fn weather_provider(&self) -> WeatherProviderKind {
+        self.weather_provider
+    }
+
+    pub fn most_recent_profile(&self) -> Option<&Path> {
+        self.most_recent_profile.as_deref()
+    }
+
+    pub fn recent_profiles(&self) -> &[PathBuf] {
+        &self.recent_profiles
+    }
+
+    fn record_recent_profile(&mut self, path: &Path) {
+        self.recent_profiles.retain(|p| p != path);
+        self.recent_profiles.insert(0, path.to_path_buf());
+        self.recent_profiles.truncate(MAX_RECENT_PROFILES);
+        self.most_recent_profile = Some(path.to_path_buf());
+    }
+
+    /// Points references to a renamed profile at its new path, or drops them if it was deleted
+    fn replace_recent_profile(&mut self, from: &Path, to: Option<&Path>) {
+        if self.most_recent_profile.as_deref() == Some(from) {
+            self.most_recent_profile = to.map(Path::to_path_buf);
+        }
+        self.recent_profiles = self
+            .recent_profiles
+            .iter()
+            .filter_map(|p| {
+                if p == from {
+                    to.map(Path::to_path_buf)
+                } else {
+                    Some(p.clone())
+                }
+            })
+            .collect();
+    }
+
+    pub fn weather_base_url(&self) -> Option<&str> {
+        self.weather_base_url.as_deref().filter(|u| !u.is_empty())
+    }
+
+    pub const fn notify_altimeter_changes(&self) -> bool {
+        self.notify_altimeter_changes
+    }
+
+    pub fn controller_prefixes(&self, icao_id: &str) -> &[String] {
+        self.controller_prefixes
+            .get(&icao_id.to_uppercase())
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Host and port for the local API, `None` if it is disabled
+    pub fn api_address(&self) -> Option<(String, u16)> {
+        self.api_enabled.then(|| {
+            (
+                self.api_bind_address
+                    .clone()
+                    .filter(|a| !a.is_empty())
+                    .unwrap_or_else(|| DEFAULT_API_BIND_ADDRESS.to_string()),
+                self.api_port.unwrap_or(DEFAULT_API_PORT),
+            )
+        })
+    }
 }
 
 impl Default for Settings {
@@ -101,18 +208,26 @@ pub fn get_appstate_settings(app: &AppHandle) -> Option<Settings> {
 
 pub fn set_latest_profile_path(app: &AppHandle, path: &PathBuf) {
     if let Some(state) = app.try_state::<Arc<AppState>>() {
-        let mut settings = state.settings.lock().unwrap();
-        *settings = (*settings).as_ref().map_or_else(
-            || Some(read_settings_or_default()),
-            |s| {
-                Some(Settings {
-                    most_recent_profile: Some(path.clone()),
-                    ..s.clone()
-                })
-            },
-        );
-        drop(settings);
+        state
+            .settings
+            .lock()
+            .unwrap()
+            .get_or_insert_with(read_settings_or_default)
+            .record_recent_profile(path);
         debug!("Set in-memory latest profile path: {path:?}");
+
+        if let Err(e) = state.profile_watcher.watch(app, path) {
+            warn!("Could not watch profile {path:?} for changes: {e:?}");
+        }
+    }
+}
+
+pub fn replace_recent_profile_path(app: &AppHandle, from: &Path, to: Option<&Path>) {
+    if let Some(state) = app.try_state::<Arc<AppState>>() {
+        if let Some(settings) = state.settings.lock().unwrap().as_mut() {
+            settings.replace_recent_profile(from, to);
+        }
+        debug!("Replaced in-memory profile path {from:?} with {to:?}");
     }
 }
 
@@ -171,6 +286,7 @@ pub fn save_settings(app: AppHandle, settings: Option<Settings>) -> Result<(), S
     let appstate_settings = get_appstate_settings(&app).unwrap_or_else(read_settings_or_default);
     let write_settings = settings.map_or(appstate_settings.clone(), |s| Settings {
         most_recent_profile: appstate_settings.most_recent_profile,
+        recent_profiles: appstate_settings.recent_profiles,
         ..s
     });
 
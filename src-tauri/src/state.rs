@@ -1,56 +1,328 @@
+use crate::alerts::{AltimeterTracker, AtisTracker};
+use crate::api::StationData;
 use crate::awc::AviationWeatherCenterApi;
+use crate::client_holder::{ClientHolder, ClientStatus};
+use crate::datafeed::{Prefile, VatsimDatafeed, VatsimDatafeedClient};
+use crate::flight_category::FlightCategoryThresholds;
+use crate::history::MetarHistory;
+use crate::networks::{AtisNetwork, AtisNetworkKind, IvaoWhazzup, VatsimNetwork};
+use crate::profile_watcher::ProfileWatcher;
+use crate::providers::{WeatherProvider, WeatherProviderKind};
+use crate::runways::RunwayDatabase;
+use crate::scheduler::MetarScheduler;
 use crate::settings::Settings;
-use std::sync::Mutex;
-use std::time::Instant;
-use tokio::sync::OnceCell;
-use vatsim_utils::errors::VatsimUtilError;
-use vatsim_utils::live_api::Vatsim;
+use chrono::{DateTime, Utc};
+use log::{debug, error, trace, warn};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
 use vatsim_utils::models::V3ResponseData;
 
+/// Cached copies older than this are refetched on demand, in case the background refresh stalls
+const VATSIM_DATA_MAX_AGE: Duration = Duration::from_secs(30);
+/// The datafeed is regenerated every 15 seconds, and published shortly after its timestamp
+const VATSIM_DATAFEED_INTERVAL: Duration = Duration::from_secs(15);
+const VATSIM_DATAFEED_PUBLISH_DELAY: Duration = Duration::from_secs(2);
+const VATSIM_REFRESH_MIN_DELAY: Duration = Duration::from_secs(5);
+const VATSIM_REFRESH_MAX_DELAY: Duration = Duration::from_mins(1);
+/// The background refresh pauses when no VATSIM data has been requested for this long, e.g.
+/// when the loaded profile reads ATIS from another network
+const VATSIM_REFRESH_IDLE_AFTER: Duration = Duration::from_mins(2);
+
 pub struct VatsimDataFetch {
     pub fetched_time: Instant,
+    /// `general.update_timestamp` of the datafeed
+    pub update_time: Option<DateTime<Utc>>,
     pub data: Result<V3ResponseData, anyhow::Error>,
+    pub prefiles: Vec<Prefile>,
+    /// Latest download attempt, later than `fetched_time` if downloads have failed since
+    attempted_time: Instant,
+    failed_attempts: u32,
 }
 
 impl VatsimDataFetch {
     #[must_use]
-    pub fn new(data: Result<V3ResponseData, anyhow::Error>) -> Self {
+    pub fn new(datafeed: Result<VatsimDatafeed, anyhow::Error>) -> Self {
+        let (data, prefiles) = match datafeed {
+            Ok(datafeed) => (Ok(datafeed.data), datafeed.prefiles),
+            Err(e) => (Err(e), vec![]),
+        };
         Self {
             fetched_time: Instant::now(),
+            update_time: data.as_ref().ok().and_then(|d| {
+                DateTime::parse_from_rfc3339(&d.general.update_timestamp)
+                    .map(|t| t.with_timezone(&Utc))
+                    .ok()
+            }),
+            failed_attempts: u32::from(data.is_err()),
             data,
+            prefiles,
+            attempted_time: Instant::now(),
         }
     }
+
+    /// Delay before downloading again after failed attempts, doubling with each failure
+    fn retry_delay(&self) -> Duration {
+        match self.failed_attempts {
+            0 => Duration::ZERO,
+            n => VATSIM_REFRESH_MIN_DELAY
+                .saturating_mul(2u32.saturating_pow(n - 1))
+                .min(VATSIM_REFRESH_MAX_DELAY),
+        }
+    }
+
+    fn is_stale(&self) -> bool {
+        (self.data.is_err() || self.fetched_time.elapsed() > VATSIM_DATA_MAX_AGE)
+            && self.attempted_time.elapsed() >= self.retry_delay()
+    }
 }
 
+type CachedWeatherProvider = (
+    WeatherProviderKind,
+    Option<String>,
+    Arc<dyn WeatherProvider>,
+);
+
 pub struct AppState {
-    awc_client: OnceCell<Result<AviationWeatherCenterApi, anyhow::Error>>,
-    vatsim_client: OnceCell<Result<Vatsim, VatsimUtilError>>,
+    awc_client: ClientHolder<AviationWeatherCenterApi>,
+    vatsim_client: ClientHolder<VatsimDatafeedClient>,
+    ivao_client: ClientHolder<IvaoWhazzup>,
+    runway_database: ClientHolder<RunwayDatabase>,
+    weather_provider: Mutex<Option<CachedWeatherProvider>>,
     pub latest_vatsim_data: Mutex<Option<VatsimDataFetch>>,
+    /// Held while downloading the datafeed so concurrent requests share a single fetch
+    vatsim_fetch_lock: tokio::sync::Mutex<()>,
+    vatsim_data_requested: Mutex<Option<Instant>>,
     pub settings: Mutex<Option<Settings>>,
+    pub metar_scheduler: MetarScheduler,
+    pub metar_history: MetarHistory,
+    pub altimeter_tracker: AltimeterTracker,
+    pub atis_tracker: AtisTracker,
+    pub station_data: StationData,
+    pub profile_watcher: ProfileWatcher,
 }
 
 impl AppState {
     #[must_use]
     pub const fn new() -> Self {
         Self {
-            awc_client: OnceCell::const_new(),
-            vatsim_client: OnceCell::const_new(),
+            awc_client: ClientHolder::new("AWC Api Client"),
+            vatsim_client: ClientHolder::new("VATSIM API client"),
+            ivao_client: ClientHolder::new("IVAO whazzup client"),
+            runway_database: ClientHolder::new("Runway database"),
+            weather_provider: Mutex::new(None),
             latest_vatsim_data: Mutex::new(None),
+            vatsim_fetch_lock: tokio::sync::Mutex::const_new(()),
+            vatsim_data_requested: Mutex::new(None),
             settings: Mutex::new(None),
+            metar_scheduler: MetarScheduler::new(),
+            metar_history: MetarHistory::new(),
+            altimeter_tracker: AltimeterTracker::new(),
+            atis_tracker: AtisTracker::new(),
+            station_data: StationData::new(),
+            profile_watcher: ProfileWatcher::new(),
         }
     }
 
-    pub async fn get_awc_client(&self) -> &Result<AviationWeatherCenterApi, anyhow::Error> {
+    /// The AWC client is always used for station lookups and TAFs. The configured base URL only
+    /// applies to it when AWC is also the selected METAR provider, and is read once at startup.
+    pub async fn get_awc_client(&self) -> Result<Arc<AviationWeatherCenterApi>, anyhow::Error> {
+        let (kind, base_url) = self.weather_provider_settings();
+        let base_url = base_url.filter(|_| kind == WeatherProviderKind::Awc);
         self.awc_client
-            .get_or_init(|| async { AviationWeatherCenterApi::try_new().await })
+            .get_or_try_init(|| AviationWeatherCenterApi::try_new(base_url.as_deref()))
             .await
     }
 
-    pub async fn get_vatsim_client(&self) -> &Result<Vatsim, VatsimUtilError> {
+    /// Returns the METAR provider selected in settings, creating a new one if the selection or
+    /// base URL has changed since the last call
+    pub async fn get_weather_provider(&self) -> Result<Arc<dyn WeatherProvider>, anyhow::Error> {
+        let (kind, base_url) = self.weather_provider_settings();
+        if kind == WeatherProviderKind::Awc {
+            return Ok(self.get_awc_client().await?);
+        }
+
+        let mut cached = self.weather_provider.lock().unwrap();
+        if let Some((cached_kind, cached_url, provider)) = cached.as_ref() {
+            if *cached_kind == kind && *cached_url == base_url {
+                return Ok(Arc::clone(provider));
+            }
+        }
+
+        let provider: Arc<dyn WeatherProvider> = Arc::new(
+            kind.plain_text_provider(base_url.as_deref())?
+                .ok_or_else(|| anyhow::anyhow!("No plain text provider for {kind:?}"))?,
+        );
+        debug!("Using {} METAR provider", provider.name());
+        *cached = Some((kind, base_url, Arc::clone(&provider)));
+        drop(cached);
+        Ok(provider)
+    }
+
+    fn weather_provider_settings(&self) -> (WeatherProviderKind, Option<String>) {
+        self.settings
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map_or((WeatherProviderKind::Awc, None), |s| {
+                (
+                    s.weather_provider(),
+                    s.weather_base_url().map(ToString::to_string),
+                )
+            })
+    }
+
+    pub async fn get_vatsim_client(&self) -> Result<Arc<VatsimDatafeedClient>, anyhow::Error> {
         self.vatsim_client
-            .get_or_init(|| async { Vatsim::new().await })
+            .get_or_try_init(VatsimDatafeedClient::new)
+            .await
+    }
+
+    /// Re-downloads the VATSIM datafeed if there is no cached copy or it is older than
+    /// `VATSIM_DATA_MAX_AGE`. Normally the background refresh keeps it current.
+    pub async fn refresh_vatsim_data_if_stale(&self) {
+        *self.vatsim_data_requested.lock().unwrap() = Some(Instant::now());
+        if !self.vatsim_data_is_stale() {
+            return;
+        }
+
+        let _fetching = self.vatsim_fetch_lock.lock().await;
+        // Another caller may have refreshed it while this one was waiting
+        if self.vatsim_data_is_stale() {
+            debug!("Datafeed is stale, fetching new data");
+            self.store_vatsim_data(self.fetch_vatsim_data().await);
+        }
+    }
+
+    /// Downloads the VATSIM datafeed, or waits for a download already in progress
+    pub async fn refresh_vatsim_data(&self) {
+        let started = Instant::now();
+        let _fetching = self.vatsim_fetch_lock.lock().await;
+        let refreshed_while_waiting = self
+            .latest_vatsim_data
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|fetch| fetch.attempted_time > started);
+        if !refreshed_while_waiting {
+            self.store_vatsim_data(self.fetch_vatsim_data().await);
+        }
+    }
+
+    fn vatsim_data_is_stale(&self) -> bool {
+        self.latest_vatsim_data
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_none_or(VatsimDataFetch::is_stale)
+    }
+
+    /// Keeps the previous datafeed if the new fetch failed, so a single failed request does not
+    /// blank every ATIS letter. Failed attempts are counted so retries back off during an outage.
+    fn store_vatsim_data(&self, data: Result<VatsimDatafeed, anyhow::Error>) {
+        let mut latest = self.latest_vatsim_data.lock().unwrap();
+        let Err(e) = data else {
+            *latest = Some(VatsimDataFetch::new(data));
+            return;
+        };
+
+        let failed_attempts = latest.as_ref().map_or(0, |fetch| fetch.failed_attempts) + 1;
+        match latest.as_mut() {
+            Some(fetch) if fetch.data.is_ok() => {
+                warn!("Could not refresh VATSIM datafeed, keeping previous data: {e:?}");
+                fetch.attempted_time = Instant::now();
+                fetch.failed_attempts = failed_attempts;
+            }
+            _ => {
+                *latest = Some(VatsimDataFetch {
+                    failed_attempts,
+                    ..VatsimDataFetch::new(Err(e))
+                });
+            }
+        }
+        if let Some(fetch) = latest.as_ref() {
+            debug!(
+                "VATSIM datafeed failed {failed_attempts} times, retrying in {}s",
+                fetch.retry_delay().as_secs()
+            );
+        }
+    }
+
+    fn vatsim_data_recently_requested(&self) -> bool {
+        self.vatsim_data_requested
+            .lock()
+            .unwrap()
+            .is_some_and(|t| t.elapsed() < VATSIM_REFRESH_IDLE_AFTER)
+    }
+
+    fn next_vatsim_refresh_delay(&self) -> Duration {
+        self.latest_vatsim_data
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map_or(VATSIM_DATAFEED_INTERVAL, |fetch| {
+                if fetch.failed_attempts > 0 {
+                    return fetch.retry_delay();
+                }
+                fetch
+                    .update_time
+                    .and_then(|t| {
+                        (t + VATSIM_DATAFEED_INTERVAL + VATSIM_DATAFEED_PUBLISH_DELAY - Utc::now())
+                            .to_std()
+                            .ok()
+                    })
+                    .unwrap_or(VATSIM_DATAFEED_INTERVAL)
+                    .clamp(VATSIM_REFRESH_MIN_DELAY, VATSIM_REFRESH_MAX_DELAY)
+            })
+    }
+
+    async fn fetch_vatsim_data(&self) -> Result<VatsimDatafeed, anyhow::Error> {
+        match self.get_vatsim_client().await {
+            Ok(client) => client.fetch().await,
+            Err(e) => {
+                error!("Error fetching VATSIM data: {e}");
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn get_atis_network(
+        &self,
+        kind: AtisNetworkKind,
+    ) -> Result<Arc<dyn AtisNetwork>, anyhow::Error> {
+        let network: Arc<dyn AtisNetwork> = match kind {
+            AtisNetworkKind::Vatsim => Arc::new(VatsimNetwork),
+            AtisNetworkKind::Ivao => {
+                self.ivao_client
+                    .get_or_try_init(|| async { IvaoWhazzup::try_new() })
+                    .await?
+            }
+        };
+        Ok(network)
+    }
+
+    pub async fn get_runway_database(&self) -> Result<Arc<RunwayDatabase>, anyhow::Error> {
+        self.runway_database
+            .get_or_try_init(RunwayDatabase::load_or_download)
             .await
     }
+
+    pub fn client_status(&self) -> ClientStatusResponse {
+        ClientStatusResponse {
+            awc: self.awc_client.status(),
+            vatsim: self.vatsim_client.status(),
+        }
+    }
+
+    pub fn flight_category_thresholds(&self) -> FlightCategoryThresholds {
+        self.settings
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(Settings::flight_category_thresholds)
+            .unwrap_or_default()
+    }
 }
 
 impl Default for AppState {
@@ -58,3 +330,80 @@ impl Default for AppState {
         Self::new()
     }
 }
+
+pub const CLIENT_STATUS_EVENT: &str = "client-status-changed";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientStatusResponse {
+    pub awc: ClientStatus,
+    pub vatsim: ClientStatus,
+}
+
+/// Keeps retrying the AWC and VATSIM clients in the background until both are initialized,
+/// emitting their status after every attempt so the UI can show that it is reconnecting
+pub async fn run_client_initialization(app: AppHandle) {
+    let Some(state) = app.try_state::<Arc<AppState>>().map(|s| Arc::clone(&s)) else {
+        error!("Could not get app state, client initialization not started");
+        return;
+    };
+
+    let mut station_refresh_started = false;
+    loop {
+        // Failures are logged and backed off inside the holders
+        if let Ok(awc) = state.get_awc_client().await {
+            if !station_refresh_started && awc.stations_from_cache() {
+                station_refresh_started = true;
+                tauri::async_runtime::spawn(refresh_cached_stations(awc));
+            }
+        }
+        let _ = state.get_vatsim_client().await;
+
+        let status = state.client_status();
+        if let Err(e) = app.emit(CLIENT_STATUS_EVENT, &status) {
+            warn!("Could not emit client status: {e:?}");
+        }
+
+        let delay = [
+            state.awc_client.retry_delay(),
+            state.vatsim_client.retry_delay(),
+        ]
+        .into_iter()
+        .flatten()
+        .min();
+
+        let Some(delay) = delay else {
+            debug!("All clients initialized");
+            break;
+        };
+        tokio::time::sleep(delay.max(Duration::from_secs(1))).await;
+    }
+}
+
+/// Refreshes the VATSIM datafeed shortly after each new version is published, based on its
+/// `general.update_timestamp`, for as long as the frontend keeps requesting VATSIM data
+pub async fn run_vatsim_datafeed_refresh(app: AppHandle) {
+    let Some(state) = app.try_state::<Arc<AppState>>().map(|s| Arc::clone(&s)) else {
+        error!("Could not get app state, VATSIM datafeed refresh not started");
+        return;
+    };
+
+    loop {
+        let delay = state.next_vatsim_refresh_delay();
+        trace!("Next VATSIM datafeed refresh in {}s", delay.as_secs());
+        tokio::time::sleep(delay).await;
+
+        if state.vatsim_data_recently_requested() {
+            state.refresh_vatsim_data().await;
+        }
+    }
+}
+
+/// Checks for a newer station table after starting up from the on-disk copy, keeping the cached
+/// table if the download fails
+async fn refresh_cached_stations(awc: Arc<AviationWeatherCenterApi>) {
+    match awc.refresh_stations().await {
+        Ok(true) => debug!("Station cache refreshed"),
+        Ok(false) => debug!("Station cache up to date"),
+        Err(e) => warn!("Could not refresh stations, using cached copy: {e:?}"),
+    }
+}
@@ -0,0 +1,657 @@
+use crate::metar;
+use crate::metar::{parse_conditions_group, WeatherConditions};
+use anyhow::bail;
+use chrono::{DateTime, Months, Timelike, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+static ISSUE_TIME_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d{2})(\d{2})(\d{2})Z$").unwrap());
+static VALID_PERIOD_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d{2})(\d{2})/(\d{2})(\d{2})$").unwrap());
+static FROM_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^FM(\d{2})(\d{2})(\d{2})$").unwrap());
+static PROB_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^PROB(\d{2})$").unwrap());
+
+/// A TAF decoded from its raw text form, with all times resolved relative to a reference time
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TafReport {
+    pub station: String,
+    pub amended: bool,
+    pub corrected: bool,
+    pub issued: Option<DateTime<Utc>>,
+    pub valid_from: Option<DateTime<Utc>>,
+    pub valid_to: Option<DateTime<Utc>>,
+    pub periods: Vec<TafPeriod>,
+    pub remarks: Option<String>,
+    pub unparsed: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TafPeriod {
+    pub kind: TafPeriodKind,
+    pub probability: Option<u32>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub conditions: WeatherConditions,
+}
+
+impl TafPeriod {
+    fn covers(&self, time: DateTime<Utc>) -> bool {
+        self.from.is_none_or(|f| f <= time) && self.to.is_none_or(|t| time < t)
+    }
+
+    /// The period re-encoded in TAF form, e.g. `TEMPO 1612/1618 24020G32KT 6000 SHRA BKN014`
+    pub fn code(&self) -> String {
+        let header = period_header(self.kind, self.probability, self.from, self.to);
+        [header, self.conditions.code()]
+            .into_iter()
+            .filter(|p| !p.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TafPeriodKind {
+    #[serde(rename = "BASE")]
+    Base,
+    #[serde(rename = "FM")]
+    From,
+    #[serde(rename = "BECMG")]
+    Becoming,
+    #[serde(rename = "TEMPO")]
+    Temporary,
+    #[serde(rename = "PROB")]
+    Probability,
+}
+
+/// The forecast in effect at a point in time: the prevailing conditions after applying any
+/// `FM` and `BECMG` changes, plus any `TEMPO`/`PROB` periods that overlap that time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TafForecast {
+    pub prevailing: WeatherConditions,
+    /// Kind and period of the last change to the prevailing conditions, `Base` if none
+    pub kind: TafPeriodKind,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// When the prevailing conditions are next forecast to change, or the TAF ends
+    pub until: Option<DateTime<Utc>>,
+    pub temporary: Vec<TafPeriod>,
+}
+
+impl TafForecast {
+    /// The forecast in TAF form, e.g. `FM161800 29015G25KT P6SM SCT025 TL170200`, followed by
+    /// the temporary periods in effect
+    const fn set_change(&mut self, period: &TafPeriod) {
+        self.kind = period.kind;
+        self.from = period.from;
+        self.to = period.to;
+    }
+
+    pub fn summary(&self) -> String {
+        let prevailing = TafPeriod {
+            kind: self.kind,
+            probability: None,
+            from: self.from,
+            to: self.to,
+            conditions: self.prevailing.clone(),
+        };
+        std::iter::once(prevailing.code())
+            .chain(self.until.map(|u| u.format("TL%d%H%M").to_string()))
+            .chain(self.temporary.iter().map(TafPeriod::code))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl TafReport {
+    pub fn forecast_at(&self, time: DateTime<Utc>) -> Option<TafForecast> {
+        if self.valid_from.is_some_and(|f| time < f) || self.valid_to.is_some_and(|t| time >= t) {
+            return None;
+        }
+
+        let mut forecast = TafForecast {
+            prevailing: WeatherConditions::default(),
+            kind: TafPeriodKind::Base,
+            from: self.valid_from,
+            to: self.valid_to,
+            until: self.valid_to,
+            temporary: vec![],
+        };
+        for period in &self.periods {
+            let started = period.from.is_none_or(|f| f <= time);
+            match period.kind {
+                TafPeriodKind::Base | TafPeriodKind::From | TafPeriodKind::Becoming if !started => {
+                    forecast.until = forecast.until.into_iter().chain(period.from).min();
+                }
+                TafPeriodKind::Base | TafPeriodKind::From => {
+                    forecast.prevailing = period.conditions.clone();
+                    forecast.set_change(period);
+                }
+                TafPeriodKind::Becoming => {
+                    // Changes are complete by the end of the period, treat them as in effect
+                    // from its start so the row shows the change as soon as it begins
+                    apply_changes(&mut forecast.prevailing, &period.conditions);
+                    forecast.set_change(period);
+                }
+                TafPeriodKind::Temporary | TafPeriodKind::Probability => {
+                    if period.covers(time) {
+                        forecast.temporary.push(period.clone());
+                    }
+                }
+            }
+        }
+
+        Some(forecast)
+    }
+}
+
+fn apply_changes(prevailing: &mut WeatherConditions, changes: &WeatherConditions) {
+    if changes.wind.is_some() {
+        prevailing.wind = changes.wind;
+    }
+    if changes.cavok {
+        prevailing.cavok = true;
+        prevailing.visibility = None;
+        prevailing.weather.clear();
+        prevailing.clouds.clear();
+        prevailing.vertical_visibility = None;
+    }
+    if changes.visibility.is_some() {
+        prevailing.cavok = false;
+        prevailing.visibility = changes.visibility;
+    }
+    if changes.no_significant_weather {
+        prevailing.weather.clear();
+    }
+    if !changes.weather.is_empty() {
+        prevailing.weather.clone_from(&changes.weather);
+    }
+    if !changes.clouds.is_empty() || changes.vertical_visibility.is_some() {
+        prevailing.cavok = false;
+        prevailing.clouds.clone_from(&changes.clouds);
+        prevailing.vertical_visibility = changes.vertical_visibility;
+    }
+}
+
+/// Decodes a raw TAF, e.g. `TafDto::raw_taf`. Day-of-month times in the TAF are resolved to
+/// the month closest to `reference`, which should be near the issue time.
+pub fn parse_taf(raw: &str, reference: DateTime<Utc>) -> Result<TafReport, anyhow::Error> {
+    let raw = raw.trim().trim_end_matches('=');
+    let (body, remarks) = raw.split_once(" RMK").map_or((raw, None), |(b, r)| {
+        (b, Some(r.trim().to_string()).filter(|r| !r.is_empty()))
+    });
+    let tokens = body.split_whitespace().collect::<Vec<_>>();
+
+    let mut report = TafReport {
+        remarks,
+        ..TafReport::default()
+    };
+    let mut i = 0;
+
+    while let Some(&token) = tokens.get(i) {
+        match token {
+            "TAF" => {}
+            "AMD" => report.amended = true,
+            "COR" => report.corrected = true,
+            _ => break,
+        }
+        i += 1;
+    }
+
+    match tokens.get(i) {
+        Some(station) if station.len() == 4 && station.chars().all(char::is_alphanumeric) => {
+            report.station = (*station).to_string();
+            i += 1;
+        }
+        _ => bail!("Could not find station identifier in TAF: {raw}"),
+    }
+
+    if let Some(c) = tokens.get(i).and_then(|t| ISSUE_TIME_REGEX.captures(t)) {
+        report.issued = resolve_day_time(reference, &c[1], &c[2], &c[3]);
+        i += 1;
+    }
+
+    let period_reference = report.issued.unwrap_or(reference);
+    if let Some((from, to)) = tokens
+        .get(i)
+        .and_then(|t| parse_valid_period(t, period_reference))
+    {
+        report.valid_from = Some(from);
+        report.valid_to = Some(to);
+        i += 1;
+    }
+
+    let mut current = TafPeriod {
+        kind: TafPeriodKind::Base,
+        probability: None,
+        from: report.valid_from,
+        to: report.valid_to,
+        conditions: WeatherConditions::default(),
+    };
+
+    while i < tokens.len() {
+        let token = tokens[i];
+
+        if let Some(c) = FROM_REGEX.captures(token) {
+            report.periods.push(current);
+            current = TafPeriod {
+                kind: TafPeriodKind::From,
+                probability: None,
+                from: resolve_day_time(period_reference, &c[1], &c[2], &c[3]),
+                to: report.valid_to,
+                conditions: WeatherConditions::default(),
+            };
+            i += 1;
+            continue;
+        }
+
+        if let Some((period, consumed)) = parse_change_header(&tokens[i..], period_reference) {
+            report.periods.push(std::mem::replace(&mut current, period));
+            i += consumed;
+            continue;
+        }
+
+        let consumed = parse_conditions_group(&mut current.conditions, &tokens, i);
+        if consumed > 0 {
+            i += consumed;
+        } else {
+            report.unparsed.push(token.to_string());
+            i += 1;
+        }
+    }
+    report.periods.push(current);
+
+    close_from_periods(&mut report.periods);
+
+    Ok(report)
+}
+
+/// Parses the start of a `BECMG`, `TEMPO`, `PROB30` or `PROB40 TEMPO` change group, returning
+/// the new empty period and the number of tokens consumed
+fn parse_change_header(tokens: &[&str], reference: DateTime<Utc>) -> Option<(TafPeriod, usize)> {
+    let (mut kind, probability) = match tokens.first()? {
+        &"BECMG" => (TafPeriodKind::Becoming, None),
+        &"TEMPO" => (TafPeriodKind::Temporary, None),
+        t => PROB_REGEX
+            .captures(t)
+            .map(|c| (TafPeriodKind::Probability, c[1].parse().ok()))?,
+    };
+    let mut consumed = 1;
+
+    // `PROB30 TEMPO` is a probabilistic temporary change
+    if kind == TafPeriodKind::Probability && tokens.get(consumed) == Some(&"TEMPO") {
+        kind = TafPeriodKind::Temporary;
+        consumed += 1;
+    }
+
+    let (from, to) = tokens
+        .get(consumed)
+        .and_then(|t| parse_valid_period(t, reference))
+        .map_or((None, None), |(f, t)| (Some(f), Some(t)));
+    if from.is_some() {
+        consumed += 1;
+    }
+
+    Some((
+        TafPeriod {
+            kind,
+            probability,
+            from,
+            to,
+            conditions: WeatherConditions::default(),
+        },
+        consumed,
+    ))
+}
+
+fn period_header(
+    kind: TafPeriodKind,
+    probability: Option<u32>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+) -> String {
+    let period = from
+        .zip(to)
+        .map(|(f, t)| format!("{}/{}", day_hour(f, false), day_hour(t, true)));
+    let probability = probability.map(|p| format!("PROB{p}"));
+    let parts = match kind {
+        TafPeriodKind::Base => vec![period],
+        TafPeriodKind::From => vec![from.map(|f| f.format("FM%d%H%M").to_string())],
+        TafPeriodKind::Becoming => vec![Some("BECMG".to_string()), period],
+        TafPeriodKind::Temporary => vec![probability, Some("TEMPO".to_string()), period],
+        TafPeriodKind::Probability => vec![probability, period],
+    };
+    parts.into_iter().flatten().collect::<Vec<_>>().join(" ")
+}
+
+/// Day and hour in TAF form, with midnight at the end of a period as hour 24 of the day before
+fn day_hour(time: DateTime<Utc>, end: bool) -> String {
+    if end && time.hour() == 0 && time.minute() == 0 {
+        let previous = time - chrono::Duration::days(1);
+        format!("{}24", previous.format("%d"))
+    } else {
+        time.format("%d%H").to_string()
+    }
+}
+
+/// `FM` periods run until the start of the next `FM` period
+fn close_from_periods(periods: &mut [TafPeriod]) {
+    let mut next_starts = periods
+        .iter()
+        .filter(|p| matches!(p.kind, TafPeriodKind::Base | TafPeriodKind::From))
+        .map(|p| p.from)
+        .skip(1)
+        .collect::<Vec<_>>()
+        .into_iter();
+    for period in periods
+        .iter_mut()
+        .filter(|p| matches!(p.kind, TafPeriodKind::Base | TafPeriodKind::From))
+    {
+        if let Some(next) = next_starts.next().flatten() {
+            period.to = Some(next);
+        }
+    }
+}
+
+fn parse_valid_period(
+    token: &str,
+    reference: DateTime<Utc>,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let c = VALID_PERIOD_REGEX.captures(token)?;
+    let from = resolve_day_time(reference, &c[1], &c[2], "00")?;
+    let mut to = resolve_day_time(reference, &c[3], &c[4], "00")?;
+    // Periods that cross a month boundary resolve the end day into the wrong month
+    if to < from {
+        to = to.checked_add_months(Months::new(1))?;
+    }
+    Some((from, to))
+}
+
+fn resolve_day_time(
+    reference: DateTime<Utc>,
+    day: &str,
+    hour: &str,
+    minute: &str,
+) -> Option<DateTime<Utc>> {
+    metar::resolve_day_time(
+        reference,
+        day.parse().ok()?,
+        hour.parse().ok()?,
+        minute.parse().ok()?,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const EGLL: &str = "TAF EGLL 161058Z 1612/1718 24012KT 9999 SCT030 \
+        TEMPO 1612/1618 24020G32KT 6000 SHRA BKN014 BECMG 1620/1623 30008KT \
+        PROB30 1703/1708 3000 BR BKN005 BECMG 1710/1713 CAVOK";
+    const KSFO: &str = "TAF KSFO 161120Z 1612/1718 28010KT P6SM FEW015 \
+        FM161800 29015G25KT P6SM SCT025 FM170200 VRB04KT 5SM BR OVC008 \
+        TEMPO 1704/1708 2SM BR OVC004";
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 10, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    fn parse(raw: &str) -> TafReport {
+        parse_taf(raw, at(16, 11, 30)).unwrap()
+    }
+
+    fn forecast(raw: &str, time: DateTime<Utc>) -> TafForecast {
+        parse(raw).forecast_at(time).unwrap()
+    }
+
+    fn wind(conditions: &WeatherConditions) -> Option<(Option<i32>, i32, Option<i32>)> {
+        conditions.wind.map(|w| (w.direction, w.speed, w.gust))
+    }
+
+    fn cloud_covers(conditions: &WeatherConditions) -> Vec<String> {
+        conditions
+            .clouds
+            .iter()
+            .map(|c| format!("{}{:03}", c.cover, c.base.unwrap_or_default() / 100))
+            .collect()
+    }
+
+    #[test]
+    fn decodes_header_and_periods() {
+        let report = parse(EGLL);
+        assert_eq!(report.station, "EGLL");
+        assert_eq!(report.issued, Some(at(16, 10, 58)));
+        assert_eq!(report.valid_from, Some(at(16, 12, 0)));
+        assert_eq!(report.valid_to, Some(at(17, 18, 0)));
+        assert!(report.unparsed.is_empty(), "{:?}", report.unparsed);
+        assert_eq!(
+            report
+                .periods
+                .iter()
+                .map(|p| (p.kind, p.probability, p.from, p.to))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    TafPeriodKind::Base,
+                    None,
+                    Some(at(16, 12, 0)),
+                    Some(at(17, 18, 0))
+                ),
+                (
+                    TafPeriodKind::Temporary,
+                    None,
+                    Some(at(16, 12, 0)),
+                    Some(at(16, 18, 0))
+                ),
+                (
+                    TafPeriodKind::Becoming,
+                    None,
+                    Some(at(16, 20, 0)),
+                    Some(at(16, 23, 0))
+                ),
+                (
+                    TafPeriodKind::Probability,
+                    Some(30),
+                    Some(at(17, 3, 0)),
+                    Some(at(17, 8, 0))
+                ),
+                (
+                    TafPeriodKind::Becoming,
+                    None,
+                    Some(at(17, 10, 0)),
+                    Some(at(17, 13, 0))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn runs_from_periods_until_the_next_one() {
+        let report = parse(KSFO);
+        assert_eq!(
+            report
+                .periods
+                .iter()
+                .map(|p| (p.kind, p.from, p.to))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    TafPeriodKind::Base,
+                    Some(at(16, 12, 0)),
+                    Some(at(16, 18, 0))
+                ),
+                (TafPeriodKind::From, Some(at(16, 18, 0)), Some(at(17, 2, 0))),
+                (TafPeriodKind::From, Some(at(17, 2, 0)), Some(at(17, 18, 0))),
+                (
+                    TafPeriodKind::Temporary,
+                    Some(at(17, 4, 0)),
+                    Some(at(17, 8, 0))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn from_periods_replace_the_prevailing_conditions() {
+        for (time, expected_wind, expected_clouds, weather) in [
+            (at(16, 17, 59), (Some(280), 10, None), ["FEW015"], 0),
+            (at(16, 18, 0), (Some(290), 15, Some(25)), ["SCT025"], 0),
+            (at(17, 3, 0), (None, 4, None), ["OVC008"], 1),
+        ] {
+            let prevailing = forecast(KSFO, time).prevailing;
+            assert_eq!(wind(&prevailing), Some(expected_wind), "{time}");
+            assert_eq!(cloud_covers(&prevailing), expected_clouds, "{time}");
+            assert_eq!(prevailing.weather.len(), weather, "{time}");
+        }
+    }
+
+    #[test]
+    fn becoming_periods_change_only_the_groups_they_forecast() {
+        let before = forecast(EGLL, at(16, 19, 0)).prevailing;
+        assert_eq!(wind(&before), Some((Some(240), 12, None)));
+
+        let during = forecast(EGLL, at(16, 21, 0)).prevailing;
+        assert_eq!(wind(&during), Some((Some(300), 8, None)));
+        assert_eq!(during.visibility, before.visibility);
+        assert_eq!(cloud_covers(&during), ["SCT030"]);
+
+        let cavok = forecast(EGLL, at(17, 12, 0)).prevailing;
+        assert!(cavok.cavok);
+        assert_eq!(cavok.visibility, None);
+        assert!(cavok.clouds.is_empty());
+        assert_eq!(wind(&cavok), Some((Some(300), 8, None)));
+    }
+
+    #[test]
+    fn lists_temporary_periods_in_effect() {
+        let temporary = |raw, time| {
+            forecast(raw, time)
+                .temporary
+                .iter()
+                .map(|p| (p.kind, p.probability))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            temporary(EGLL, at(16, 12, 0)),
+            [(TafPeriodKind::Temporary, None)]
+        );
+        assert_eq!(temporary(EGLL, at(16, 18, 0)), []);
+        assert_eq!(
+            temporary(EGLL, at(17, 5, 0)),
+            [(TafPeriodKind::Probability, Some(30))]
+        );
+        assert_eq!(
+            temporary(KSFO, at(17, 7, 59)),
+            [(TafPeriodKind::Temporary, None)]
+        );
+        assert_eq!(temporary(KSFO, at(17, 8, 0)), []);
+
+        // Temporary changes are listed separately, not applied to the prevailing conditions
+        let tempo = forecast(EGLL, at(16, 14, 0));
+        assert_eq!(wind(&tempo.prevailing), Some((Some(240), 12, None)));
+        assert_eq!(
+            wind(&tempo.temporary[0].conditions),
+            Some((Some(240), 20, Some(32)))
+        );
+        assert_eq!(cloud_covers(&tempo.temporary[0].conditions), ["BKN014"]);
+    }
+
+    #[test]
+    fn tracks_the_change_in_effect_and_the_next_one() {
+        for (raw, time, kind, until) in [
+            (KSFO, at(16, 13, 0), TafPeriodKind::Base, at(16, 18, 0)),
+            (KSFO, at(16, 18, 0), TafPeriodKind::From, at(17, 2, 0)),
+            (KSFO, at(17, 3, 0), TafPeriodKind::From, at(17, 18, 0)),
+            (EGLL, at(16, 14, 0), TafPeriodKind::Base, at(16, 20, 0)),
+            (EGLL, at(16, 21, 0), TafPeriodKind::Becoming, at(17, 10, 0)),
+            (EGLL, at(17, 12, 0), TafPeriodKind::Becoming, at(17, 18, 0)),
+        ] {
+            let forecast = forecast(raw, time);
+            assert_eq!(forecast.kind, kind, "{time}");
+            assert_eq!(forecast.until, Some(until), "{time}");
+        }
+    }
+
+    #[test]
+    fn summarises_the_forecast_in_taf_form() {
+        for (raw, time, summary) in [
+            (
+                KSFO,
+                at(16, 13, 0),
+                "1612/1618 28010KT P6SM FEW015 TL161800",
+            ),
+            (
+                KSFO,
+                at(16, 20, 0),
+                "FM161800 29015G25KT P6SM SCT025 TL170200",
+            ),
+            (
+                KSFO,
+                at(17, 5, 0),
+                "FM170200 VRB04KT 5SM BR OVC008 TL171800 TEMPO 1704/1708 2SM BR OVC004",
+            ),
+            (
+                EGLL,
+                at(16, 14, 0),
+                "1612/1718 24012KT 9999 SCT030 TL162000 TEMPO 1612/1618 24020G32KT 6000 SHRA BKN014",
+            ),
+            (
+                EGLL,
+                at(17, 5, 0),
+                "BECMG 1620/1623 30008KT 9999 SCT030 TL171000 PROB30 1703/1708 3000 BR BKN005",
+            ),
+            (
+                EGLL,
+                at(17, 12, 0),
+                "BECMG 1710/1713 30008KT CAVOK TL171800",
+            ),
+        ] {
+            assert_eq!(forecast(raw, time).summary(), summary, "{time}");
+        }
+
+        let midnight = parse_taf(
+            "TAF EGLL 161700Z 1618/1724 24012KT 9999 SCT030",
+            at(16, 17, 0),
+        )
+        .unwrap()
+        .forecast_at(at(17, 12, 0))
+        .unwrap();
+        assert_eq!(midnight.summary(), "1618/1724 24012KT 9999 SCT030 TL180000");
+    }
+
+    #[test]
+    fn has_no_forecast_outside_the_valid_period() {
+        let report = parse(EGLL);
+        assert_eq!(report.forecast_at(at(16, 11, 59)), None);
+        assert!(report.forecast_at(at(17, 17, 59)).is_some());
+        assert_eq!(report.forecast_at(at(17, 18, 0)), None);
+    }
+
+    #[test]
+    fn resolves_periods_across_month_end() {
+        let report = parse_taf(
+            "TAF AMD KSFO 302320Z 3100/0106 28010KT P6SM SKC FM010000 VRB03KT P6SM SKC",
+            at(30, 23, 30),
+        )
+        .unwrap();
+        assert!(report.amended);
+        assert_eq!(report.valid_from, Some(at(31, 0, 0)));
+        assert_eq!(
+            report.valid_to,
+            Some(Utc.with_ymd_and_hms(2024, 11, 1, 6, 0, 0).unwrap())
+        );
+        assert_eq!(
+            report.periods[1].from,
+            Some(Utc.with_ymd_and_hms(2024, 11, 1, 0, 0, 0).unwrap())
+        );
+    }
+}
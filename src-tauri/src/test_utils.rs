@@ -0,0 +1,101 @@
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+type Handler = dyn Fn(&str) -> (u16, String) + Send + Sync;
+
+/// Minimal HTTP server on a random local port, answering each GET with the status and body
+/// returned by a handler for the requested path and query
+pub struct MockServer {
+    address: SocketAddr,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    pub async fn start(handler: impl Fn(&str) -> (u16, String) + Send + Sync + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(vec![]));
+        let handler: Arc<Handler> = Arc::new(handler);
+
+        let server_requests = Arc::clone(&requests);
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let requests = Arc::clone(&server_requests);
+                let handler = Arc::clone(&handler);
+                tokio::spawn(async move {
+                    let Some(path) = read_request_path(&mut socket).await else {
+                        return;
+                    };
+                    let (status, body) = handler(&path);
+                    requests.lock().unwrap().push(path);
+
+                    let response = format!(
+                        "HTTP/1.1 {status} Mock\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                        body.len()
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+
+        Self { address, requests }
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// Paths requested so far, in order
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// Reads the request head and returns the path from its request line
+async fn read_request_path(socket: &mut tokio::net::TcpStream) -> Option<String> {
+    let mut request = vec![];
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let read = socket.read(&mut buf).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        request.extend_from_slice(&buf[..read]);
+    }
+
+    String::from_utf8_lossy(&request)
+        .lines()
+        .next()?
+        .split_whitespace()
+        .nth(1)
+        .map(ToString::to_string)
+}
+
+/// Empty directory under the system temp directory, removed again when dropped
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// `name` must be unique across tests, as they run in parallel
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("mini-metars-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,307 @@
+use crate::awc::Station;
+use crate::datafeed::Prefile;
+use serde::{Deserialize, Serialize};
+use vatsim_utils::distance::haversine;
+use vatsim_utils::models::{Pilot, V3ResponseData};
+
+/// Pilots below this groundspeed are treated as on the ground
+const GROUND_SPEED_THRESHOLD_KNOTS: i64 = 40;
+/// Pilots on the ground further than this from their departure airport are not counted, as they
+/// have usually filed a flight plan somewhere else
+const ON_AIRPORT_RADIUS_NM: f64 = 10.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InboundFlight {
+    pub callsign: String,
+    pub aircraft: String,
+    pub distance_nm: f64,
+    pub groundspeed: i64,
+    /// Minutes to the station at the current groundspeed
+    pub eta_minutes: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrafficCounts {
+    pub station: String,
+    /// Pilots on the ground at the station with a flight plan departing it
+    pub departures: usize,
+    /// Airborne pilots with a flight plan arriving at the station, nearest first
+    pub arrivals: Vec<InboundFlight>,
+    /// Flight plans departing the station filed by pilots who have not connected yet
+    pub prefiles: usize,
+}
+
+pub fn traffic_counts(
+    datafeed: &V3ResponseData,
+    prefiles: &[Prefile],
+    station: &Station,
+) -> TrafficCounts {
+    let distance_to =
+        |pilot: &Pilot| haversine(pilot.latitude, pilot.longitude, station.lat, station.lon);
+
+    let departures = datafeed
+        .pilots
+        .iter()
+        .filter(|p| {
+            p.flight_plan
+                .as_ref()
+                .is_some_and(|f| f.departure.eq_ignore_ascii_case(&station.icao_id))
+                && p.groundspeed < GROUND_SPEED_THRESHOLD_KNOTS
+                && distance_to(p) <= ON_AIRPORT_RADIUS_NM
+        })
+        .count();
+
+    let mut arrivals = datafeed
+        .pilots
+        .iter()
+        .filter(|p| {
+            p.flight_plan
+                .as_ref()
+                .is_some_and(|f| f.arrival.eq_ignore_ascii_case(&station.icao_id))
+                && p.groundspeed >= GROUND_SPEED_THRESHOLD_KNOTS
+        })
+        .map(|p| {
+            let distance_nm = distance_to(p);
+            InboundFlight {
+                callsign: p.callsign.clone(),
+                aircraft: p
+                    .flight_plan
+                    .as_ref()
+                    .map(|f| f.aircraft_short.clone())
+                    .unwrap_or_default(),
+                distance_nm,
+                groundspeed: p.groundspeed,
+                eta_minutes: eta_minutes(distance_nm, p.groundspeed),
+            }
+        })
+        .collect::<Vec<_>>();
+    arrivals.sort_by_key(|a| a.eta_minutes);
+
+    let prefiles = prefiles
+        .iter()
+        .filter(|p| {
+            p.flight_plan
+                .as_ref()
+                .is_some_and(|f| f.departure.eq_ignore_ascii_case(&station.icao_id))
+        })
+        .count();
+
+    TrafficCounts {
+        station: station.icao_id.clone(),
+        departures,
+        arrivals,
+        prefiles,
+    }
+}
+
+/// A stationary aircraft never arrives, so it has the largest possible ETA
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+fn eta_minutes(distance_nm: f64, groundspeed: i64) -> i64 {
+    if groundspeed <= 0 {
+        return i64::MAX;
+    }
+    (distance_nm / groundspeed as f64 * 60.0).round() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datafeed::VatsimDatafeed;
+    use vatsim_utils::models::{FlightPlan, GeneralData};
+
+    const KSFO: (f64, f64) = (37.619, -122.375);
+
+    fn station() -> Station {
+        Station {
+            icao_id: "KSFO".to_string(),
+            iata_id: "SFO".to_string(),
+            faa_id: "SFO".to_string(),
+            wmo_id: "72494".to_string(),
+            lat: KSFO.0,
+            lon: KSFO.1,
+            elev: 3,
+            site: "San Francisco Intl".to_string(),
+            state: "CA".to_string(),
+            country: "US".to_string(),
+            priority: 1,
+        }
+    }
+
+    fn flight_plan(departure: &str, arrival: &str) -> FlightPlan {
+        FlightPlan {
+            flight_rules: "I".to_string(),
+            aircraft: "B738/M-SDE2E3FGHIRWXY/LB1".to_string(),
+            aircraft_faa: "B738/L".to_string(),
+            aircraft_short: "B738".to_string(),
+            departure: departure.to_string(),
+            arrival: arrival.to_string(),
+            alternate: String::new(),
+            cruise_tas: "450".to_string(),
+            altitude: "35000".to_string(),
+            deptime: "1300".to_string(),
+            enroute_time: "0115".to_string(),
+            fuel_time: "0300".to_string(),
+            remarks: "/V/".to_string(),
+            route: "DCT".to_string(),
+            revision_id: 1,
+            assigned_transponder: "0000".to_string(),
+        }
+    }
+
+    /// A pilot `degrees_north` of KSFO, where one degree of latitude is 60 nm
+    fn pilot(
+        callsign: &str,
+        route: Option<(&str, &str)>,
+        degrees_north: f64,
+        groundspeed: i64,
+    ) -> Pilot {
+        Pilot {
+            cid: 1_234_567,
+            name: "Test Pilot".to_string(),
+            callsign: callsign.to_string(),
+            server: "USA-WEST".to_string(),
+            pilot_rating: 0,
+            military_rating: 0,
+            latitude: KSFO.0 + degrees_north,
+            longitude: KSFO.1,
+            altitude: 0,
+            groundspeed,
+            transponder: "2000".to_string(),
+            heading: 180,
+            qnh_i_hg: 29.92,
+            qnh_mb: 1013,
+            flight_plan: route.map(|(departure, arrival)| flight_plan(departure, arrival)),
+            logon_time: "2024-10-16T11:00:00.0000000Z".to_string(),
+            last_updated: "2024-10-16T12:00:00.0000000Z".to_string(),
+        }
+    }
+
+    fn prefile(callsign: &str, departure: &str) -> Prefile {
+        Prefile {
+            cid: 7_654_321,
+            callsign: callsign.to_string(),
+            flight_plan: Some(flight_plan(departure, "KLAX")),
+        }
+    }
+
+    fn datafeed(pilots: Vec<Pilot>, prefiles: Vec<Prefile>) -> VatsimDatafeed {
+        VatsimDatafeed {
+            data: V3ResponseData {
+                general: GeneralData {
+                    version: 3,
+                    reload: 1,
+                    update: "20241016120000".to_string(),
+                    update_timestamp: "2024-10-16T12:00:00.0000000Z".to_string(),
+                    connected_clients: 0,
+                    unique_users: 0,
+                },
+                pilots,
+                controllers: vec![],
+                atis: vec![],
+                servers: vec![],
+                facilities: vec![],
+                ratings: vec![],
+                pilot_ratings: vec![],
+                military_ratings: vec![],
+            },
+            prefiles,
+        }
+    }
+
+    fn counts(datafeed: &VatsimDatafeed) -> TrafficCounts {
+        traffic_counts(&datafeed.data, &datafeed.prefiles, &station())
+    }
+
+    #[test]
+    fn counts_departures_on_the_ground_at_the_station() {
+        let datafeed = datafeed(
+            vec![
+                pilot("UAL1", Some(("KSFO", "KLAX")), 0.0, 0),
+                pilot("UAL2", Some(("ksfo", "KLAX")), 0.05, 15),
+                // Taking off
+                pilot("UAL3", Some(("KSFO", "KLAX")), 0.0, 140),
+                // Filed from KSFO while parked somewhere else
+                pilot("UAL4", Some(("KSFO", "KLAX")), 1.0, 0),
+                // Arrived and parked
+                pilot("UAL5", Some(("KLAX", "KSFO")), 0.0, 0),
+                pilot("UAL6", None, 0.0, 0),
+            ],
+            vec![],
+        );
+        let counts = counts(&datafeed);
+        assert_eq!(counts.station, "KSFO");
+        assert_eq!(counts.departures, 2);
+        assert!(counts.arrivals.is_empty());
+    }
+
+    #[test]
+    fn lists_airborne_arrivals_by_eta() {
+        let datafeed = datafeed(
+            vec![
+                pilot("DAL1", Some(("KSEA", "KSFO")), 1.0, 240),
+                pilot("DAL2", Some(("KLAX", "ksfo")), 0.5, 360),
+                // Landed and taxiing in
+                pilot("DAL3", Some(("KLAX", "KSFO")), 0.0, 20),
+                pilot("DAL4", Some(("KSFO", "KSEA")), 0.5, 360),
+            ],
+            vec![],
+        );
+        assert_eq!(
+            counts(&datafeed).arrivals,
+            [
+                InboundFlight {
+                    callsign: "DAL2".to_string(),
+                    aircraft: "B738".to_string(),
+                    distance_nm: 30.0,
+                    groundspeed: 360,
+                    eta_minutes: 5,
+                },
+                InboundFlight {
+                    callsign: "DAL1".to_string(),
+                    aircraft: "B738".to_string(),
+                    distance_nm: 60.0,
+                    groundspeed: 240,
+                    eta_minutes: 15,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn computes_eta_from_distance_and_groundspeed() {
+        for (distance_nm, groundspeed, eta) in [
+            (60.0, 240, 15),
+            (100.0, 450, 13),
+            (0.0, 140, 0),
+            (30.0, 0, i64::MAX),
+            (0.0, 0, i64::MAX),
+        ] {
+            assert_eq!(
+                eta_minutes(distance_nm, groundspeed),
+                eta,
+                "{distance_nm} nm at {groundspeed} kt"
+            );
+        }
+    }
+
+    #[test]
+    fn counts_prefiles_departing_the_station() {
+        let datafeed = datafeed(
+            vec![],
+            vec![
+                prefile("SWA1", "KSFO"),
+                prefile("SWA2", "ksfo"),
+                prefile("SWA3", "KOAK"),
+                Prefile {
+                    flight_plan: None,
+                    ..prefile("SWA4", "KSFO")
+                },
+            ],
+        );
+        let counts = counts(&datafeed);
+        assert_eq!(counts.prefiles, 2);
+        assert_eq!(counts.departures, 0);
+    }
+}
@@ -9,7 +9,13 @@ import { logIfDev } from "./logging.ts";
 import { clsx } from "clsx";
 import { createShortcut, KbdKey } from "@solid-primitives/keyboard";
 import {
+  AtisChangeAlert,
+  AtisChangedPayload,
+  AtisChangeSound,
+  AtisNetwork,
+  importProfileCmd,
   initializeDatafeedCmd,
+  loadLibraryProfileCmd,
   loadProfileCmd,
   loadSettingsInitialCmd,
   Profile,
@@ -17,10 +23,20 @@ import {
   saveProfileCmd,
   saveSettingsCmd,
   Settings,
+  StationEntry,
 } from "./tauri.ts";
 import { type } from "@tauri-apps/plugin-os";
 import { CustomTitlebar } from "./CustomTitlebar.tsx";
+import { ProfileSwitcher } from "./ProfileSwitcher.tsx";
 import { warn } from "@tauri-apps/plugin-log";
+import { listen } from "@tauri-apps/api/event";
+import { message } from "@tauri-apps/plugin-dialog";
+import {
+  isPermissionGranted,
+  requestPermission,
+  sendNotification,
+} from "@tauri-apps/plugin-notification";
+import { playAlertSound } from "./sounds.ts";
 
 function removeIndex<T>(array: readonly T[], index: number): T[] {
   return [...array.slice(0, index), ...array.slice(index + 1)];
@@ -32,6 +48,9 @@ export interface MainUiStore {
   showTitlebar: boolean;
   units: "inHg" | "hPa";
   hideAirportIfMissingAtis: boolean;
+  atisChangeAlert: AtisChangeAlert;
+  atisChangeSound: AtisChangeSound;
+  network: AtisNetwork;
 }
 
 function App() {
@@ -47,13 +66,17 @@ function App() {
 
   // Main signals for IDs and input
   const [inputId, setInputId] = createSignal("");
-  const [ids, setIds] = createStore<string[]>([]);
+  const [showProfileSwitcher, setShowProfileSwitcher] = createSignal(false);
+  const [stations, setStations] = createStore<StationEntry[]>([]);
   const [mainUi, setMainUi] = createStore<MainUiStore>({
     showScroll: true,
     showInput: true,
     showTitlebar: true,
     units: "inHg",
     hideAirportIfMissingAtis: false,
+    atisChangeAlert: "None",
+    atisChangeSound: "Chime",
+    network: "Vatsim",
   });
 
   // Settings store
@@ -61,6 +84,17 @@ function App() {
     loadMostRecentProfileOnOpen: true,
     alwaysOnTop: true,
     autoResize: true,
+    flightCategoryRules: "Faa",
+    icaoFlightCategoryLevels: {
+      vfr: { ceilingFt: 1500, visibilityM: 5000 },
+      mvfr: { ceilingFt: 1000, visibilityM: 3000 },
+      ifr: { ceilingFt: 500, visibilityM: 1500 },
+    },
+    weatherProvider: "Awc",
+    notifyAltimeterChanges: false,
+    controllerPrefixes: {},
+    apiEnabled: false,
+    recentProfiles: [],
   });
 
   let CtrlOrCmd: KbdKey = type() === "macos" || type() === "ios" ? "Meta" : "Control";
@@ -68,11 +102,14 @@ function App() {
   let currentProfileState = createMemo<Profile>(() => {
     return {
       name: "",
-      stations: ids,
+      stations: stations,
       showTitlebar: mainUi.showTitlebar,
       showInput: mainUi.showInput,
       units: mainUi.units,
       hideAirportIfMissingAtis: mainUi.hideAirportIfMissingAtis,
+      atisChangeAlert: mainUi.atisChangeAlert,
+      atisChangeSound: mainUi.atisChangeSound,
+      network: mainUi.network,
     };
   });
 
@@ -90,6 +127,30 @@ function App() {
     },
     { preventDefault: true, requireReset: true }
   );
+  createShortcut(
+    [CtrlOrCmd, "I"],
+    async () => {
+      try {
+        let res = await importProfileCmd();
+        await loadProfile(res.profile);
+        await saveSettingsCmd(settings);
+        if (res.unresolved.length > 0) {
+          await message(`Stations not found: ${res.unresolved.join(", ")}`, {
+            title: `Imported ${res.name}`,
+            kind: "warning",
+          });
+        }
+      } catch (error) {
+        await warn(`Frontend error: ${error}`);
+      }
+    },
+    { preventDefault: true, requireReset: true }
+  );
+  createShortcut(
+    [CtrlOrCmd, "P"],
+    async () => await applyFnAndResize(() => setShowProfileSwitcher((prev) => !prev)),
+    { preventDefault: true, requireReset: true }
+  );
   createShortcut(
     [CtrlOrCmd, "S"],
     async () => {
@@ -120,7 +181,7 @@ function App() {
     [CtrlOrCmd, "D"],
     async () =>
       await applyFnAndResize(() => {
-        if (ids.length > 0) {
+        if (stations.length > 0) {
           setMainUi("showInput", (prev) => !prev);
         }
       }),
@@ -195,30 +256,47 @@ function App() {
     if (p.window === null) {
       await applyFnAndResize(() => {
         batch(() => {
-          setIds(p.stations);
+          setStations(p.stations);
           setMainUi("showInput", p.showInput);
           setMainUi("showTitlebar", p.showTitlebar);
           setMainUi("units", p.units);
           setMainUi("hideAirportIfMissingAtis", p.hideAirportIfMissingAtis);
+          setMainUi("atisChangeAlert", p.atisChangeAlert);
+          setMainUi("atisChangeSound", p.atisChangeSound);
+          setMainUi("network", p.network);
         });
       });
     } else {
       batch(() => {
-        setIds(p.stations);
+        setStations(p.stations);
         setMainUi("showInput", p.showInput);
         setMainUi("showTitlebar", p.showTitlebar);
         setMainUi("units", p.units);
         setMainUi("hideAirportIfMissingAtis", p.hideAirportIfMissingAtis);
+        setMainUi("atisChangeAlert", p.atisChangeAlert);
+        setMainUi("atisChangeSound", p.atisChangeSound);
+        setMainUi("network", p.network);
       });
     }
   }
 
+  async function switchProfile(path: string) {
+    try {
+      await applyFnAndResize(() => setShowProfileSwitcher(false));
+      let p = await loadLibraryProfileCmd(path);
+      await loadProfile(p);
+      await saveSettingsCmd(settings);
+    } catch (error) {
+      await warn(`Frontend error: ${error}`);
+    }
+  }
+
   async function addStation(e: SubmitEvent) {
     e.preventDefault();
     await applyFnAndResize(() =>
       batch(() => {
         if (inputId().length >= 3 && inputId().length <= 4) {
-          setIds(ids.length, inputId());
+          setStations(stations.length, { id: inputId() });
           setInputId("");
         }
       })
@@ -226,10 +304,37 @@ function App() {
   }
 
   async function removeStation(index: number) {
-    await applyFnAndResize(() => setIds((ids) => removeIndex(ids, index)));
+    await applyFnAndResize(() => setStations((stations) => removeIndex(stations, index)));
+  }
+
+  async function alertAtisChange(change: AtisChangedPayload, stationAlert?: AtisChangeAlert) {
+    let alert = stationAlert ?? mainUi.atisChangeAlert;
+    if (alert === "Sound" || alert === "SoundAndNotification") {
+      playAlertSound(mainUi.atisChangeSound);
+    }
+    if (alert === "Notification" || alert === "SoundAndNotification") {
+      let granted = await isPermissionGranted();
+      if (!granted) {
+        granted = (await requestPermission()) === "granted";
+      }
+      if (granted) {
+        sendNotification({
+          title: `${change.station} ATIS ${change.letter}`,
+          body: `Information ${change.previousLetter} is now ${change.letter}`,
+        });
+      }
+    }
   }
 
   onMount(async () => {
+    await listen<Profile>("profile-changed", async (event) => {
+      try {
+        await loadProfile(event.payload);
+      } catch (error) {
+        await warn(`Frontend error: ${error}`);
+      }
+    });
+
     let res = await loadSettingsInitialCmd();
     await initializeDatafeedCmd();
     setSettings(res.settings);
@@ -252,17 +357,32 @@ function App() {
         })}
       >
         <div class="flex flex-col bg-black text-white" ref={containerRef}>
+          <Show when={showProfileSwitcher()}>
+            <ProfileSwitcher
+              onSelect={switchProfile}
+              onClose={async () => await applyFnAndResize(() => setShowProfileSwitcher(false))}
+            />
+          </Show>
           <div class="flex flex-col grow">
-            <For each={ids}>
-              {(id, i) => (
-                <div class="flex">
-                  <Metar
-                    requestedId={id}
-                    resizeAfterFn={applyFnAndResize}
-                    mainUi={mainUi}
-                    deleteOnClick={async () => await removeStation(i())}
-                  />
-                </div>
+            <For each={stations}>
+              {(station, i) => (
+                <Show when={!station.hidden}>
+                  <div class="flex">
+                    <Metar
+                      station={station}
+                      resizeAfterFn={applyFnAndResize}
+                      mainUi={mainUi}
+                      deleteOnClick={async () => await removeStation(i())}
+                      onAtisChange={async (change) => {
+                        try {
+                          await alertAtisChange(change, station.alerts?.atisChange);
+                        } catch (error) {
+                          await warn(`Frontend error: ${error}`);
+                        }
+                      }}
+                    />
+                  </div>
+                </Show>
               )}
             </For>
             <Show when={mainUi.showInput}>
@@ -8,7 +8,29 @@ import {
   onMount,
   Show,
 } from "solid-js";
-import { lookupStationCmd, updateAtisCmd, updateMetarCmd } from "./tauri.ts";
+import {
+  AtisChangedPayload,
+  checkAtisConsistencyCmd,
+  Discrepancy,
+  FetchMetarResponse,
+  fetchTafCmd,
+  FlightCategory,
+  getControllersCmd,
+  getMetarHistoryCmd,
+  getRunwayWindsCmd,
+  getTrafficCountsCmd,
+  lookupStationCmd,
+  OnlineController,
+  MetarsUpdatedPayload,
+  RunwayWindComponents,
+  RunwayWindsResponse,
+  StationEntry,
+  TrafficCounts,
+  subscribeMetarCmd,
+  unsubscribeMetarCmd,
+  updateAtisCmd,
+} from "./tauri.ts";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
 import { createStore } from "solid-js/store";
 import { MainUiStore } from "./App.tsx";
 import { clsx } from "clsx";
@@ -16,10 +38,55 @@ import { debug, trace, warn } from "@tauri-apps/plugin-log";
 import { DeleteButton } from "./DeleteButton.tsx";
 
 interface MetarProps {
-  requestedId: string;
+  station: StationEntry;
   mainUi: MainUiStore;
   resizeAfterFn: (fn: () => void) => void;
   deleteOnClick: () => void;
+  onAtisChange: (change: AtisChangedPayload) => void;
+}
+
+// Network feeds are regenerated every 15 seconds, so anything older means updates have stalled
+const STALE_ATIS_AGE_SECS = 90;
+
+// Earlier METARs shown below the full METAR text
+const METAR_HISTORY_HOURS = 3;
+
+// TAFs are issued every few hours, refreshing more often keeps the forecast in effect current
+const TAF_REFRESH_MINUTES = 5;
+
+const FLIGHT_CATEGORY_CLASSES: Record<FlightCategory, string> = {
+  VFR: "text-green-500",
+  MVFR: "text-blue-400",
+  IFR: "text-red-500",
+  LIFR: "text-fuchsia-500",
+};
+
+function discrepancyString(d: Discrepancy) {
+  const time = (t: string) => `${new Date(t).toISOString().substring(11, 16)}Z`;
+  switch (d.kind) {
+    case "altimeter":
+      return `ATIS altimeter ${d.atis.value} ${d.atis.unit}, METAR ${d.metar.value} ${d.metar.unit}`;
+    case "wind":
+      return `ATIS wind ${d.atis}, METAR ${d.metar}`;
+    case "observationTime":
+      return `ATIS observation ${time(d.atis)} older than METAR ${time(d.metar)}`;
+  }
+}
+
+// e.g. "28R H15 R4 (G H25 R7)", or "10L T15" for a tailwind
+function runwayWindString(c: RunwayWindComponents) {
+  const component = (value: number, positive: string, negative: string) =>
+    `${value < 0 ? negative : positive}${Math.abs(Math.round(value))}`;
+  let parts = [c.runway];
+  if (c.headwind !== undefined && c.crosswind !== undefined) {
+    parts.push(component(c.headwind, "H", "T"), component(c.crosswind, "R", "L"));
+  }
+  if (c.gustHeadwind !== undefined && c.gustCrosswind !== undefined) {
+    parts.push(
+      `(G ${component(c.gustHeadwind, "H", "T")} ${component(c.gustCrosswind, "R", "L")})`
+    );
+  }
+  return parts.join(" ");
 }
 
 function getRandomInt(min: number, max: number) {
@@ -37,63 +104,160 @@ export const Metar: Component<MetarProps> = (props) => {
   const [displayId, setDisplayId] = createSignal("");
   const [wind, setWind] = createSignal("");
   const [rawMetar, setRawMetar] = createSignal("");
+  const [flightCategory, setFlightCategory] = createSignal<FlightCategory | undefined>(undefined);
+  const flightCategoryClass = createMemo(() => {
+    let category = flightCategory();
+    return category === undefined ? undefined : FLIGHT_CATEGORY_CLASSES[category];
+  });
   const [altimeter, setAltimeter] = createStore<{ inHg: number; hpa: number }>({
     inHg: 0.0,
     hpa: 0.0,
   });
+  const units = createMemo(() => props.station.units ?? props.mainUi.units);
   const altimeterString = createMemo(() => {
-    if (props.mainUi.units === "inHg") {
+    if (units() === "inHg") {
       return altimeter.inHg == 0 ? "" : altimeter.inHg.toFixed(2);
     } else {
       return altimeter.hpa == 0 ? "" : altimeter.hpa.toFixed(0);
     }
   });
+  const [runwayWinds, setRunwayWinds] = createSignal<RunwayWindsResponse | undefined>(undefined);
+  const runwayWindsString = createMemo(() => {
+    const winds = runwayWinds();
+    const favoured = winds?.favouredRunway;
+    if (winds === undefined || favoured === undefined) {
+      return undefined;
+    }
+    // Favoured runway first, then the rest in database order
+    return winds.runways
+      .slice()
+      .sort((a, b) => Number(b.runway === favoured) - Number(a.runway === favoured))
+      .map(runwayWindString)
+      .join("\n");
+  });
   const [showFullMetar, setShowFullMetar] = createSignal(false);
+  const [metarHistory, setMetarHistory] = createSignal<string[]>([]);
+  const [rawTaf, setRawTaf] = createSignal("");
+  const [tafSummary, setTafSummary] = createSignal("");
+  const [showTaf, setShowTaf] = createSignal(false);
   const [atisLetter, setAtisLetter] = createSignal("-");
   const [atisTexts, setAtisTexts] = createStore<string[]>([]);
+  const [atisRunways, setAtisRunways] = createSignal<string | undefined>(undefined);
+  const [atisDataAge, setAtisDataAge] = createSignal<number | undefined>(undefined);
+  const [discrepancies, setDiscrepancies] = createStore<Discrepancy[]>([]);
+  const atisTitle = createMemo(() => {
+    let lines = [atisRunways()].concat(discrepancies.map(discrepancyString));
+    return lines.filter((l) => l !== undefined).join("\n") || undefined;
+  });
   const [showAtisTexts, setShowAtisTexts] = createSignal(false);
+  const [controllers, setControllers] = createStore<OnlineController[]>([]);
+  const controllersString = createMemo(() =>
+    controllers
+      .map((c) => {
+        let since = c.logonTime
+          ? ` since ${new Date(c.logonTime).toISOString().substring(11, 16)}Z`
+          : "";
+        return `${c.callsign} ${c.frequency}${since}`;
+      })
+      .join("\n")
+  );
+  const [traffic, setTraffic] = createSignal<TrafficCounts | undefined>(undefined);
+  const trafficString = createMemo(() => {
+    let t = traffic();
+    if (t === undefined) {
+      return "";
+    }
+    let prefiled = t.prefiles > 0 ? [`${t.prefiles} prefiled`] : [];
+    return prefiled
+      .concat(t.arrivals.map((a) => `${a.callsign} ${a.aircraft} ${a.etaMinutes} min`))
+      .join("\n");
+  });
 
-  // Update handle
-  const [metarTimerHandle, setMetarTimerHandle] = createSignal<number | undefined>(undefined);
+  // Update handles
+  const [unlistenMetars, setUnlistenMetars] = createSignal<UnlistenFn | undefined>(undefined);
+  const [unlistenAtis, setUnlistenAtis] = createSignal<UnlistenFn | undefined>(undefined);
   const [letterTimerHandle, setLetterTimerHandle] = createSignal<number | undefined>(undefined);
+  const [tafTimerHandle, setTafTimerHandle] = createSignal<number | undefined>(undefined);
 
   const fetchAndUpdateStation = async () => {
     try {
-      await debug(`Frontend: Looking up requested ID: ${props.requestedId}`);
-      let station = await lookupStationCmd(props.requestedId);
+      await debug(`Frontend: Looking up requested ID: ${props.station.id}`);
+      let station = await lookupStationCmd(props.station.id);
       setIcaoId(station.icaoId);
-      setDisplayId(station.faaId !== "-" ? station.faaId : station.icaoId);
+      setDisplayId(
+        props.station.alias ?? (station.faaId !== "-" ? station.faaId : station.icaoId)
+      );
       setValidId(true);
     } catch (error) {
-      setDisplayId(props.requestedId);
+      setDisplayId(props.station.alias ?? props.station.id);
       await warn(`Frontend error: ${error}`);
     }
   };
 
-  const updateMetar = async () => {
-    if (!validId()) {
+  const applyMetar = async (res: FetchMetarResponse) => {
+    let newTimestamp = new Date(res.metar.obsTime);
+    if (currentTimestamp() === undefined || newTimestamp > currentTimestamp()!) {
+      await trace(`Frontend: New METAR found for ${icaoId()}`);
+      setCurrentTimestamp(newTimestamp);
+      setAltimeter(res.altimeter);
+      setWind(res.windString);
+      setRawMetar(res.metar.rawOb);
+      setFlightCategory(res.flightCategory);
+      await updateRunwayWinds();
+      await updateConsistency();
+    } else {
+      await trace(`Frontend: Fetched METAR for ${icaoId()} same as displayed`);
+    }
+  };
+
+  const updateRunwayWinds = async () => {
+    try {
+      setRunwayWinds(await getRunwayWindsCmd(icaoId()));
+    } catch (error) {
+      // Not every station has runway data, so this is expected rather than a warning
+      setRunwayWinds(undefined);
+      await trace(`Frontend: No runway winds for ${icaoId()}: ${error}`);
+    }
+  };
+
+  // Checked when either the METAR or the ATIS letter changes, rather than on every ATIS poll
+  const updateConsistency = async () => {
+    if (atisLetter() === "-") {
+      setDiscrepancies([]);
       return;
     }
 
     try {
-      await trace(`Frontend: Starting update check for id ${icaoId()}`);
-      let res = await updateMetarCmd(icaoId());
-      await trace(`Frontend: Retrieved METAR: ${res}`);
-      let newTimestamp = new Date(res.metar.obsTime);
-      if (currentTimestamp() === undefined || newTimestamp > currentTimestamp()!) {
-        await trace(`Frontend: New METAR found for ${icaoId()}`);
-        setCurrentTimestamp(newTimestamp);
-        setAltimeter(res.altimeter);
-        setWind(res.windString);
-        setRawMetar(res.metar.rawOb);
-      } else {
-        await trace(`Frontend: Fetched METAR for ${icaoId()} same as displayed`);
-      }
+      let rules = props.station.atisCallsignRules;
+      let res = await checkAtisConsistencyCmd(
+        [icaoId()],
+        props.mainUi.network,
+        rules === undefined ? undefined : { [icaoId()]: rules }
+      );
+      setDiscrepancies(res.find((c) => c.station === icaoId())?.discrepancies ?? []);
     } catch (error) {
       await warn(`Frontend error: ${error}`);
     }
   };
 
+  const updateTaf = async () => {
+    if (!validId()) {
+      return;
+    }
+
+    props.resizeAfterFn(async () => {
+      try {
+        let res = await fetchTafCmd(icaoId());
+        batch(() => {
+          setRawTaf(res.taf.rawTAF);
+          setTafSummary(res.currentSummary ?? "");
+        });
+      } catch (error) {
+        await warn(`Frontend error: ${error}`);
+      }
+    });
+  };
+
   const updateAtis = async () => {
     if (!validId()) {
       return;
@@ -102,10 +266,22 @@ export const Metar: Component<MetarProps> = (props) => {
     props.resizeAfterFn(async () => {
       try {
         await trace(`Starting ATIS letter fetch for ${icaoId()}`);
-        let res = await updateAtisCmd(icaoId());
+        let res = await updateAtisCmd(
+          icaoId(),
+          props.mainUi.network,
+          props.station.atisCallsignRules
+        );
         await trace(`Retrieved ATIS Letter ${res}`);
+        let letterChanged = res.letter !== atisLetter();
         setAtisLetter(res.letter);
         setAtisTexts(res.texts);
+        setAtisRunways(res.runwaySummary);
+        setAtisDataAge(res.dataAgeSecs);
+        if (letterChanged) {
+          await updateConsistency();
+        }
+        setControllers(await getControllersCmd(icaoId()));
+        setTraffic(await getTrafficCountsCmd(icaoId()));
       } catch (error) {
         await warn(`Frontend error: ${error}`);
       }
@@ -116,28 +292,71 @@ export const Metar: Component<MetarProps> = (props) => {
     try {
       await fetchAndUpdateStation();
       if (validId()) {
-        await updateMetar();
-        setMetarTimerHandle(setInterval(updateMetar, 1000 * getRandomInt(120, 150)));
+        // The first METAR arrives with the batch poll the subscription triggers
+        setUnlistenMetars(
+          await listen<MetarsUpdatedPayload>("metars-updated", async (event) => {
+            let res = event.payload.metars.find((m) => m.metar.icaoId === icaoId());
+            if (res !== undefined) {
+              await applyMetar(res);
+            }
+          })
+        );
+        await subscribeMetarCmd(icaoId(), props.station.alerts?.minAltimeterChangeHpa);
+        setUnlistenAtis(
+          await listen<AtisChangedPayload>("atis-changed", (event) => {
+            if (event.payload.station === icaoId()) {
+              props.onAtisChange(event.payload);
+            }
+          })
+        );
 
         await updateAtis();
         setLetterTimerHandle(setInterval(updateAtis, 1000 * getRandomInt(20, 30)));
+        await updateTaf();
+        setTafTimerHandle(setInterval(updateTaf, 1000 * 60 * TAF_REFRESH_MINUTES));
       }
     } catch (error) {
       await warn(`Frontend error: ${error}`);
     }
   });
 
-  onCleanup(() => {
-    if (metarTimerHandle() !== undefined) {
-      clearInterval(metarTimerHandle());
+  onCleanup(async () => {
+    if (unlistenMetars() !== undefined) {
+      unlistenMetars()!();
+    }
+
+    if (unlistenAtis() !== undefined) {
+      unlistenAtis()!();
+    }
+
+    if (validId()) {
+      await unsubscribeMetarCmd(icaoId());
     }
 
     if (letterTimerHandle() !== undefined) {
       clearInterval(letterTimerHandle());
     }
+
+    if (tafTimerHandle() !== undefined) {
+      clearInterval(tafTimerHandle());
+    }
   });
 
-  const toggleShowMetar = () => {
+  const toggleShowMetar = async () => {
+    if (!showFullMetar() && validId()) {
+      try {
+        let history = await getMetarHistoryCmd(icaoId(), METAR_HISTORY_HOURS);
+        setMetarHistory(
+          history
+            .map((m) => m.metar.rawOb)
+            .filter((raw) => raw !== rawMetar())
+            .reverse()
+        );
+      } catch (error) {
+        await warn(`Frontend error: ${error}`);
+      }
+    }
+
     props.resizeAfterFn(() => {
       batch(() => {
         if (showFullMetar()) {
@@ -145,6 +364,7 @@ export const Metar: Component<MetarProps> = (props) => {
         } else {
           setShowFullMetar(true);
           setShowAtisTexts(false);
+          setShowTaf(false);
         }
       });
     });
@@ -163,6 +383,21 @@ export const Metar: Component<MetarProps> = (props) => {
         } else {
           setShowAtisTexts(true);
           setShowFullMetar(false);
+          setShowTaf(false);
+        }
+      });
+    });
+  };
+
+  const toggleShowTaf = () => {
+    props.resizeAfterFn(() => {
+      batch(() => {
+        if (showTaf() || rawTaf() === "") {
+          setShowTaf(false);
+        } else {
+          setShowTaf(true);
+          setShowFullMetar(false);
+          setShowAtisTexts(false);
         }
       });
     });
@@ -179,7 +414,10 @@ export const Metar: Component<MetarProps> = (props) => {
   return (
     <Show
       when={
-        props.mainUi.showInput || !props.mainUi.hideAirportIfMissingAtis || atisLetter() !== "-"
+        props.mainUi.showInput ||
+        !props.mainUi.hideAirportIfMissingAtis ||
+        props.station.pinned ||
+        atisLetter() !== "-"
       }
     >
       <Show when={props.mainUi.showInput}>
@@ -187,26 +425,63 @@ export const Metar: Component<MetarProps> = (props) => {
       </Show>
       <div class="flex flex-col mx-1 select-none cursor-pointer">
         <div class="flex font-mono text-sm space-x-2.5">
-          <div class="w-8">{displayId()}</div>
-          <div class="w-8 text-center" onClick={toggleShowAtisTexts}>
+          <div
+            class={clsx("w-8", flightCategoryClass())}
+            title={controllersString() || undefined}
+          >
+            {displayId()}
+          </div>
+          <div
+            class={clsx({
+              "w-8 text-center": true,
+              "text-gray-500": (atisDataAge() ?? 0) > STALE_ATIS_AGE_SECS,
+              "text-yellow-400":
+                discrepancies.length > 0 && (atisDataAge() ?? 0) <= STALE_ATIS_AGE_SECS,
+            })}
+            title={atisTitle()}
+            onClick={toggleShowAtisTexts}
+          >
             {atisLetter()}
           </div>
           <div
             class={clsx({
               "text-center": true,
-              "w-12": props.mainUi.units === "inHg",
-              "w-10": props.mainUi.units === "hPa",
+              "w-12": units() === "inHg",
+              "w-10": units() === "hPa",
             })}
             onClick={toggleShowMetar}
           >
             {altimeterString()}
           </div>
-          <div class="flex-grow" onClick={toggleShowMetar}>
+          <div class="flex-grow" title={runwayWindsString()} onClick={toggleShowMetar}>
             {wind()}
           </div>
+          <Show
+            when={
+              traffic() &&
+              (traffic()!.departures > 0 ||
+                traffic()!.arrivals.length > 0 ||
+                traffic()!.prefiles > 0)
+            }
+          >
+            <div class="text-gray-400 pr-1" title={trafficString() || undefined}>
+              ↑{traffic()!.departures}
+              {traffic()!.prefiles > 0 ? `+${traffic()!.prefiles}` : ""} ↓
+              {traffic()!.arrivals.length}
+            </div>
+          </Show>
         </div>
+        <Show when={tafSummary() !== ""}>
+          <div class="text-xs text-gray-400 truncate" title={tafSummary()} onClick={toggleShowTaf}>
+            {tafSummary()}
+          </div>
+        </Show>
         <Show when={showFullMetar() && rawMetar() !== ""}>
           <div class={fullTextClass()}>{rawMetar()}</div>
+          <For each={metarHistory()}>{(raw) => <div class={fullTextClass()}>{raw}</div>}</For>
+        </Show>
+        <Show when={showTaf() && rawTaf() !== ""}>
+          <div class={fullTextClass()}>{rawTaf()}</div>
         </Show>
         <Show when={showAtisTexts()}>
           <For each={atisTexts}>{(atisText) => <div class={fullTextClass()}>{atisText}</div>}</For>
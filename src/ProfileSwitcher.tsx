@@ -0,0 +1,63 @@
+import { Component, createMemo, createSignal, For, onMount } from "solid-js";
+// @ts-ignore
+import { autofocus } from "@solid-primitives/autofocus";
+import { warn } from "@tauri-apps/plugin-log";
+import { listProfilesCmd, ProfileSummary } from "./tauri.ts";
+
+interface ProfileSwitcherProps {
+  onSelect: (path: string) => void;
+  onClose: () => void;
+}
+
+export const ProfileSwitcher: Component<ProfileSwitcherProps> = (props) => {
+  const [profiles, setProfiles] = createSignal<ProfileSummary[]>([]);
+  const [filter, setFilter] = createSignal("");
+  const filtered = createMemo(() =>
+    profiles().filter((p) => p.name.toLowerCase().includes(filter().toLowerCase()))
+  );
+
+  onMount(async () => {
+    try {
+      setProfiles(await listProfilesCmd());
+    } catch (error) {
+      await warn(`Frontend error: ${error}`);
+    }
+  });
+
+  const onKeyDown = (e: KeyboardEvent) => {
+    if (e.key === "Escape") {
+      e.preventDefault();
+      props.onClose();
+    } else if (e.key === "Enter" && filtered().length > 0) {
+      e.preventDefault();
+      props.onSelect(filtered()[0].path);
+    }
+  };
+
+  return (
+    <div class="flex flex-col font-mono text-sm border-b border-gray-700 pb-1">
+      <input
+        type="text"
+        class="text-white bg-gray-900 mx-1 my-1 border-gray-700 border focus:outline-none focus:border-gray-500 px-1 rounded"
+        placeholder="Profile"
+        value={filter()}
+        onInput={(e) => setFilter(e.currentTarget.value)}
+        onKeyDown={onKeyDown}
+        use:autofocus
+        autofocus
+        autocomplete="off"
+      />
+      <For each={filtered()}>
+        {(profile) => (
+          <div
+            class="flex mx-1 px-1 cursor-pointer hover:bg-gray-800 rounded"
+            onClick={() => props.onSelect(profile.path)}
+          >
+            <div class="flex-grow truncate">{profile.name}</div>
+            <div class="text-gray-500">{profile.stationCount}</div>
+          </div>
+        )}
+      </For>
+    </div>
+  );
+};
@@ -0,0 +1,35 @@
+import { AtisChangeSound } from "./tauri.ts";
+
+interface Tone {
+  frequency: number;
+  start: number;
+  duration: number;
+}
+
+const tones: Record<AtisChangeSound, Tone[]> = {
+  Chime: [
+    { frequency: 880, start: 0, duration: 0.25 },
+    { frequency: 660, start: 0.25, duration: 0.4 },
+  ],
+  Beep: [{ frequency: 1000, start: 0, duration: 0.2 }],
+};
+
+let context: AudioContext | undefined;
+
+function playAlertSound(sound: AtisChangeSound) {
+  context = context ?? new AudioContext();
+  const now = context.currentTime;
+
+  for (const tone of tones[sound]) {
+    const oscillator = context.createOscillator();
+    const gain = context.createGain();
+    oscillator.frequency.value = tone.frequency;
+    gain.gain.setValueAtTime(0.2, now + tone.start);
+    gain.gain.exponentialRampToValueAtTime(0.001, now + tone.start + tone.duration);
+    oscillator.connect(gain).connect(context.destination);
+    oscillator.start(now + tone.start);
+    oscillator.stop(now + tone.start + tone.duration);
+  }
+}
+
+export { playAlertSound };
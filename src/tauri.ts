@@ -56,25 +56,307 @@ interface Station {
   priority: number;
 }
 
+interface Wind {
+  direction?: number;
+  speed: number;
+  gust?: number;
+  unit: "KT" | "MPS" | "KMH";
+  variableFrom?: number;
+  variableTo?: number;
+}
+
+interface Visibility {
+  distance: number;
+  unit: "m" | "SM" | "ft";
+  modifier?: "lessThan" | "greaterThan";
+  noDirectionalVariation: boolean;
+}
+
+interface RunwayVisualRange {
+  runway: string;
+  range: number;
+  rangeModifier?: "lessThan" | "greaterThan";
+  variableRange?: number;
+  variableRangeModifier?: "lessThan" | "greaterThan";
+  unit: "m" | "SM" | "ft";
+  tendency?: "up" | "down" | "noChange";
+}
+
+interface WeatherGroup {
+  intensity: "light" | "moderate" | "heavy" | "vicinity";
+  descriptor?: string;
+  phenomena: string[];
+}
+
+interface DecodedCloudLayer {
+  cover: string;
+  base?: number;
+  cloudType?: string;
+}
+
+interface WeatherConditions {
+  wind?: Wind;
+  visibility?: Visibility;
+  weather: WeatherGroup[];
+  clouds: DecodedCloudLayer[];
+  verticalVisibility?: number;
+  cavok: boolean;
+  noSignificantWeather: boolean;
+}
+
+interface Trend {
+  kind: "NOSIG" | "BECMG" | "TEMPO";
+  from?: string;
+  until?: string;
+  at?: string;
+  conditions: WeatherConditions;
+}
+
+interface MetarReport extends WeatherConditions {
+  reportType?: "Metar" | "Speci";
+  station: string;
+  time?: { day: number; hour: number; minute: number };
+  auto: boolean;
+  corrected: boolean;
+  directionalVisibility: { meters: number; direction: string }[];
+  runwayVisualRanges: RunwayVisualRange[];
+  temperature?: number;
+  dewpoint?: number;
+  altimeter?: { unit: "inHg" | "hPa"; value: number };
+  trends: Trend[];
+  remarks?: string;
+  unparsed: string[];
+}
+
+type FlightCategory = "VFR" | "MVFR" | "IFR" | "LIFR";
+
 interface FetchMetarResponse {
   metar: MetarDto;
   windString: string;
   altimeter: { inHg: number; hPa: number };
+  decoded?: MetarReport;
+  flightCategory?: FlightCategory;
+}
+
+interface MetarsUpdatedPayload {
+  metars: FetchMetarResponse[];
+  missing: string[];
+}
+
+interface AltimeterChangedPayload {
+  station: string;
+  previousObsTime: string;
+  obsTime: string;
+  previousInHg: number;
+  previousHpa: number;
+  inHg: number;
+  hpa: number;
+  deltaInHg: number;
+  deltaHpa: number;
+  trendHpa?: number;
+}
+
+interface TafDto {
+  icaoId: string;
+  issueTime?: string;
+  validTimeFrom: string;
+  validTimeTo: string;
+  rawTAF: string;
+  mostRecent?: number;
+  remarks?: string;
+  lat: number;
+  lon: number;
+  elev: number;
+  name: string;
+}
+
+interface TafPeriod {
+  kind: "BASE" | "FM" | "BECMG" | "TEMPO" | "PROB";
+  probability?: number;
+  from?: string;
+  to?: string;
+  conditions: WeatherConditions;
+}
+
+interface TafReport {
+  station: string;
+  amended: boolean;
+  corrected: boolean;
+  issued?: string;
+  validFrom?: string;
+  validTo?: string;
+  periods: TafPeriod[];
+  remarks?: string;
+  unparsed: string[];
+}
+
+interface TafForecast {
+  prevailing: WeatherConditions;
+  kind: TafPeriod["kind"];
+  from?: string;
+  to?: string;
+  until?: string;
+  temporary: TafPeriod[];
+}
+
+interface FetchTafResponse {
+  taf: TafDto;
+  decoded?: TafReport;
+  current?: TafForecast;
+  currentSummary?: string;
+}
+
+interface RunwayWindComponents {
+  runway: string;
+  trueHeading: number;
+  magneticHeading: number;
+  lengthFt?: number;
+  headwind?: number;
+  crosswind?: number;
+  gustHeadwind?: number;
+  gustCrosswind?: number;
+}
+
+interface RunwayWindsResponse {
+  station: string;
+  windString: string;
+  magneticVariation?: number;
+  runways: RunwayWindComponents[];
+  favouredRunway?: string;
+}
+
+interface AtisInfo {
+  letter?: string;
+  arrivalRunways: string[];
+  departureRunways: string[];
+  approaches: string[];
+  altimeter?: { unit: "inHg" | "hPa"; value: number };
+  wind?: Wind;
+  observationTime?: { day?: number; hour: number; minute: number };
+  transitionLevel?: number;
+  notams: string[];
 }
 
 interface FetchAtisResponse {
   letter: string;
   texts: string[];
+  decoded: AtisInfo[];
+  runwaySummary?: string;
+  dataAgeSecs?: number;
+}
+
+type Discrepancy =
+  | {
+      kind: "altimeter";
+      atis: { unit: "inHg" | "hPa"; value: number };
+      metar: { unit: "inHg" | "hPa"; value: number };
+    }
+  | { kind: "wind"; atis: string; metar: string }
+  | { kind: "observationTime"; atis: string; metar: string };
+
+interface StationConsistency {
+  station: string;
+  atisLetter: string;
+  metarObsTime: string;
+  discrepancies: Discrepancy[];
+}
+
+type ControllerPosition = "Delivery" | "Ground" | "Tower" | "Approach" | "Center";
+
+interface OnlineController {
+  callsign: string;
+  name: string;
+  position: ControllerPosition;
+  frequency: string;
+  logonTime?: string;
+}
+
+interface InboundFlight {
+  callsign: string;
+  aircraft: string;
+  distanceNm: number;
+  groundspeed: number;
+  etaMinutes: number;
+}
+
+interface TrafficCounts {
+  station: string;
+  departures: number;
+  arrivals: InboundFlight[];
+  prefiles: number;
+}
+
+type ClientStatus =
+  | { status: "uninitialized" }
+  | { status: "connected" }
+  | { status: "reconnecting"; attempts: number; retryInSecs: number; lastError: string };
+
+interface ClientStatusResponse {
+  awc: ClientStatus;
+  vatsim: ClientStatus;
 }
 
 interface Profile {
+  version?: number;
   name: string;
-  stations: string[];
+  stations: StationEntry[];
   showInput: boolean;
   showTitlebar: boolean;
   window?: Window;
   units: "inHg" | "hPa";
   hideAirportIfMissingAtis: boolean;
+  atisChangeAlert: AtisChangeAlert;
+  atisChangeSound: AtisChangeSound;
+  network: AtisNetwork;
+}
+
+interface StationEntry {
+  id: string;
+  alias?: string;
+  units?: "inHg" | "hPa";
+  atisCallsignRules?: AtisCallsignRules;
+  alerts?: StationAlerts;
+  hidden?: boolean;
+  pinned?: boolean;
+}
+
+interface ProfileSummary {
+  name: string;
+  path: string;
+  stationCount: number;
+  modified?: string;
+}
+
+interface ImportProfileResponse {
+  name: string;
+  profile: Profile;
+  unresolved: string[];
+}
+
+interface StationAlerts {
+  atisChange?: AtisChangeAlert;
+  minAltimeterChangeHpa?: number;
+}
+
+type AtisNetwork = "Vatsim" | "Ivao";
+
+interface AtisCallsignRules {
+  aliases: string[];
+  patterns: string[];
+  arrivalSuffixes: string[];
+  departureSuffixes: string[];
+}
+
+type AtisChangeAlert = "None" | "Sound" | "Notification" | "SoundAndNotification";
+
+type AtisChangeSound = "Chime" | "Beep";
+
+interface AtisChangedPayload {
+  station: string;
+  previousLetter: string;
+  letter: string;
+  added: string[];
+  removed: string[];
 }
 
 interface Window {
@@ -84,11 +366,32 @@ interface Window {
   scaleFactor: number;
 }
 
+interface CategoryMinimums {
+  ceilingFt: number;
+  visibilityM: number;
+}
+
+interface IcaoCategoryLevels {
+  vfr: CategoryMinimums;
+  mvfr: CategoryMinimums;
+  ifr: CategoryMinimums;
+}
+
 interface Settings {
   loadMostRecentProfileOnOpen: boolean;
   mostRecentProfile?: string;
+  recentProfiles: string[];
   alwaysOnTop: boolean;
   autoResize: boolean;
+  flightCategoryRules: "Faa" | "Icao";
+  icaoFlightCategoryLevels: IcaoCategoryLevels;
+  weatherProvider: "Awc" | "NoaaTgftp" | "VatsimMetar";
+  weatherBaseUrl?: string;
+  notifyAltimeterChanges: boolean;
+  controllerPrefixes: Record<string, string[]>;
+  apiEnabled: boolean;
+  apiBindAddress?: string;
+  apiPort?: number;
 }
 
 interface InitialSettingsLoad {
@@ -99,13 +402,59 @@ interface InitialSettingsLoad {
 const updateMetarCmd = (id: string): Promise<FetchMetarResponse> =>
   invoke("fetch_metar", { id: id });
 
+const getMetarHistoryCmd = (id: string, hours: number): Promise<FetchMetarResponse[]> =>
+  invoke("get_metar_history", { id: id, hours: hours });
+
+const subscribeMetarCmd = (id: string, minAltimeterChangeHpa?: number): Promise<void> =>
+  invoke("subscribe_metar", { id: id, minAltimeterChangeHpa: minAltimeterChangeHpa });
+
+const unsubscribeMetarCmd = (id: string): Promise<void> => invoke("unsubscribe_metar", { id: id });
+
+const decodeMetarCmd = (raw: string): Promise<MetarReport> => invoke("decode_metar", { raw: raw });
+
+const fetchTafCmd = (id: string): Promise<FetchTafResponse> => invoke("fetch_taf", { id: id });
+
 const lookupStationCmd = (id: string): Promise<Station> => invoke("lookup_station", { id: id });
 
-const updateAtisCmd = (id: string): Promise<FetchAtisResponse> =>
-  invoke("get_atis", { icaoId: id });
+const getRunwayWindsCmd = (id: string): Promise<RunwayWindsResponse> =>
+  invoke("get_runway_winds", { id: id });
+
+const updateAtisCmd = (
+  id: string,
+  network?: AtisNetwork,
+  rules?: AtisCallsignRules
+): Promise<FetchAtisResponse> => invoke("get_atis", { icaoId: id, network: network, rules: rules });
+
+const checkAtisConsistencyCmd = (
+  ids: string[],
+  network?: AtisNetwork,
+  rules?: Record<string, AtisCallsignRules>
+): Promise<StationConsistency[]> =>
+  invoke("check_atis_consistency", { ids: ids, network: network, rules: rules });
+
+const getControllersCmd = (id: string): Promise<OnlineController[]> =>
+  invoke("get_controllers", { id: id });
+
+const getTrafficCountsCmd = (id: string): Promise<TrafficCounts> =>
+  invoke("get_traffic_counts", { id: id });
 
 const loadProfileCmd = (): Promise<Profile> => invoke("load_profile", {});
 
+const listProfilesCmd = (): Promise<ProfileSummary[]> => invoke("list_profiles", {});
+
+const loadLibraryProfileCmd = (path: string): Promise<Profile> =>
+  invoke("load_library_profile", { path: path });
+
+const renameProfileCmd = (name: string, newName: string): Promise<void> =>
+  invoke("rename_profile", { name: name, newName: newName });
+
+const duplicateProfileCmd = (name: string, newName: string): Promise<void> =>
+  invoke("duplicate_profile", { name: name, newName: newName });
+
+const deleteProfileCmd = (name: string): Promise<void> => invoke("delete_profile", { name: name });
+
+const importProfileCmd = (): Promise<ImportProfileResponse> => invoke("import_profile", {});
+
 const saveProfileCmd = (profile: Profile): Promise<void> =>
   invoke("save_current_profile", { profile: profile });
 
@@ -122,16 +471,67 @@ const saveSettingsCmd = (settings?: Settings): Promise<void> =>
 
 const initializeDatafeedCmd = (): Promise<void> => invoke("initialize_datafeed", {});
 
+const getClientStatusCmd = (): Promise<ClientStatusResponse> => invoke("get_client_status", {});
+
 export {
   updateMetarCmd,
+  getMetarHistoryCmd,
+  subscribeMetarCmd,
+  unsubscribeMetarCmd,
+  decodeMetarCmd,
+  fetchTafCmd,
   lookupStationCmd,
+  getRunwayWindsCmd,
   updateAtisCmd,
+  checkAtisConsistencyCmd,
+  getControllersCmd,
+  getTrafficCountsCmd,
   loadProfileCmd,
+  listProfilesCmd,
+  loadLibraryProfileCmd,
+  renameProfileCmd,
+  duplicateProfileCmd,
+  deleteProfileCmd,
+  importProfileCmd,
   saveProfileCmd,
   saveProfileAsCmd,
   loadSettingsCmd,
   loadSettingsInitialCmd,
   saveSettingsCmd,
   initializeDatafeedCmd,
+  getClientStatusCmd,
+};
+export type {
+  CloudLayer,
+  MetarDto,
+  FetchMetarResponse,
+  MetarsUpdatedPayload,
+  AltimeterChangedPayload,
+  AtisChangedPayload,
+  AtisChangeAlert,
+  AtisChangeSound,
+  AtisNetwork,
+  AtisCallsignRules,
+  MetarReport,
+  WeatherConditions,
+  FlightCategory,
+  FetchTafResponse,
+  TafForecast,
+  RunwayWindsResponse,
+  Discrepancy,
+  StationConsistency,
+  ControllerPosition,
+  OnlineController,
+  InboundFlight,
+  TrafficCounts,
+  ClientStatusResponse,
+  Profile,
+  ProfileSummary,
+  ImportProfileResponse,
+  StationEntry,
+  StationAlerts,
+  CategoryMinimums,
+  IcaoCategoryLevels,
+  Settings,
+  InitialSettingsLoad,
 };
-export type { CloudLayer, MetarDto, Profile, Settings, InitialSettingsLoad };